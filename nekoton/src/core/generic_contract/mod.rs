@@ -48,15 +48,26 @@ impl GenericContract {
     pub fn estimate_fees(
         &self,
         signed_message: crate::crypto::JsSignedMessage,
-    ) -> Result<PromiseString, JsValue> {
+    ) -> Result<crate::core::models::PromiseFeeBreakdown, JsValue> {
         let inner = self.inner.clone();
         let message = crate::crypto::parse_signed_message(signed_message)?;
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
             let mut contract = inner.contract.lock().trust_me();
 
-            let res = contract.estimate_fees(&message.boc).await.handle_error()?;
-            Ok(JsValue::from(res.to_string()))
+            let balance = contract.contract_state().balance;
+            let transaction = contract
+                .execute_transaction_locally(
+                    &message.boc,
+                    nt::core::TransactionExecutionOptions {
+                        disable_signature_check: true,
+                        override_balance: None,
+                    },
+                )
+                .await
+                .handle_error()?;
+
+            crate::core::models::make_fee_breakdown(&transaction, balance).map(JsValue::from)
         })))
     }
 
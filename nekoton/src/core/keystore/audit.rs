@@ -0,0 +1,334 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+pub const KEYSTORE_AUDIT_LOG_STORAGE_KEY: &str = "__bindings__keystore_audit_log";
+pub const KEYSTORE_AUDIT_LOCK_NAME: &str = "__bindings__keystore_audit_lock";
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditOperation {
+    Sign,
+    SignData,
+    SignDataRaw,
+    SignTypedData,
+    SignOwnershipProof,
+    EncryptData,
+    DecryptData,
+    ExportKey,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    pub operation: AuditOperation,
+    pub public_key: String,
+    pub signer: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    /// Unix timestamp in milliseconds
+    pub timestamp: u64,
+}
+
+impl AuditRecord {
+    pub fn new(
+        operation: AuditOperation,
+        public_key: &ed25519_dalek::PublicKey,
+        signer: &str,
+        origin: Option<String>,
+    ) -> Self {
+        Self {
+            operation,
+            public_key: hex::encode(public_key.as_bytes()),
+            signer: signer.to_owned(),
+            message_hash: None,
+            destination: None,
+            value: None,
+            origin,
+            timestamp: nt_utils::now_ms_u64(),
+        }
+    }
+
+    pub fn with_message_hash(mut self, hash: &[u8]) -> Self {
+        self.message_hash = Some(hex::encode(hash));
+        self
+    }
+
+    pub fn with_destination(mut self, destination: Option<String>, value: Option<String>) -> Self {
+        self.destination = destination;
+        self.value = value;
+        self
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogEntry {
+    pub index: u64,
+    #[serde(flatten)]
+    pub record: AuditRecord,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditLogEntry {
+    fn compute_hash(index: u64, record: &AuditRecord, prev_hash: &str) -> Result<String> {
+        let record = serde_json::to_vec(record)?;
+        let hash = sha2::Sha256::new()
+            .chain(index.to_be_bytes())
+            .chain(prev_hash.as_bytes())
+            .chain(&record)
+            .finalize();
+        Ok(hex::encode(hash))
+    }
+}
+
+/// Append-only log of the keystore operations, where each entry contains the hash of the previous one.
+///
+/// Entries are stored in segments, so that an append only rewrites the last one.
+/// The head contains the number of entries and the hash of the last one, so that
+/// removed entries are detected. Only the latest segments are kept
+pub struct AuditLog {
+    storage: Arc<dyn nt::external::Storage>,
+    lock: futures::lock::Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn new(storage: Arc<dyn nt::external::Storage>) -> Self {
+        Self {
+            storage,
+            lock: Default::default(),
+        }
+    }
+
+    pub async fn append(&self, record: AuditRecord) -> Result<()> {
+        let _guard = self.lock.lock().await;
+
+        // Appends from all instances are serialized, so that the chain is never forked
+        let _shared = super::events::acquire_shared_lock(KEYSTORE_AUDIT_LOCK_NAME).await?;
+        self.append_locked(record).await
+    }
+
+    async fn append_locked(&self, record: AuditRecord) -> Result<()> {
+        let mut head = match self.load_stored().await? {
+            Some(StoredAuditLog::Head(head)) => head,
+            Some(StoredAuditLog::Legacy(entries)) => self.migrate(entries).await?,
+            None => AuditLogHead::default(),
+        };
+
+        let index = head.count;
+        let prev_hash = head.head_hash.clone();
+        let hash = AuditLogEntry::compute_hash(index, &record, &prev_hash)?;
+
+        let segment = index / SEGMENT_SIZE;
+        let mut entries = match index % SEGMENT_SIZE {
+            0 => Vec::new(),
+            // Entries which were written without the head update are dropped
+            offset => {
+                let mut entries = self.load_segment(segment).await?;
+                entries.truncate(offset as usize);
+                entries
+            }
+        };
+        entries.push(AuditLogEntry {
+            index,
+            record,
+            prev_hash,
+            hash: hash.clone(),
+        });
+        self.store_segment(segment, &entries).await?;
+
+        head.count = index + 1;
+        head.head_hash = hash;
+
+        let first_segment = head.first_index / SEGMENT_SIZE;
+        if segment - first_segment < MAX_SEGMENTS {
+            return self.store_head(&head).await;
+        }
+
+        let first_entry = self
+            .load_segment(first_segment + 1)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(AuditLogError::SegmentNotFound)?;
+        head.first_index = first_entry.index;
+        head.first_prev_hash = first_entry.prev_hash;
+        self.store_head(&head).await?;
+
+        self.storage
+            .remove(&segment_storage_key(first_segment))
+            .await
+    }
+
+    pub async fn load(&self) -> Result<AuditLogSnapshot> {
+        let head = match self.load_stored().await? {
+            Some(StoredAuditLog::Head(head)) => head,
+            Some(StoredAuditLog::Legacy(entries)) => {
+                return Ok(AuditLogSnapshot {
+                    head: AuditLogHead {
+                        count: entries.len() as u64,
+                        head_hash: entries
+                            .last()
+                            .map(|entry| entry.hash.clone())
+                            .unwrap_or_else(empty_hash),
+                        ..Default::default()
+                    },
+                    entries,
+                })
+            }
+            None => AuditLogHead::default(),
+        };
+
+        let mut entries = Vec::new();
+        if head.count > head.first_index {
+            let first_segment = head.first_index / SEGMENT_SIZE;
+            let last_segment = (head.count - 1) / SEGMENT_SIZE;
+            for segment in first_segment..=last_segment {
+                entries.extend(self.load_segment(segment).await?);
+            }
+        }
+        entries.truncate((head.count - head.first_index) as usize);
+
+        Ok(AuditLogSnapshot { head, entries })
+    }
+
+    /// Moves entries from the single array, used by the previous versions
+    async fn migrate(&self, entries: Vec<AuditLogEntry>) -> Result<AuditLogHead> {
+        for (segment, chunk) in entries.chunks(SEGMENT_SIZE as usize).enumerate() {
+            self.store_segment(segment as u64, chunk).await?;
+        }
+
+        let head = AuditLogHead {
+            count: entries.len() as u64,
+            head_hash: entries
+                .last()
+                .map(|entry| entry.hash.clone())
+                .unwrap_or_else(empty_hash),
+            ..Default::default()
+        };
+        self.store_head(&head).await?;
+        Ok(head)
+    }
+
+    async fn load_stored(&self) -> Result<Option<StoredAuditLog>> {
+        match self.storage.get(KEYSTORE_AUDIT_LOG_STORAGE_KEY).await? {
+            Some(data) => Ok(Some(serde_json::from_str(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn store_head(&self, head: &AuditLogHead) -> Result<()> {
+        self.storage
+            .set(
+                KEYSTORE_AUDIT_LOG_STORAGE_KEY,
+                &serde_json::to_string(head)?,
+            )
+            .await
+    }
+
+    async fn load_segment(&self, segment: u64) -> Result<Vec<AuditLogEntry>> {
+        match self.storage.get(&segment_storage_key(segment)).await? {
+            Some(data) => Ok(serde_json::from_str(&data)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn store_segment(&self, segment: u64, entries: &[AuditLogEntry]) -> Result<()> {
+        self.storage
+            .set(
+                &segment_storage_key(segment),
+                &serde_json::to_string(entries)?,
+            )
+            .await
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogHead {
+    /// Total number of appended entries
+    pub count: u64,
+    /// Hash of the last entry
+    pub head_hash: String,
+    /// Index of the first kept entry
+    pub first_index: u64,
+    /// Hash of the entry before the first kept one
+    pub first_prev_hash: String,
+}
+
+impl Default for AuditLogHead {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            head_hash: empty_hash(),
+            first_index: 0,
+            first_prev_hash: empty_hash(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredAuditLog {
+    Head(AuditLogHead),
+    Legacy(Vec<AuditLogEntry>),
+}
+
+pub struct AuditLogSnapshot {
+    pub head: AuditLogHead,
+    pub entries: Vec<AuditLogEntry>,
+}
+
+impl AuditLogSnapshot {
+    /// Checks that no entries were modified, removed or reordered.
+    /// Returns the index of the first invalid entry
+    pub fn find_invalid_entry(&self) -> Option<u64> {
+        let mut prev_hash = self.head.first_prev_hash.clone();
+        for (offset, entry) in self.entries.iter().enumerate() {
+            let index = self.head.first_index + offset as u64;
+            let valid = entry.index == index
+                && entry.prev_hash == prev_hash
+                && matches!(
+                    AuditLogEntry::compute_hash(index, &entry.record, &entry.prev_hash),
+                    Ok(hash) if hash == entry.hash
+                );
+            if !valid {
+                return Some(index);
+            }
+            prev_hash = entry.hash.clone();
+        }
+
+        // Trailing entries were removed
+        let end = self.head.first_index + self.entries.len() as u64;
+        if end != self.head.count || prev_hash != self.head.head_hash {
+            return Some(end);
+        }
+        None
+    }
+}
+
+fn segment_storage_key(segment: u64) -> String {
+    format!("{}_{}", KEYSTORE_AUDIT_LOG_STORAGE_KEY, segment)
+}
+
+fn empty_hash() -> String {
+    hex::encode([0u8; 32])
+}
+
+const SEGMENT_SIZE: u64 = 64;
+const MAX_SEGMENTS: u64 = 32;
+
+#[derive(thiserror::Error, Debug)]
+pub enum AuditLogError {
+    #[error("Audit log segment not found")]
+    SegmentNotFound,
+}
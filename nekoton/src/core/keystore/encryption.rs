@@ -0,0 +1,374 @@
+use std::str::FromStr;
+
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::{Aead, KeyInit};
+use rand::Rng;
+use zeroize::{Zeroize, Zeroizing};
+
+const BOX_NONCE_LEN: usize = 24;
+const CONTENT_KEY_LEN: usize = 32;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EncryptionAlgorithm {
+    /// Encrypted for each recipient by the core keystore
+    ChaCha20Poly1305,
+    /// NaCl `crypto_box` with the ed25519 keys converted to X25519,
+    /// the same as libsodium `crypto_sign_ed25519_*_to_curve25519` does
+    XSalsa20Poly1305,
+    /// Content is encrypted once with a random key,
+    /// which is sealed for each recipient with NaCl `crypto_box`
+    Aes256Gcm,
+}
+
+impl EncryptionAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ChaCha20Poly1305 => "ChaCha20Poly1305",
+            Self::XSalsa20Poly1305 => "XSalsa20Poly1305",
+            Self::Aes256Gcm => "AES256GCM",
+        }
+    }
+
+    /// Whether the data is encrypted for each recipient by the core keystore
+    pub fn is_native(&self) -> bool {
+        matches!(self, Self::ChaCha20Poly1305)
+    }
+}
+
+impl FromStr for EncryptionAlgorithm {
+    type Err = EncryptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "ChaCha20Poly1305" => Self::ChaCha20Poly1305,
+            "XSalsa20Poly1305" => Self::XSalsa20Poly1305,
+            "AES256GCM" => Self::Aes256Gcm,
+            _ => return Err(EncryptionError::UnknownAlgorithm),
+        })
+    }
+}
+
+/// Data encrypted in the bindings, because the core keystore only supports `ChaCha20Poly1305`
+pub struct SealedData {
+    pub algorithm: EncryptionAlgorithm,
+    pub source_public_key: ed25519_dalek::PublicKey,
+    pub recipient_public_key: ed25519_dalek::PublicKey,
+    pub data: Vec<u8>,
+    pub nonce: Vec<u8>,
+    /// Content key sealed for the recipient, only for `AES256GCM`
+    pub wrapped_key: Option<WrappedKey>,
+}
+
+pub struct WrappedKey {
+    pub data: Vec<u8>,
+    pub nonce: Vec<u8>,
+}
+
+/// Encrypts data for each recipient.
+///
+/// With `AES256GCM` the content is encrypted only once and shared by all recipients
+pub fn seal_data(
+    algorithm: EncryptionAlgorithm,
+    keypair: &ed25519_dalek::Keypair,
+    recipients: &[ed25519_dalek::PublicKey],
+    data: &[u8],
+) -> Result<Vec<SealedData>, EncryptionError> {
+    let mut rng = rand::thread_rng();
+    let secret_key = x25519_secret_key(&keypair.secret);
+
+    let content = match algorithm {
+        EncryptionAlgorithm::XSalsa20Poly1305 => None,
+        EncryptionAlgorithm::Aes256Gcm => {
+            let key = Zeroizing::new(rng.gen::<[u8; CONTENT_KEY_LEN]>());
+            let nonce = rng.gen::<[u8; 12]>();
+            let data = aes_gcm::Aes256Gcm::new(GenericArray::from_slice(key.as_ref()))
+                .encrypt(GenericArray::from_slice(&nonce), data)
+                .map_err(|_| EncryptionError::FailedToEncryptData)?;
+            Some((key, data, nonce))
+        }
+        EncryptionAlgorithm::ChaCha20Poly1305 => return Err(EncryptionError::UnsupportedAlgorithm),
+    };
+
+    recipients
+        .iter()
+        .map(|recipient_public_key| {
+            let public_key = x25519_public_key(recipient_public_key)?;
+            let box_nonce = rng.gen::<[u8; BOX_NONCE_LEN]>();
+
+            let (data, nonce, wrapped_key) = match &content {
+                None => (
+                    crypto_box(&secret_key, &public_key, &box_nonce, data)?,
+                    box_nonce.to_vec(),
+                    None,
+                ),
+                Some((key, data, nonce)) => (
+                    data.clone(),
+                    nonce.to_vec(),
+                    Some(WrappedKey {
+                        data: crypto_box(&secret_key, &public_key, &box_nonce, key.as_ref())?,
+                        nonce: box_nonce.to_vec(),
+                    }),
+                ),
+            };
+
+            Ok(SealedData {
+                algorithm,
+                source_public_key: keypair.public,
+                recipient_public_key: *recipient_public_key,
+                data,
+                nonce,
+                wrapped_key,
+            })
+        })
+        .collect()
+}
+
+pub fn open_data(
+    keypair: &ed25519_dalek::Keypair,
+    data: &SealedData,
+) -> Result<Vec<u8>, EncryptionError> {
+    if data.recipient_public_key != keypair.public {
+        return Err(EncryptionError::RecipientNotFound);
+    }
+
+    let secret_key = x25519_secret_key(&keypair.secret);
+    let public_key = x25519_public_key(&data.source_public_key)?;
+
+    match (data.algorithm, &data.wrapped_key) {
+        (EncryptionAlgorithm::XSalsa20Poly1305, None) => {
+            crypto_box_open(&secret_key, &public_key, &data.nonce, &data.data)
+        }
+        (EncryptionAlgorithm::Aes256Gcm, Some(wrapped_key)) => {
+            let key = Zeroizing::new(crypto_box_open(
+                &secret_key,
+                &public_key,
+                &wrapped_key.nonce,
+                &wrapped_key.data,
+            )?);
+            if key.len() != CONTENT_KEY_LEN {
+                return Err(EncryptionError::InvalidContentKey);
+            }
+            if data.nonce.len() != 12 {
+                return Err(EncryptionError::InvalidNonce);
+            }
+
+            aes_gcm::Aes256Gcm::new(GenericArray::from_slice(&key))
+                .decrypt(GenericArray::from_slice(&data.nonce), data.data.as_slice())
+                .map_err(|_| EncryptionError::FailedToDecryptData)
+        }
+        (EncryptionAlgorithm::ChaCha20Poly1305, _) => Err(EncryptionError::UnsupportedAlgorithm),
+        _ => Err(EncryptionError::InvalidContentKey),
+    }
+}
+
+/// X25519 shared secret of the ed25519 keys, the same as libsodium computes
+/// for the keys converted with `crypto_sign_ed25519_*_to_curve25519`
+pub fn compute_shared_secret(
+    secret_key: &ed25519_dalek::SecretKey,
+    public_key: &ed25519_dalek::PublicKey,
+) -> Result<Zeroizing<[u8; 32]>, EncryptionError> {
+    Ok(x25519(
+        &x25519_secret_key(secret_key),
+        &x25519_public_key(public_key)?,
+    ))
+}
+
+/// `crypto_sign_ed25519_sk_to_curve25519`
+fn x25519_secret_key(secret_key: &ed25519_dalek::SecretKey) -> Zeroizing<[u8; 32]> {
+    use sha2::Digest;
+
+    let mut hash = sha2::Sha512::digest(secret_key.as_bytes());
+    let mut scalar = Zeroizing::new([0u8; 32]);
+    scalar.copy_from_slice(&hash[..32]);
+    hash.as_mut_slice().zeroize();
+    clamp_scalar(&mut scalar);
+    scalar
+}
+
+/// `crypto_sign_ed25519_pk_to_curve25519`
+fn x25519_public_key(public_key: &ed25519_dalek::PublicKey) -> Result<[u8; 32], EncryptionError> {
+    use curve25519_dalek_ng::edwards::CompressedEdwardsY;
+
+    CompressedEdwardsY(public_key.to_bytes())
+        .decompress()
+        .map(|point| point.to_montgomery().to_bytes())
+        .ok_or(EncryptionError::InvalidPublicKey)
+}
+
+fn x25519(secret_key: &[u8; 32], public_key: &[u8; 32]) -> Zeroizing<[u8; 32]> {
+    use curve25519_dalek_ng::montgomery::MontgomeryPoint;
+    use curve25519_dalek_ng::scalar::Scalar;
+
+    let mut scalar = Zeroizing::new(*secret_key);
+    clamp_scalar(&mut scalar);
+    let mut scalar = Scalar::from_bits(*scalar);
+    let shared_secret = (scalar * MontgomeryPoint(*public_key)).to_bytes();
+    scalar.zeroize();
+    Zeroizing::new(shared_secret)
+}
+
+fn clamp_scalar(scalar: &mut [u8; 32]) {
+    scalar[0] &= 248;
+    scalar[31] &= 127;
+    scalar[31] |= 64;
+}
+
+/// `crypto_box_beforenm`
+fn crypto_box_key(
+    secret_key: &[u8; 32],
+    public_key: &[u8; 32],
+) -> crypto_secretbox::XSalsa20Poly1305 {
+    let shared_secret = x25519(secret_key, public_key);
+    let mut key = salsa20::hsalsa::<salsa20::cipher::consts::U10>(
+        GenericArray::from_slice(shared_secret.as_ref()),
+        &GenericArray::default(),
+    );
+    let cipher = crypto_secretbox::XSalsa20Poly1305::new(&key);
+    key.as_mut_slice().zeroize();
+    cipher
+}
+
+/// `crypto_box_easy`, the tag is prepended to the ciphertext
+fn crypto_box(
+    secret_key: &[u8; 32],
+    public_key: &[u8; 32],
+    nonce: &[u8; BOX_NONCE_LEN],
+    data: &[u8],
+) -> Result<Vec<u8>, EncryptionError> {
+    crypto_box_key(secret_key, public_key)
+        .encrypt(GenericArray::from_slice(nonce), data)
+        .map_err(|_| EncryptionError::FailedToEncryptData)
+}
+
+/// `crypto_box_open_easy`
+fn crypto_box_open(
+    secret_key: &[u8; 32],
+    public_key: &[u8; 32],
+    nonce: &[u8],
+    data: &[u8],
+) -> Result<Vec<u8>, EncryptionError> {
+    if nonce.len() != BOX_NONCE_LEN {
+        return Err(EncryptionError::InvalidNonce);
+    }
+
+    crypto_box_key(secret_key, public_key)
+        .decrypt(GenericArray::from_slice(nonce), data)
+        .map_err(|_| EncryptionError::FailedToDecryptData)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum EncryptionError {
+    #[error("Unknown encryption algorithm")]
+    UnknownAlgorithm,
+    #[error("Unsupported encryption algorithm")]
+    UnsupportedAlgorithm,
+    #[error("Invalid content key")]
+    InvalidContentKey,
+    #[error("Invalid nonce")]
+    InvalidNonce,
+    #[error("Invalid public key")]
+    InvalidPublicKey,
+    #[error("Data is not encrypted for this key")]
+    RecipientNotFound,
+    #[error("Failed to encrypt data")]
+    FailedToEncryptData,
+    #[error("Failed to decrypt data")]
+    FailedToDecryptData,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use super::*;
+
+    fn decode<const N: usize>(data: &str) -> [u8; N] {
+        hex::decode(data).unwrap().try_into().unwrap()
+    }
+
+    /// Vectors from NaCl `tests/box.c`
+    #[test]
+    fn crypto_box_known_answer() {
+        let alice_secret_key =
+            decode("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob_public_key =
+            decode("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f");
+        let bob_secret_key =
+            decode("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+        let alice_public_key =
+            decode("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a");
+        let nonce = decode("69696ee955b62b73cd62bda875fc73d68219e0036b7a0b37");
+        let message = hex::decode(
+            "be075fc53c81f2d5cf141316ebeb0c7b5228c52a4c62cbd44b66849b64244ffc\
+             e5ecbaaf33bd751a1ac728d45e6c61296cdc3c01233561f41db66cce314adb31\
+             0e3be8250c46f06dceea3a7fa1348057e2f6556ad6b1318a024a838f21af1fde\
+             048977eb48f59ffd4924ca1c60902e52f0a089bc76897040e082f93776384864\
+             5e0705",
+        )
+        .unwrap();
+        let expected = hex::decode(
+            "f3ffc7703f9400e52a7dfb4b3d3305d98e993b9f48681273c29650ba32fc76ce\
+             48332ea7164d96a4476fb8c531a1186ac0dfc17c98dce87b4da7f011ec48c972\
+             71d2c20f9b928fe2270d6fb863d51738b48eeee314a7cc8ab932164548e526ae\
+             90224368517acfeabd6bb3732bc0e9da99832b61ca01b6de56244a9e88d5f9b3\
+             7973f622a43d14a6599b1f654cb45a74e355a5",
+        )
+        .unwrap();
+
+        let encrypted = crypto_box(&alice_secret_key, &bob_public_key, &nonce, &message).unwrap();
+        assert_eq!(encrypted, expected);
+
+        let decrypted =
+            crypto_box_open(&bob_secret_key, &alice_public_key, &nonce, &encrypted).unwrap();
+        assert_eq!(decrypted, message);
+    }
+
+    /// Vectors from libsodium `test/default/ed25519_convert.c`
+    #[test]
+    fn ed25519_keys_conversion() {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&decode::<32>(
+            "421151a459faeade3d247115f94aedae42318124095afabe4d1451a559faedee",
+        ))
+        .unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+
+        assert_eq!(
+            hex::encode(x25519_public_key(&public).unwrap()),
+            "f1814f0e8ff1043d8a44d25babff3cedcae6c22c3edaa48f857ae70de2baae50"
+        );
+        assert_eq!(
+            hex::encode(x25519_secret_key(&secret)),
+            "8052030376d47112be7f73ed7a019293dd12ad910b654455798b4667d73de166"
+        );
+    }
+
+    #[test]
+    fn sealed_data_round_trip() {
+        let mut rng = rand::thread_rng();
+        let keypair = |rng: &mut rand::rngs::ThreadRng| {
+            let secret = ed25519_dalek::SecretKey::from_bytes(&rng.gen::<[u8; 32]>()).unwrap();
+            let public = ed25519_dalek::PublicKey::from(&secret);
+            ed25519_dalek::Keypair { secret, public }
+        };
+
+        let sender = keypair(&mut rng);
+        let recipients = [keypair(&mut rng), keypair(&mut rng)];
+        let public_keys = recipients.iter().map(|key| key.public).collect::<Vec<_>>();
+
+        for algorithm in [
+            EncryptionAlgorithm::XSalsa20Poly1305,
+            EncryptionAlgorithm::Aes256Gcm,
+        ] {
+            let sealed = seal_data(algorithm, &sender, &public_keys, b"hello").unwrap();
+            assert_eq!(sealed.len(), recipients.len());
+            if algorithm == EncryptionAlgorithm::Aes256Gcm {
+                assert_eq!(sealed[0].data, sealed[1].data);
+            }
+
+            for (recipient, data) in recipients.iter().zip(&sealed) {
+                assert_eq!(open_data(recipient, data).unwrap(), b"hello");
+            }
+            assert!(open_data(&recipients[1], &sealed[0]).is_err());
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use nt_utils::TrustMe;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use super::KeyStoreEntry;
+
+pub const KEYSTORE_REVISION_STORAGE_KEY: &str = "__bindings__keystore_revision";
+pub const KEYSTORE_LOCK_NAME: &str = "__bindings__keystore_lock";
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = "KeyStoreEventHandler")]
+    pub type KeyStoreEventHandlerImpl;
+
+    #[wasm_bindgen(method, js_name = "onKeyAdded")]
+    pub fn on_key_added(this: &KeyStoreEventHandlerImpl, entry: KeyStoreEntry);
+
+    #[wasm_bindgen(method, js_name = "onKeyRemoved")]
+    pub fn on_key_removed(this: &KeyStoreEventHandlerImpl, entry: KeyStoreEntry);
+
+    #[wasm_bindgen(method, js_name = "onKeyRenamed")]
+    pub fn on_key_renamed(this: &KeyStoreEventHandlerImpl, entry: KeyStoreEntry);
+
+    /// Passwords are cached by the `masterKey` of the entry,
+    /// so derived keys share the state of their master key
+    #[wasm_bindgen(method, js_name = "onPasswordCacheChanged")]
+    pub fn on_password_cache_changed(
+        this: &KeyStoreEventHandlerImpl,
+        master_key: &str,
+        cached: bool,
+    );
+}
+
+unsafe impl Send for KeyStoreEventHandlerImpl {}
+
+unsafe impl Sync for KeyStoreEventHandlerImpl {}
+
+/// Storage revision of the keystore, used to detect updates from other instances
+pub struct KeyStoreRevision {
+    storage: Arc<dyn nt::external::Storage>,
+    known: Mutex<u64>,
+    lock: futures::lock::Mutex<()>,
+}
+
+impl KeyStoreRevision {
+    pub async fn load(storage: Arc<dyn nt::external::Storage>) -> Result<Self> {
+        let known = load_revision(storage.as_ref()).await?;
+        Ok(Self {
+            storage,
+            known: Mutex::new(known),
+            lock: Default::default(),
+        })
+    }
+
+    /// Serializes storage writes of all instances. Must be held for every
+    /// read-modify-write of the keystore data, including metadata
+    pub async fn lock(&self) -> Result<KeyStoreLock<'_>> {
+        let local = self.lock.lock().await;
+        let shared = acquire_shared_lock(KEYSTORE_LOCK_NAME).await?;
+        Ok(KeyStoreLock {
+            _local: local,
+            _shared: shared,
+        })
+    }
+
+    /// Starts an update of the keystore entries.
+    ///
+    /// The revision is checked while the lock is held and is bumped on [`RevisionGuard::commit`]
+    pub async fn begin(&self) -> Result<RevisionGuard<'_>> {
+        let lock = self.lock().await?;
+        self.check().await?;
+        Ok(RevisionGuard {
+            revision: self,
+            lock,
+        })
+    }
+
+    pub async fn sync(&self) -> Result<()> {
+        let revision = load_revision(self.storage.as_ref()).await?;
+        *self.known.lock().trust_me() = revision;
+        Ok(())
+    }
+
+    /// Fails if the keystore was modified by another instance since the last sync
+    async fn check(&self) -> Result<()> {
+        let revision = load_revision(self.storage.as_ref()).await?;
+        let known = *self.known.lock().trust_me();
+        if revision != known {
+            return Err(KeyStoreRevisionError::Conflict {
+                expected: known,
+                found: revision,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    async fn bump(&self) -> Result<()> {
+        let revision = *self.known.lock().trust_me() + 1;
+        self.storage
+            .set(KEYSTORE_REVISION_STORAGE_KEY, &revision.to_string())
+            .await?;
+        *self.known.lock().trust_me() = revision;
+        Ok(())
+    }
+}
+
+/// Exclusive access to the keystore storage, released on drop
+pub struct KeyStoreLock<'a> {
+    _local: futures::lock::MutexGuard<'a, ()>,
+    _shared: SharedLock,
+}
+
+/// Holds the keystore lock until the update is committed or dropped
+pub struct RevisionGuard<'a> {
+    revision: &'a KeyStoreRevision,
+    lock: KeyStoreLock<'a>,
+}
+
+impl<'a> RevisionGuard<'a> {
+    pub fn lock(&self) -> &KeyStoreLock<'a> {
+        &self.lock
+    }
+
+    pub async fn commit(self) -> Result<()> {
+        self.revision.bump().await
+    }
+}
+
+/// Lock which is shared by all extension contexts of the same origin (Web Locks API).
+/// Unlike values in the storage, it is exclusive and is released when the context is closed
+pub(super) struct SharedLock {
+    release: js_sys::Function,
+}
+
+unsafe impl Send for SharedLock {}
+
+unsafe impl Sync for SharedLock {}
+
+impl Drop for SharedLock {
+    fn drop(&mut self) {
+        let _ = self.release.call0(&JsValue::undefined());
+    }
+}
+
+/// Waits until the lock with the specified name is acquired
+pub(super) async fn acquire_shared_lock(name: &str) -> Result<SharedLock> {
+    let locks = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("navigator"))
+        .and_then(|navigator| js_sys::Reflect::get(&navigator, &JsValue::from_str("locks")))
+        .ok()
+        .filter(|locks| !locks.is_undefined())
+        .ok_or(KeyStoreRevisionError::LocksNotSupported)?;
+    let request = js_sys::Reflect::get(&locks, &JsValue::from_str("request"))
+        .ok()
+        .and_then(|request| request.dyn_into::<js_sys::Function>().ok())
+        .ok_or(KeyStoreRevisionError::LocksNotSupported)?;
+
+    let (tx, rx) = futures::channel::oneshot::channel::<js_sys::Function>();
+
+    // The lock is held until the promise returned from the callback is resolved
+    let callback = Closure::once_into_js(move |_lock: JsValue| {
+        let mut tx = Some(tx);
+        js_sys::Promise::new(&mut |resolve, _| {
+            if let Some(tx) = tx.take() {
+                let _ = tx.send(resolve);
+            }
+        })
+    });
+    request
+        .call2(&locks, &JsValue::from_str(name), &callback)
+        .map_err(|_| KeyStoreRevisionError::LocksNotSupported)?;
+
+    let release = rx.await?;
+    Ok(SharedLock { release })
+}
+
+async fn load_revision(storage: &dyn nt::external::Storage) -> Result<u64> {
+    match storage.get(KEYSTORE_REVISION_STORAGE_KEY).await? {
+        Some(data) => Ok(data.parse()?),
+        None => Ok(0),
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum KeyStoreRevisionError {
+    #[error("Keystore was modified by another instance (expected revision {expected}, found {found}). Reload it first")]
+    Conflict { expected: u64, found: u64 },
+    #[error("Web Locks API is not supported")]
+    LocksNotSupported,
+}
@@ -0,0 +1,186 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Version of the password hardening scheme
+pub const KDF_VERSION: u8 = 1;
+
+const SALT_LEN: usize = 16;
+const HARDENED_PASSWORD_LEN: usize = 32;
+
+/// Argon2id parameters used to harden the key password before it is passed to the core signers
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfParams {
+    /// Memory size in KiB
+    pub memory_cost: u32,
+    /// Number of passes over the memory
+    pub time_cost: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_cost: 19456, // 19 MiB
+            time_cost: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    fn is_weaker_than(&self, other: &Self) -> bool {
+        self.memory_cost < other.memory_cost || self.time_cost < other.time_cost
+    }
+
+    fn argon2(&self) -> Result<argon2::Argon2<'static>, KdfError> {
+        let params = argon2::Params::new(
+            self.memory_cost,
+            self.time_cost,
+            self.parallelism,
+            Some(HARDENED_PASSWORD_LEN),
+        )
+        .map_err(|_| KdfError::InvalidParams)?;
+
+        Ok(argon2::Argon2::new(
+            argon2::Algorithm::Argon2id,
+            argon2::Version::V0x13,
+            params,
+        ))
+    }
+}
+
+/// KDF settings of the key password.
+///
+/// Keys without it were added before the passwords were hardened,
+/// and pass the password to the core signers as is
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyEncryption {
+    pub version: u8,
+    pub params: KdfParams,
+    /// Hex encoded random salt
+    pub salt: String,
+}
+
+impl KeyEncryption {
+    /// Creates settings with the new random salt
+    pub fn new(params: KdfParams) -> Result<Self, KdfError> {
+        params.argon2()?;
+
+        let salt = rand::thread_rng().gen::<[u8; SALT_LEN]>();
+        Ok(Self {
+            version: KDF_VERSION,
+            params,
+            salt: hex::encode(salt),
+        })
+    }
+
+    /// Returns the password which is used by the core signers
+    pub fn harden(&self, password: &str) -> Result<String, KdfError> {
+        self.derive_key(password.as_bytes()).map(hex::encode)
+    }
+
+    pub fn derive_key(&self, password: &[u8]) -> Result<[u8; HARDENED_PASSWORD_LEN], KdfError> {
+        if self.version != KDF_VERSION {
+            return Err(KdfError::UnsupportedVersion);
+        }
+        let salt = hex::decode(&self.salt).map_err(|_| KdfError::InvalidSalt)?;
+
+        let mut output = [0u8; HARDENED_PASSWORD_LEN];
+        self.params
+            .argon2()?
+            .hash_password_into(password, &salt, &mut output)
+            .map_err(|_| KdfError::FailedToHashPassword)?;
+
+        Ok(output)
+    }
+}
+
+/// Keys without hardened passwords or with weaker than default parameters should be upgraded
+pub fn is_legacy_encryption(encryption: Option<&KeyEncryption>) -> bool {
+    match encryption {
+        Some(encryption) => {
+            encryption.version < KDF_VERSION
+                || encryption.params.is_weaker_than(&KdfParams::default())
+        }
+        None => true,
+    }
+}
+
+/// Applies the key encryption to the password if there is one
+pub fn harden_password(
+    encryption: Option<&KeyEncryption>,
+    password: String,
+) -> Result<String, KdfError> {
+    match encryption {
+        Some(encryption) => encryption.harden(&password),
+        None => Ok(password),
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum KdfError {
+    #[error("Invalid KDF params")]
+    InvalidParams,
+    #[error("Unsupported KDF version")]
+    UnsupportedVersion,
+    #[error("Invalid KDF salt")]
+    InvalidSalt,
+    #[error("Failed to hash password")]
+    FailedToHashPassword,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fast_params() -> KdfParams {
+        KdfParams {
+            memory_cost: 64,
+            time_cost: 1,
+            parallelism: 1,
+        }
+    }
+
+    #[test]
+    fn hardened_password_depends_on_salt_and_params() {
+        let encryption = KeyEncryption::new(fast_params()).unwrap();
+        let hardened = encryption.harden("password").unwrap();
+        assert_eq!(hardened, encryption.harden("password").unwrap());
+        assert_ne!(hardened, encryption.harden("Password").unwrap());
+
+        let other_salt = KeyEncryption::new(fast_params()).unwrap();
+        assert_ne!(hardened, other_salt.harden("password").unwrap());
+
+        let other_params = KeyEncryption {
+            params: KdfParams {
+                time_cost: 2,
+                ..fast_params()
+            },
+            ..encryption.clone()
+        };
+        assert_ne!(hardened, other_params.harden("password").unwrap());
+    }
+
+    #[test]
+    fn legacy_encryption_is_reported() {
+        assert!(is_legacy_encryption(None));
+
+        let weak = KeyEncryption::new(fast_params()).unwrap();
+        assert!(is_legacy_encryption(Some(&weak)));
+
+        let default = KeyEncryption::new(KdfParams::default()).unwrap();
+        assert!(!is_legacy_encryption(Some(&default)));
+    }
+
+    #[test]
+    fn invalid_params_are_rejected() {
+        let params = KdfParams {
+            memory_cost: 0,
+            ..fast_params()
+        };
+        assert!(KeyEncryption::new(params).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use nt_utils::TrustMe;
+use serde::{Deserialize, Serialize};
+
+use super::events::KeyStoreLock;
+use super::password_cache::PasswordCachePolicy;
+use super::session::SessionKeyPolicy;
+
+pub const KEYSTORE_METADATA_STORAGE_KEY: &str = "__bindings__keystore_metadata";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_cache_policy: Option<PasswordCachePolicy>,
+    /// Unix timestamp in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// Unix timestamp in milliseconds of the last signature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<u32>,
+    /// Fingerprint of the Ledger device which holds the key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ledger_device: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionKeyPolicy>,
+}
+
+/// Additional key properties which are not tracked by the core keystore
+pub struct KeyStoreMetadata {
+    storage: Arc<dyn nt::external::Storage>,
+    entries: Mutex<HashMap<String, KeyMetadata>>,
+}
+
+impl KeyStoreMetadata {
+    pub async fn load(storage: Arc<dyn nt::external::Storage>) -> Result<Self> {
+        let entries = load_entries(storage.as_ref()).await?;
+        Ok(Self {
+            storage,
+            entries: Mutex::new(entries),
+        })
+    }
+
+    pub async fn reload(&self) -> Result<()> {
+        let entries = load_entries(self.storage.as_ref()).await?;
+        *self.entries.lock().trust_me() = entries;
+        Ok(())
+    }
+
+    pub fn get(&self, public_key: &ed25519_dalek::PublicKey) -> KeyMetadata {
+        self.entries
+            .lock()
+            .trust_me()
+            .get(&hex::encode(public_key.as_bytes()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Updates metadata on top of the latest stored state,
+    /// so that changes made by other instances are not lost
+    pub async fn update<F>(
+        &self,
+        _lock: &KeyStoreLock<'_>,
+        public_key: &ed25519_dalek::PublicKey,
+        f: F,
+    ) -> Result<KeyMetadata>
+    where
+        F: FnOnce(&mut KeyMetadata),
+    {
+        let mut entries = load_entries(self.storage.as_ref()).await?;
+
+        let metadata = entries
+            .entry(hex::encode(public_key.as_bytes()))
+            .or_default();
+        f(metadata);
+        let metadata = metadata.clone();
+
+        self.store(entries).await?;
+        Ok(metadata)
+    }
+
+    pub async fn remove(
+        &self,
+        _lock: &KeyStoreLock<'_>,
+        public_key: &ed25519_dalek::PublicKey,
+    ) -> Result<()> {
+        let mut entries = load_entries(self.storage.as_ref()).await?;
+        entries.remove(&hex::encode(public_key.as_bytes()));
+        self.store(entries).await
+    }
+
+    pub async fn clear(&self, _lock: &KeyStoreLock<'_>) -> Result<()> {
+        self.entries.lock().trust_me().clear();
+        self.storage.remove(KEYSTORE_METADATA_STORAGE_KEY).await
+    }
+
+    async fn store(&self, entries: HashMap<String, KeyMetadata>) -> Result<()> {
+        let data = serde_json::to_string(&entries)?;
+        self.storage
+            .set(KEYSTORE_METADATA_STORAGE_KEY, &data)
+            .await?;
+        *self.entries.lock().trust_me() = entries;
+        Ok(())
+    }
+}
+
+async fn load_entries(storage: &dyn nt::external::Storage) -> Result<HashMap<String, KeyMetadata>> {
+    match storage.get(KEYSTORE_METADATA_STORAGE_KEY).await? {
+        Some(data) => Ok(serde_json::from_str(&data)?),
+        None => Ok(Default::default()),
+    }
+}
@@ -8,18 +8,186 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::*;
 
+use crate::crypto::mnemonic::{prepare_import_phrase, Bip39Language, ImportPhrase, MnemonicError};
+use crate::helpers::comment::*;
 use crate::utils::*;
 
+use self::audit::*;
+use self::encryption::*;
+use self::events::*;
+use self::kdf::*;
+use self::metadata::*;
+use self::password_cache::*;
+use self::raw_key::*;
+use self::session::*;
+use self::shares::*;
+use self::storage::*;
+
+pub mod audit;
+pub mod encryption;
+pub mod events;
+pub mod kdf;
+pub mod metadata;
+pub mod password_cache;
+pub mod raw_key;
+pub mod session;
+pub mod shares;
+pub mod storage;
+
 #[wasm_bindgen]
 pub struct KeyStore {
     #[wasm_bindgen(skip)]
     pub inner: Arc<nt::core::keystore::KeyStore>,
+    #[wasm_bindgen(skip)]
+    pub state: Arc<KeyStoreState>,
+}
+
+pub struct KeyStoreState {
+    pub storage: Arc<KeyStoreStorage>,
+    pub metadata: KeyStoreMetadata,
+    pub password_cache: PasswordCacheTracker,
+    pub revision: KeyStoreRevision,
+    pub handler: Option<KeyStoreEventHandlerImpl>,
+    pub ledger_connection: Arc<crate::external::LedgerConnectionImpl>,
+    pub sessions: SessionKeyPasswords,
+    pub audit_log: AuditLog,
+}
+
+impl KeyStoreState {
+    fn notify<F>(&self, f: F)
+    where
+        F: FnOnce(&KeyStoreEventHandlerImpl),
+    {
+        if let Some(handler) = &self.handler {
+            f(handler);
+        }
+    }
+
+    fn notify_password_cache_changed(&self, cache_id: &[u8; 32], cached: bool) {
+        self.notify(|handler| handler.on_password_cache_changed(&hex::encode(cache_id), cached));
+    }
+
+    fn commit_password(&self, cache_id: &ed25519_dalek::PublicKey, action: CacheAction) {
+        if self.password_cache.commit(cache_id.as_bytes(), action) {
+            let cached = matches!(action, CacheAction::Store);
+            self.notify_password_cache_changed(cache_id.as_bytes(), cached);
+        }
+    }
+
+    /// Updates the last use time of the key.
+    /// Doesn't fail since the operation itself has already succeeded
+    async fn touch_key(&self, public_key: &ed25519_dalek::PublicKey) {
+        let now = nt_utils::now_ms_u64();
+        let result = match self.revision.lock().await {
+            Ok(lock) => self
+                .metadata
+                .update(&lock, public_key, |metadata| {
+                    metadata.last_used_at = Some(now)
+                })
+                .await
+                .map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            crate::warn(&format!("Failed to update key usage time: {:?}", e));
+        }
+    }
+
+    /// Ledger keys can only be used with the device they were added from
+    async fn check_ledger_device(
+        &self,
+        public_key: &ed25519_dalek::PublicKey,
+    ) -> Result<(), JsValue> {
+        match self.metadata.get(public_key).ledger_device {
+            Some(device) => self
+                .ledger_connection
+                .check_device(&device)
+                .await
+                .handle_error(),
+            None => Ok(()),
+        }
+    }
+
+    /// Audit log is best-effort, so the completed operation is never reported as failed
+    async fn audit(&self, record: AuditRecord) {
+        if let Err(e) = self.audit_log.append(record).await {
+            crate::warn(&format!("Failed to append audit log entry: {:?}", e));
+        }
+    }
+
+    fn forget_password(&self, cache_id: &ed25519_dalek::PublicKey) {
+        if self.password_cache.remove(cache_id.as_bytes()) {
+            self.notify_password_cache_changed(cache_id.as_bytes(), false);
+        }
+    }
+
+    /// Derived keys share the cached password of their master key,
+    /// so the strictest policy of all keys with the same cache id is used
+    async fn password_cache_policy(
+        &self,
+        key_store: &nt::core::keystore::KeyStore,
+        cache_id: &ed25519_dalek::PublicKey,
+    ) -> PasswordCachePolicy {
+        let public_keys = key_store
+            .get_entries()
+            .await
+            .into_iter()
+            .filter(|entry| &entry.master_key == cache_id)
+            .map(|entry| entry.public_key)
+            .collect::<Vec<_>>();
+
+        let policy = PasswordCachePolicy::strictest(
+            public_keys
+                .iter()
+                .filter_map(|public_key| self.metadata.get(public_key).password_cache_policy),
+        );
+
+        self.password_cache.update_group(
+            cache_id.as_bytes(),
+            &policy,
+            public_keys.iter().map(|public_key| public_key.to_bytes()),
+        );
+        policy
+    }
+
+    /// Converts the password entered by the user into the password of the core signers.
+    /// Derived keys use the KDF settings of their master key
+    fn harden_password(
+        &self,
+        cache_id: &ed25519_dalek::PublicKey,
+        password: String,
+    ) -> Result<String, JsValue> {
+        let encryption = self.storage.get_encryption(cache_id);
+        harden_password(encryption.as_ref(), password).handle_error()
+    }
+
+    async fn prepare_password(
+        &self,
+        key_store: &nt::core::keystore::KeyStore,
+        cache_id: &ed25519_dalek::PublicKey,
+        password: Option<String>,
+        cache: bool,
+    ) -> Result<(nt::crypto::Password, CacheAction), JsValue> {
+        let password = match password {
+            Some(password) => Some(self.harden_password(cache_id, password)?),
+            None => None,
+        };
+
+        let policy = self.password_cache_policy(key_store, cache_id).await;
+        self.password_cache
+            .prepare(cache_id.as_bytes(), &policy, password, cache)
+            .handle_error()
+    }
 }
 
 #[wasm_bindgen]
 impl KeyStore {
+    /// Checks that the serialized keystore can be loaded and resolves with
+    /// the password protected keys which still use legacy KDF settings.
+    ///
+    /// NOTE: session keys are reported too, since they can't be told apart without the metadata
     #[wasm_bindgen]
-    pub fn verify(data: &str) -> bool {
+    pub fn verify(data: &str) -> PromiseKeyStoreVerification {
         struct StubLedgerConnection;
 
         #[async_trait::async_trait]
@@ -41,55 +209,171 @@ impl KeyStore {
             }
         }
 
-        fn try_verify(data: &str) -> anyhow::Result<()> {
-            nt::core::keystore::KeyStore::builder()
+        /// Provides the serialized keystore without touching the real storage
+        struct StubStorage(String);
+
+        #[async_trait::async_trait]
+        impl nt::external::Storage for StubStorage {
+            async fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+                Ok((key == nt::core::keystore::KEYSTORE_STORAGE_KEY).then(|| self.0.clone()))
+            }
+
+            async fn set(&self, _: &str, _: &str) -> anyhow::Result<()> {
+                Ok(())
+            }
+
+            fn set_unchecked(&self, _: &str, _: &str) {}
+
+            async fn remove(&self, _: &str) -> anyhow::Result<()> {
+                Ok(())
+            }
+
+            fn remove_unchecked(&self, _: &str) {}
+        }
+
+        async fn try_verify(data: StoredKeyStore) -> anyhow::Result<Vec<String>> {
+            let key_store = nt::core::keystore::KeyStore::builder()
                 .with_signer(DERIVED_SIGNER, nt::crypto::DerivedKeySigner::new())?
                 .with_signer(ENCRYPTED_SIGNER, nt::crypto::EncryptedKeySigner::new())?
+                .with_signer(RAW_SIGNER, RawKeySigner::new())?
                 .with_signer(
                     LEDGER_SIGNER,
                     nt::crypto::LedgerKeySigner::new(Arc::new(StubLedgerConnection)),
                 )?
-                .verify(data)
+                .load(Arc::new(StubStorage(data.keystore.clone())))
+                .await?;
+
+            Ok(key_store
+                .get_entries()
+                .await
+                .into_iter()
+                .filter(|entry| {
+                    entry.signer_name != LEDGER_SIGNER
+                        && is_legacy_encryption(data.get(&entry.master_key))
+                })
+                .map(|entry| hex::encode(entry.public_key.as_bytes()))
+                .collect())
         }
 
-        try_verify(data).is_ok()
+        let data = StoredKeyStore::parse(data);
+
+        JsCast::unchecked_into(future_to_promise(async move {
+            let (valid, legacy_keys) = match try_verify(data).await {
+                Ok(legacy_keys) => (true, legacy_keys),
+                Err(_) => (false, Vec::new()),
+            };
+
+            Ok(ObjectBuilder::new()
+                .set("valid", valid)
+                .set(
+                    "legacyKeys",
+                    legacy_keys
+                        .into_iter()
+                        .map(JsValue::from)
+                        .collect::<js_sys::Array>(),
+                )
+                .build())
+        }))
     }
 
     #[wasm_bindgen]
     pub fn load(
         storage: &crate::external::Storage,
         ledger_connection: &crate::external::LedgerConnection,
+        handler: Option<KeyStoreEventHandlerImpl>,
     ) -> PromiseKeyStore {
-        let storage = storage.inner.clone();
+        let storage = storage.inner.clone() as Arc<dyn nt::external::Storage>;
         let ledger_connection = ledger_connection.inner.clone();
 
         JsCast::unchecked_into(future_to_promise(async move {
+            // KDF settings are loaded by the core keystore together with the keys
+            let key_store_storage = Arc::new(KeyStoreStorage::new(storage.clone()));
+            let metadata = KeyStoreMetadata::load(storage.clone())
+                .await
+                .handle_error()?;
+            let revision = KeyStoreRevision::load(storage.clone())
+                .await
+                .handle_error()?;
+            let audit_log = AuditLog::new(storage.clone());
+
             let inner = Arc::new(
                 nt::core::keystore::KeyStore::builder()
                     .with_signer(DERIVED_SIGNER, nt::crypto::DerivedKeySigner::new())
                     .handle_error()?
                     .with_signer(ENCRYPTED_SIGNER, nt::crypto::EncryptedKeySigner::new())
                     .handle_error()?
+                    .with_signer(RAW_SIGNER, RawKeySigner::new())
+                    .handle_error()?
                     .with_signer(
                         LEDGER_SIGNER,
-                        nt::crypto::LedgerKeySigner::new(ledger_connection),
+                        nt::crypto::LedgerKeySigner::new(ledger_connection.clone()),
                     )
                     .handle_error()?
-                    .load(storage as Arc<dyn nt::external::Storage>)
+                    .load(key_store_storage.clone())
                     .await
                     .handle_error()?,
             );
 
-            Ok(JsValue::from(Self { inner }))
+            let state = Arc::new(KeyStoreState {
+                storage: key_store_storage,
+                metadata,
+                password_cache: Default::default(),
+                revision,
+                handler,
+                ledger_connection,
+                sessions: Default::default(),
+                audit_log,
+            });
+
+            Ok(JsValue::from(Self { inner, state }))
         }))
     }
 
+    /// Storage key which changes on every keystore update from any instance
+    #[wasm_bindgen(js_name = "revisionStorageKey")]
+    pub fn revision_storage_key() -> String {
+        KEYSTORE_REVISION_STORAGE_KEY.to_owned()
+    }
+
     #[wasm_bindgen]
     pub fn reload(&self) -> PromiseVoid {
         let inner = self.inner.clone();
+        let state = self.state.clone();
 
         JsCast::unchecked_into(future_to_promise(async move {
+            let old_entries = inner.get_entries().await;
+
             inner.reload().await.handle_error()?;
+            state.metadata.reload().await.handle_error()?;
+            state.revision.sync().await.handle_error()?;
+
+            let new_entries = inner.get_entries().await;
+            for entry in &old_entries {
+                if !new_entries
+                    .iter()
+                    .any(|new| new.public_key == entry.public_key)
+                {
+                    let entry = make_key_store_entry(entry.clone(), &state);
+                    state.notify(|handler| handler.on_key_removed(entry));
+                }
+            }
+            for entry in &new_entries {
+                let event = match old_entries
+                    .iter()
+                    .find(|old| old.public_key == entry.public_key)
+                {
+                    None => KeyStoreEvent::Added,
+                    Some(old) if old.name != entry.name => KeyStoreEvent::Renamed,
+                    Some(_) => continue,
+                };
+
+                let entry = make_key_store_entry(entry.clone(), &state);
+                state.notify(|handler| match event {
+                    KeyStoreEvent::Added => handler.on_key_added(entry),
+                    KeyStoreEvent::Renamed => handler.on_key_renamed(entry),
+                });
+            }
+
             Ok(JsValue::undefined())
         }))
     }
@@ -99,12 +383,102 @@ impl KeyStore {
         self.inner.password_cache().refresh();
     }
 
+    /// Returns the remaining time in milliseconds if the password is cached
+    /// and can still be used with the key's cache policy.
+    ///
+    /// Derived keys report the password cached for their master key
     #[wasm_bindgen(js_name = "isPasswordCached")]
-    pub fn is_password_cached(&self, public_key: &str) -> Result<bool, JsValue> {
+    pub fn is_password_cached(&self, public_key: &str) -> Result<Option<f64>, JsValue> {
         let public_key = parse_public_key(public_key)?;
         Ok(self
-            .inner
-            .is_password_cached(public_key.as_bytes(), KEYSTORE_CACHE_GAP))
+            .state
+            .password_cache
+            .remaining_for_key(public_key.as_bytes(), nt_utils::now_ms_u64())
+            .filter(|remaining| *remaining > KEYSTORE_CACHE_GAP)
+            .map(|remaining| remaining.as_millis() as f64))
+    }
+
+    /// Derived keys share the password of their master key, so the strictest
+    /// policy among them is applied to all of them
+    #[wasm_bindgen(js_name = "setPasswordCachePolicy")]
+    pub fn set_password_cache_policy(
+        &self,
+        public_key: &str,
+        policy: Option<JsPasswordCachePolicy>,
+    ) -> Result<PromiseKeyStoreEntry, JsValue> {
+        let public_key = parse_public_key(public_key)?;
+        let policy = policy
+            .map(|policy| JsValue::into_serde::<PasswordCachePolicy>(&policy))
+            .transpose()
+            .handle_error()?;
+
+        let never = matches!(&policy, Some(policy) if policy.never);
+
+        let inner = self.inner.clone();
+        let state = self.state.clone();
+
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let entry = update_key_metadata(&inner, &state, public_key, |metadata| {
+                metadata.password_cache_policy = policy;
+            })
+            .await?;
+
+            if let Some(cache_id) = find_cache_id(&inner, &public_key).await {
+                // Updates the policy which is used by `isPasswordCached`
+                state.password_cache_policy(&inner, &cache_id).await;
+
+                if never {
+                    inner.password_cache().remove(cache_id.as_bytes());
+                    state.forget_password(&cache_id);
+                }
+            }
+
+            Ok(entry.unchecked_into())
+        })))
+    }
+
+    #[wasm_bindgen(js_name = "setKeyTags")]
+    pub fn set_key_tags(
+        &self,
+        public_key: &str,
+        tags: StringArray,
+    ) -> Result<PromiseKeyStoreEntry, JsValue> {
+        let public_key = parse_public_key(public_key)?;
+        let tags = JsValue::into_serde::<Vec<String>>(&tags).handle_error()?;
+
+        Ok(self.update_key_metadata(public_key, |metadata| {
+            metadata.tags = tags;
+        }))
+    }
+
+    #[wasm_bindgen(js_name = "setKeyOrder")]
+    pub fn set_key_order(
+        &self,
+        public_key: &str,
+        order: Option<u32>,
+    ) -> Result<PromiseKeyStoreEntry, JsValue> {
+        let public_key = parse_public_key(public_key)?;
+
+        Ok(self.update_key_metadata(public_key, move |metadata| {
+            metadata.order = order;
+        }))
+    }
+
+    fn update_key_metadata<F>(
+        &self,
+        public_key: ed25519_dalek::PublicKey,
+        f: F,
+    ) -> PromiseKeyStoreEntry
+    where
+        F: FnOnce(&mut KeyMetadata) + 'static,
+    {
+        let inner = self.inner.clone();
+        let state = self.state.clone();
+
+        JsCast::unchecked_into(future_to_promise(async move {
+            let entry = update_key_metadata(&inner, &state, public_key, f).await?;
+            Ok(entry.unchecked_into())
+        }))
     }
 
     #[wasm_bindgen(js_name = "addKey")]
@@ -112,60 +486,165 @@ impl KeyStore {
         use nt::crypto::*;
 
         let inner = self.inner.clone();
+        let state = self.state.clone();
         let new_key = JsValue::into_serde::<ParsedNewKey>(&new_key).handle_error()?;
+        let is_ledger_key = matches!(&new_key, ParsedNewKey::LedgerKey { .. });
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
-            let entry = match new_key {
+            // Fingerprint is queried first, so the key is never stored without it
+            let ledger_device = if is_ledger_key {
+                Some(
+                    state
+                        .ledger_connection
+                        .get_device_fingerprint()
+                        .await
+                        .handle_error()?,
+                )
+            } else {
+                None
+            };
+
+            let revision = state.revision.begin().await.handle_error()?;
+
+            // New passwords are hardened with the default KDF settings,
+            // derived keys use the settings of their master key
+            let (entry, encryption) = match new_key {
                 ParsedNewKey::MasterKey {
                     name,
                     params,
                     password,
-                } => {
-                    inner
-                        .add_key::<DerivedKeySigner>(match params {
-                            ParsedNewMasterKeyParams::MasterKeyParams { phrase } => {
-                                DerivedKeyCreateInput::Import {
-                                    key_name: name,
-                                    phrase: phrase.into(),
-                                    password: explicit_password(password),
-                                }
-                            }
-                            ParsedNewMasterKeyParams::DerivedKeyParams {
-                                master_key,
-                                account_id,
-                            } => DerivedKeyCreateInput::Derive {
-                                key_name: name,
-                                master_key: parse_public_key(&master_key)?,
-                                account_id,
-                                password: explicit_password(password),
-                            },
-                        })
-                        .await
-                }
+                } => match params {
+                    ParsedNewMasterKeyParams::MasterKeyParams { phrase, language } => {
+                        let encryption = KeyEncryption::new(Default::default()).handle_error()?;
+                        let password =
+                            explicit_password(encryption.harden(&password).handle_error()?);
+                        let entry = add_master_key(&inner, name, &phrase, language, password).await;
+                        (entry, Some(encryption))
+                    }
+                    ParsedNewMasterKeyParams::DerivedKeyParams {
+                        master_key,
+                        account_id,
+                    } => {
+                        let master_key = parse_public_key(&master_key)?;
+                        let encryption = state.storage.get_encryption(&master_key);
+                        let input = DerivedKeyCreateInput::Derive {
+                            key_name: name,
+                            master_key,
+                            account_id,
+                            password: explicit_password(
+                                harden_password(encryption.as_ref(), password).handle_error()?,
+                            ),
+                        };
+                        let entry = inner.add_key::<DerivedKeySigner>(input).await;
+                        (entry, encryption)
+                    }
+                    ParsedNewMasterKeyParams::MasterKeySharesParams { shares, language } => {
+                        let encryption = KeyEncryption::new(Default::default()).handle_error()?;
+                        let password =
+                            explicit_password(encryption.harden(&password).handle_error()?);
+                        let phrase = combine_shares(&shares).handle_error()?;
+                        let entry = add_master_key(&inner, name, &phrase, language, password).await;
+                        (entry, Some(encryption))
+                    }
+                },
                 ParsedNewKey::EncryptedKey {
                     name,
-                    phrase,
+                    secret,
                     mnemonic_type,
+                    language,
                     password,
                 } => {
-                    inner
-                        .add_key::<EncryptedKeySigner>(EncryptedKeyCreateInput {
-                            name,
-                            phrase: phrase.into(),
-                            mnemonic_type: mnemonic_type.into(),
-                            password: explicit_password(password),
-                        })
-                        .await
+                    let encryption = KeyEncryption::new(Default::default()).handle_error()?;
+                    let password = explicit_password(encryption.harden(&password).handle_error()?);
+
+                    let entry = match secret.import().handle_error()? {
+                        ImportedSecret::Phrase(phrase) => {
+                            let mnemonic_type = mnemonic_type
+                                .ok_or(KeyStoreError::MnemonicTypeRequired)
+                                .handle_error()?;
+                            match prepare_import_phrase(&phrase, language).handle_error()? {
+                                ImportPhrase::English(phrase) => {
+                                    let input = EncryptedKeyCreateInput {
+                                        name,
+                                        phrase: phrase.into(),
+                                        mnemonic_type: mnemonic_type.into(),
+                                        password,
+                                    };
+                                    inner.add_key::<EncryptedKeySigner>(input).await
+                                }
+                                ImportPhrase::Native { phrase, language } => {
+                                    add_native_phrase_key(
+                                        &inner,
+                                        name,
+                                        phrase,
+                                        language,
+                                        mnemonic_type.into(),
+                                        password,
+                                    )
+                                    .await
+                                }
+                            }
+                        }
+                        ImportedSecret::RawKey(secret, format) => {
+                            let input = RawKeyCreateInput {
+                                name,
+                                secret: secret.to_bytes().to_vec(),
+                                format,
+                                password,
+                            };
+                            inner.add_key::<RawKeySigner>(input).await
+                        }
+                    };
+                    (entry, Some(encryption))
                 }
                 ParsedNewKey::LedgerKey { name, account_id } => {
-                    inner
-                        .add_key::<LedgerKeySigner>(LedgerKeyCreateInput { name, account_id })
-                        .await
+                    let input = LedgerKeyCreateInput { name, account_id };
+                    let entry = inner.add_key::<LedgerKeySigner>(input).await;
+                    (entry, None)
+                }
+            };
+            let entry = entry.handle_error()?;
+
+            // Signer queries the key without the fingerprint, so the device could be changed
+            if let Some(device) = &ledger_device {
+                if let Err(e) = state.ledger_connection.check_device(device).await {
+                    if let Err(e) = inner.remove_key(&entry.public_key).await {
+                        crate::warn(&format!("Failed to remove the added key: {:?}", e));
+                    }
+                    return Err(e).handle_error();
                 }
             }
-            .handle_error()?;
 
-            Ok(JsValue::from(make_key_store_entry(entry)))
+            // Key can't be used without its KDF settings, so it is not kept if they are not stored
+            if let Some(encryption) = encryption {
+                if let Err(e) = state
+                    .storage
+                    .set_encryption(revision.lock(), &entry.master_key, encryption)
+                    .await
+                {
+                    if let Err(e) = inner.remove_key(&entry.public_key).await {
+                        crate::warn(&format!("Failed to remove the added key: {:?}", e));
+                    }
+                    return Err(e).handle_error();
+                }
+            }
+
+            let created_at = nt_utils::now_ms_u64();
+            state
+                .metadata
+                .update(revision.lock(), &entry.public_key, |metadata| {
+                    metadata.created_at.get_or_insert(created_at);
+                    if ledger_device.is_some() {
+                        metadata.ledger_device = ledger_device;
+                    }
+                })
+                .await
+                .handle_error()?;
+            revision.commit().await.handle_error()?;
+
+            let entry = make_key_store_entry(entry, &state);
+            state.notify(|handler| handler.on_key_added(entry.clone()));
+            Ok(JsValue::from(entry))
         })))
     }
 
@@ -174,9 +653,12 @@ impl KeyStore {
         use nt::crypto::*;
 
         let inner = self.inner.clone();
+        let state = self.state.clone();
         let rename = JsValue::into_serde::<ParsedRenameKey>(&rename).handle_error()?;
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let revision = state.revision.begin().await.handle_error()?;
+
             let entry = match rename {
                 ParsedRenameKey::MasterKey {
                     master_key,
@@ -191,11 +673,14 @@ impl KeyStore {
                     inner.update_key::<DerivedKeySigner>(input).await
                 }
                 ParsedRenameKey::EncryptedKey { public_key, name } => {
-                    let input = EncryptedKeyUpdateParams::Rename {
-                        public_key: parse_public_key(&public_key)?,
-                        name,
-                    };
-                    inner.update_key::<EncryptedKeySigner>(input).await
+                    let public_key = parse_public_key(&public_key)?;
+                    if is_raw_key(&inner, &public_key).await {
+                        let input = RawKeyUpdateParams::Rename { public_key, name };
+                        inner.update_key::<RawKeySigner>(input).await
+                    } else {
+                        let input = EncryptedKeyUpdateParams::Rename { public_key, name };
+                        inner.update_key::<EncryptedKeySigner>(input).await
+                    }
                 }
                 ParsedRenameKey::LedgerKey { public_key, name } => {
                     let input = LedgerUpdateKeyInput::Rename {
@@ -206,8 +691,11 @@ impl KeyStore {
                 }
             }
             .handle_error()?;
+            revision.commit().await.handle_error()?;
 
-            Ok(make_key_store_entry(entry).unchecked_into())
+            let entry = make_key_store_entry(entry, &state);
+            state.notify(|handler| handler.on_key_renamed(entry.clone()));
+            Ok(entry.unchecked_into())
         })))
     }
 
@@ -216,97 +704,206 @@ impl KeyStore {
         &self,
         change_password: JsChangeKeyPassword,
     ) -> Result<PromiseKeyStoreEntry, JsValue> {
-        use nt::crypto::*;
-
         let inner = self.inner.clone();
+        let state = self.state.clone();
         let change_password =
             JsValue::into_serde::<ParsedChangeKeyPassword>(&change_password).handle_error()?;
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
-            let entry = match change_password {
+            let revision = state.revision.begin().await.handle_error()?;
+
+            let (target, old_password, new_password) = match change_password {
                 ParsedChangeKeyPassword::MasterKey {
                     master_key,
                     old_password,
                     new_password,
-                } => {
-                    let input = DerivedKeyUpdateParams::ChangePassword {
-                        master_key: parse_public_key(&master_key)?,
-                        old_password: explicit_password(old_password),
-                        new_password: explicit_password(new_password),
-                    };
-                    inner.update_key::<DerivedKeySigner>(input).await
-                }
+                } => (
+                    PasswordTarget::MasterKey(parse_public_key(&master_key)?),
+                    old_password,
+                    new_password,
+                ),
                 ParsedChangeKeyPassword::EncryptedKey {
                     public_key,
                     old_password,
                     new_password,
-                } => {
-                    let public_key = parse_public_key(&public_key)?;
-                    let input = EncryptedKeyUpdateParams::ChangePassword {
-                        public_key,
-                        old_password: explicit_password(old_password),
-                        new_password: explicit_password(new_password),
-                    };
-                    inner.update_key::<EncryptedKeySigner>(input).await
-                }
-            }
+                } => (
+                    PasswordTarget::EncryptedKey(parse_public_key(&public_key)?),
+                    old_password,
+                    new_password,
+                ),
+            };
+
+            // New password also gets the new salt. Legacy keys are upgraded to the default settings
+            let params = state
+                .storage
+                .get_encryption(target.cache_id())
+                .map(|encryption| encryption.params)
+                .unwrap_or_default();
+            let encryption = KeyEncryption::new(params).handle_error()?;
+
+            let entry = change_key_encryption(
+                &inner,
+                &state,
+                revision.lock(),
+                target,
+                old_password,
+                &new_password,
+                encryption,
+            )
+            .await?
             .handle_error()?;
+            revision.commit().await.handle_error()?;
 
-            Ok(make_key_store_entry(entry).unchecked_into())
+            Ok(make_key_store_entry(entry, &state).unchecked_into())
         })))
     }
 
-    #[wasm_bindgen(js_name = "exportKey")]
-    pub fn export_key(&self, export_key: JsExportKey) -> Result<PromiseExportedKey, JsValue> {
-        use nt::crypto::*;
+    /// Re-encrypts password protected keys with the new KDF settings.
+    ///
+    /// Keys which are protected with another password are skipped.
+    /// Resolves with the upgraded keys
+    #[wasm_bindgen(js_name = "upgradeEncryption")]
+    pub fn upgrade_encryption(
+        &self,
+        password: String,
+        params: Option<JsKdfParams>,
+    ) -> Result<PromiseKeyStoreEntries, JsValue> {
+        let params = params
+            .map(|params| JsValue::into_serde::<KdfParams>(&params))
+            .transpose()
+            .handle_error()?
+            .unwrap_or_default();
+        KeyEncryption::new(params).handle_error()?;
 
         let inner = self.inner.clone();
-        let export_key = JsValue::into_serde::<ParsedExportKey>(&export_key).handle_error()?;
+        let state = self.state.clone();
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
-            let output = match export_key {
-                ParsedExportKey::MasterKey {
-                    master_key,
-                    password,
-                } => {
-                    let input = DerivedKeyExportParams {
-                        master_key: parse_public_key(&master_key)?,
-                        password: explicit_password(password),
-                    };
-                    inner
-                        .export_key::<DerivedKeySigner>(input)
-                        .await
-                        .map(make_exported_master_key)
+            let revision = state.revision.begin().await.handle_error()?;
+
+            let mut targets = Vec::new();
+            for entry in inner.get_entries().await {
+                let target = match entry.signer_name.as_str() {
+                    DERIVED_SIGNER => PasswordTarget::MasterKey(entry.master_key),
+                    ENCRYPTED_SIGNER | RAW_SIGNER => PasswordTarget::EncryptedKey(entry.public_key),
+                    _ => continue,
+                };
+                // Session keys are protected with random passwords
+                if state.metadata.get(&entry.public_key).session.is_some() {
+                    continue;
                 }
-                ParsedExportKey::EncryptedKey {
-                    public_key,
-                    password,
-                } => {
-                    let public_key = parse_public_key(&public_key)?;
-                    let input = EncryptedKeyPassword {
-                        public_key,
-                        password: explicit_password(password),
-                    };
-                    inner
-                        .export_key::<EncryptedKeySigner>(input)
-                        .await
-                        .map(make_exported_encrypted_key)
+                if !targets.contains(&target) {
+                    targets.push(target);
                 }
             }
-            .handle_error()?;
+
+            let mut upgraded = Vec::new();
+            for target in targets {
+                let current = state.storage.get_encryption(target.cache_id());
+                if matches!(&current, Some(current) if current.params == params) {
+                    continue;
+                }
+
+                let encryption = KeyEncryption::new(params).handle_error()?;
+                let result = change_key_encryption(
+                    &inner,
+                    &state,
+                    revision.lock(),
+                    target,
+                    password.clone(),
+                    &password,
+                    encryption,
+                )
+                .await?;
+                if result.is_ok() {
+                    upgraded.push(*target.cache_id());
+                }
+            }
+            revision.commit().await.handle_error()?;
+
+            Ok(inner
+                .get_entries()
+                .await
+                .into_iter()
+                .filter(|entry| upgraded.contains(&entry.master_key))
+                .map(|entry| make_key_store_entry(entry, &state))
+                .map(JsValue::from)
+                .collect::<js_sys::Array>()
+                .unchecked_into())
+        })))
+    }
+
+    #[wasm_bindgen(js_name = "exportKey")]
+    pub fn export_key(
+        &self,
+        export_key: JsExportKey,
+        origin: Option<String>,
+    ) -> Result<PromiseExportedKey, JsValue> {
+        let inner = self.inner.clone();
+        let state = self.state.clone();
+        let export_key = JsValue::into_serde::<ParsedExportKey>(&export_key).handle_error()?;
+        let record = export_key.audit_record(origin)?;
+
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let output = match export_phrase(&inner, &state, export_key).await? {
+                ExportedPhrase::MasterKey(output) => make_exported_master_key(output),
+                ExportedPhrase::EncryptedKey(output) => make_exported_encrypted_key(output),
+                ExportedPhrase::RawKey(output) => make_exported_raw_key(output),
+            };
+            state.audit(record).await;
 
             Ok(JsValue::from(output))
         })))
     }
 
+    #[wasm_bindgen(js_name = "exportKeyShares")]
+    pub fn export_key_shares(
+        &self,
+        export_key: JsExportKey,
+        threshold: u8,
+        shares: u8,
+    ) -> Result<PromiseExportedKeyShares, JsValue> {
+        let inner = self.inner.clone();
+        let state = self.state.clone();
+        let export_key = JsValue::into_serde::<ParsedExportKey>(&export_key).handle_error()?;
+        let record = export_key.audit_record(None)?;
+
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let exported = export_phrase(&inner, &state, export_key).await?;
+            let shares = split_phrase(exported.phrase()?, threshold, shares)
+                .handle_error()?
+                .into_iter()
+                .map(JsValue::from)
+                .collect::<js_sys::Array>();
+
+            let output = match exported {
+                ExportedPhrase::MasterKey(_) => ObjectBuilder::new()
+                    .set("type", "master_key")
+                    .set("shares", shares),
+                ExportedPhrase::EncryptedKey(output) => ObjectBuilder::new()
+                    .set("type", "encrypted_key")
+                    .set("shares", shares)
+                    .set(
+                        "mnemonicType",
+                        crate::crypto::make_mnemonic_type(output.mnemonic_type),
+                    ),
+                ExportedPhrase::RawKey(_) => {
+                    return Err(KeyStoreError::RawKeySharesNotSupported).handle_error()
+                }
+            };
+            state.audit(record).await;
+
+            Ok(output.build())
+        })))
+    }
+
     #[wasm_bindgen(js_name = "getPublicKeys")]
     pub fn get_public_keys(
         &self,
         get_public_keys: JsGetPublicKeys,
     ) -> Result<PromisePublicKeys, JsValue> {
-        use nt::crypto::*;
-
         let inner = self.inner.clone();
+        let state = self.state.clone();
         let get_public_keys =
             JsValue::into_serde::<ParsedGetPublicKeys>(&get_public_keys).handle_error()?;
 
@@ -319,39 +916,41 @@ impl KeyStore {
                     offset,
                     limit,
                 } => {
-                    let input = DerivedKeyGetPublicKeys {
-                        master_key: parse_public_key(&master_key)?,
-                        password: cached_password(password, cache),
-                        limit,
-                        offset,
-                    };
-                    inner
-                        .get_public_keys::<DerivedKeySigner>(input)
-                        .await
-                        .map(make_public_keys_list)
+                    let master_key = parse_public_key(&master_key)?;
+                    let public_keys = get_derived_public_keys(
+                        &inner, &state, master_key, password, cache, offset, limit,
+                    )
+                    .await?;
+                    Ok(make_public_keys_list(public_keys))
                 }
-                ParsedGetPublicKeys::LedgerKey { offset, limit } => {
-                    let input = LedgerKeyGetPublicKeys { offset, limit };
-                    inner
-                        .get_public_keys::<LedgerKeySigner>(input)
+                ParsedGetPublicKeys::LedgerKey {
+                    offset,
+                    limit,
+                    device,
+                } => {
+                    // NOTE: signer is bypassed, because it can't pass the device
+                    state
+                        .ledger_connection
+                        .get_public_keys(offset, limit, device)
                         .await
                         .map(make_public_keys_list)
+                        .handle_error()
                 }
             }
-            .handle_error()
         })))
     }
 
     #[wasm_bindgen]
     pub fn check_password(&self, key_password: JsKeyPassword) -> Result<PromiseBool, JsValue> {
         let inner = self.inner.clone();
+        let state = self.state.clone();
         let key_password =
             JsValue::into_serde::<ParsedKeyPassword>(&key_password).handle_error()?;
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
             let hash = ton_types::UInt256::default();
             Ok(JsValue::from(
-                sign_data(&inner, key_password, hash.as_slice())
+                sign_data(&inner, &state, key_password, hash.as_slice(), None, None)
                     .await
                     .is_ok(),
             ))
@@ -365,25 +964,49 @@ impl KeyStore {
         public_keys: PublicKeysList,
         algorithm: &str,
         key_password: JsKeyPassword,
+        origin: Option<String>,
     ) -> Result<PromiseEncryptedData, JsValue> {
         use std::str::FromStr;
 
         let inner = self.inner.clone();
+        let state = self.state.clone();
         let data = base64::decode(data).handle_error()?;
         let public_keys = parse_public_key_list(public_keys)?;
-        let algorithm = nt::crypto::EncryptionAlgorithm::from_str(algorithm).handle_error()?;
+        let algorithm = EncryptionAlgorithm::from_str(algorithm).handle_error()?;
         let key_password =
             JsValue::into_serde::<ParsedKeyPassword>(&key_password).handle_error()?;
+        let record = key_password
+            .audit_record(AuditOperation::EncryptData, origin)?
+            .with_message_hash(&sha2::Sha256::digest(&data))
+            .with_destination(Some(join_public_keys(&public_keys)), None);
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
-            Ok(
-                encrypt_data(&inner, &data, key_password, &public_keys, algorithm)
-                    .await?
+            let encrypted_data = if algorithm.is_native() {
+                encrypt_data(
+                    &inner,
+                    &state,
+                    &data,
+                    key_password,
+                    &public_keys,
+                    nt::crypto::EncryptionAlgorithm::ChaCha20Poly1305,
+                )
+                .await?
+                .into_iter()
+                .map(make_encrypted_data)
+                .map(JsValue::from)
+                .collect::<js_sys::Array>()
+            } else {
+                let keypair = export_keypair(&inner, &state, key_password, origin).await?;
+                seal_data(algorithm, &keypair, &public_keys, &data)
+                    .handle_error()?
                     .into_iter()
-                    .map(|data| make_encrypted_data(data).unchecked_into::<JsValue>())
+                    .map(make_sealed_data)
+                    .map(JsValue::from)
                     .collect::<js_sys::Array>()
-                    .unchecked_into(),
-            )
+            };
+            state.audit(record).await;
+
+            Ok(encrypted_data.unchecked_into())
         })))
     }
 
@@ -392,55 +1015,194 @@ impl KeyStore {
         &self,
         data: EncryptedData,
         key_password: JsKeyPassword,
+        origin: Option<String>,
     ) -> Result<PromiseString, JsValue> {
         let inner = self.inner.clone();
+        let state = self.state.clone();
         let data = parse_encrypted_data(data)?;
         let key_password =
             JsValue::into_serde::<ParsedKeyPassword>(&key_password).handle_error()?;
+        let record = key_password.audit_record(AuditOperation::DecryptData, origin.clone())?;
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
-            let data = decrypt_data(&inner, data, key_password).await?;
+            let data = match data {
+                ParsedEncryptedData::Native(data) => {
+                    decrypt_data(&inner, &state, data, key_password).await?
+                }
+                ParsedEncryptedData::Sealed(data) => {
+                    let keypair = export_keypair(&inner, &state, key_password, origin).await?;
+                    open_data(&keypair, &data).handle_error()?
+                }
+            };
+            state
+                .audit(record.with_message_hash(&sha2::Sha256::digest(&data)))
+                .await;
+
             Ok(JsValue::from(base64::encode(data)).unchecked_into())
         })))
     }
 
-    #[wasm_bindgen]
-    pub fn sign(
+    #[wasm_bindgen(js_name = "encodeEncryptedComment")]
+    pub fn encode_encrypted_comment(
         &self,
-        message: &crate::crypto::UnsignedMessage,
+        transport: &crate::transport::Transport,
+        comment: &str,
+        recipient: &str,
         key_password: JsKeyPassword,
-    ) -> Result<PromiseSignedMessage, JsValue> {
-        let message = message.inner.clone();
+    ) -> Result<PromiseString, JsValue> {
         let inner = self.inner.clone();
+        let state = self.state.clone();
+        let clock = transport.clock.clone();
+        let handle = transport.handle.clone();
+        let comment = comment.as_bytes().to_vec();
+        let recipient = parse_address(recipient)?;
         let key_password =
             JsValue::into_serde::<ParsedKeyPassword>(&key_password).handle_error()?;
+        let record = key_password
+            .audit_record(AuditOperation::EncryptData, None)?
+            .with_message_hash(&sha2::Sha256::digest(&comment))
+            .with_destination(Some(recipient.to_string()), None);
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
-            let hash = nt::crypto::UnsignedMessage::hash(message.as_ref());
-            let signature = sign_data(&inner, key_password, hash).await?;
-
-            let message = message.sign(&signature).handle_error()?;
+            let recipient_public_key =
+                get_wallet_public_key(clock.as_ref(), handle.as_ref(), &recipient).await?;
+
+            let data = encrypt_data(
+                &inner,
+                &state,
+                &comment,
+                key_password,
+                &[recipient_public_key],
+                nt::crypto::EncryptionAlgorithm::ChaCha20Poly1305,
+            )
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(EncryptedCommentError::InvalidComment)
+            .handle_error()?;
+            state.audit(record).await;
 
-            crate::crypto::make_signed_message(message).map(JsValue::from)
+            let payload = build_encrypted_comment_payload(&data).handle_error()?;
+            let payload = ton_types::serialize_toc(&payload).handle_error()?;
+            Ok(JsValue::from(base64::encode(payload)).unchecked_into())
         })))
     }
 
-    #[wasm_bindgen(js_name = "signData")]
-    pub fn sign_data(
+    #[wasm_bindgen(js_name = "decryptComment")]
+    pub fn decrypt_comment(
         &self,
-        data: &str,
+        data: EncryptedData,
         key_password: JsKeyPassword,
-    ) -> Result<PromiseSignedData, JsValue> {
-        let data = base64::decode(data).handle_error()?;
+    ) -> Result<PromiseString, JsValue> {
         let inner = self.inner.clone();
+        let state = self.state.clone();
+        let mut data = match parse_encrypted_data(data)? {
+            ParsedEncryptedData::Native(data) => data,
+            ParsedEncryptedData::Sealed(_) => {
+                return Err(EncryptedCommentError::InvalidComment).handle_error()
+            }
+        };
         let key_password =
             JsValue::into_serde::<ParsedKeyPassword>(&key_password).handle_error()?;
 
-        Ok(JsCast::unchecked_into(future_to_promise(async move {
-            let hash: [u8; 32] = sha2::Sha256::digest(&data).into();
-            let signature = sign_data(&inner, key_password, &hash).await?;
+        // Sender can also decrypt its own comment, using the shared secret with the recipient
+        let public_key = key_password.public_key()?;
+        if data.source_public_key == public_key {
+            std::mem::swap(&mut data.source_public_key, &mut data.recipient_public_key);
+        } else if data.recipient_public_key != public_key {
+            return Err(EncryptedCommentError::UnknownKey).handle_error();
+        }
+        let record = key_password.audit_record(AuditOperation::DecryptData, None)?;
 
-            Ok(crate::crypto::make_signed_data(hash, signature).unchecked_into())
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let data = decrypt_data(&inner, &state, data, key_password).await?;
+            state
+                .audit(record.with_message_hash(&sha2::Sha256::digest(&data)))
+                .await;
+
+            let comment = String::from_utf8(data)
+                .map_err(|_| EncryptedCommentError::InvalidComment)
+                .handle_error()?;
+            Ok(JsValue::from(comment).unchecked_into())
+        })))
+    }
+
+    /// Network signature id is resolved from the transport
+    #[wasm_bindgen]
+    pub fn sign(
+        &self,
+        message: &crate::crypto::UnsignedMessage,
+        key_password: JsKeyPassword,
+        transport: &crate::transport::Transport,
+        origin: Option<String>,
+    ) -> Result<PromiseSignedMessage, JsValue> {
+        let inner = self.inner.clone();
+        let state = self.state.clone();
+        let handle = transport.handle.clone();
+        let cached_signature_id = transport.signature_id.clone();
+        let key_password =
+            JsValue::into_serde::<ParsedKeyPassword>(&key_password).handle_error()?;
+        let (destination, value) = audit_message_transfers(message);
+        let record = key_password
+            .audit_record(AuditOperation::Sign, origin)?
+            .with_destination(destination, value);
+        let ledger_context = match &key_password {
+            ParsedKeyPassword::LedgerKey(_) => message.ledger_signing_context(),
+            _ => None,
+        };
+        let message = message.inner.clone();
+
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let public_key = key_password.public_key()?;
+            let signature_id =
+                crate::transport::resolve_signature_id(&handle, &cached_signature_id).await?;
+            let hash = nt::crypto::UnsignedMessage::hash(message.as_ref());
+            let signature = sign_data(
+                &inner,
+                &state,
+                key_password,
+                hash,
+                signature_id,
+                ledger_context,
+            )
+            .await?;
+            state.touch_key(&public_key).await;
+            state.audit(record.with_message_hash(hash)).await;
+
+            let message = message.sign(&signature).handle_error()?;
+
+            crate::crypto::make_signed_message(message).map(JsValue::from)
+        })))
+    }
+
+    #[wasm_bindgen(js_name = "signData")]
+    pub fn sign_data(
+        &self,
+        data: &str,
+        key_password: JsKeyPassword,
+        transport: &crate::transport::Transport,
+        origin: Option<String>,
+    ) -> Result<PromiseSignedData, JsValue> {
+        let data = base64::decode(data).handle_error()?;
+        let inner = self.inner.clone();
+        let state = self.state.clone();
+        let handle = transport.handle.clone();
+        let cached_signature_id = transport.signature_id.clone();
+        let key_password =
+            JsValue::into_serde::<ParsedKeyPassword>(&key_password).handle_error()?;
+
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let public_key = key_password.public_key()?;
+            let signature_id =
+                crate::transport::resolve_signature_id(&handle, &cached_signature_id).await?;
+            let record = key_password.audit_record(AuditOperation::SignData, origin)?;
+            let hash: [u8; 32] = sha2::Sha256::digest(&data).into();
+            let signature =
+                sign_data(&inner, &state, key_password, &hash, signature_id, None).await?;
+            state.touch_key(&public_key).await;
+            state.audit(record.with_message_hash(&hash)).await;
+
+            Ok(crate::crypto::make_signed_data(hash, signature).unchecked_into())
         })))
     }
 
@@ -449,55 +1211,520 @@ impl KeyStore {
         &self,
         data: &str,
         key_password: JsKeyPassword,
+        transport: &crate::transport::Transport,
+        origin: Option<String>,
     ) -> Result<PromiseSignedDataRaw, JsValue> {
         let data = base64::decode(data).handle_error()?;
         let inner = self.inner.clone();
+        let state = self.state.clone();
+        let handle = transport.handle.clone();
+        let cached_signature_id = transport.signature_id.clone();
         let key_password =
             JsValue::into_serde::<ParsedKeyPassword>(&key_password).handle_error()?;
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
-            let signature = sign_data(&inner, key_password, &data).await?;
+            let public_key = key_password.public_key()?;
+            let signature_id =
+                crate::transport::resolve_signature_id(&handle, &cached_signature_id).await?;
+            let record = key_password
+                .audit_record(AuditOperation::SignDataRaw, origin)?
+                .with_message_hash(&sha2::Sha256::digest(&data));
+            let signature =
+                sign_data(&inner, &state, key_password, &data, signature_id, None).await?;
+            state.touch_key(&public_key).await;
+            state.audit(record).await;
 
             Ok(crate::crypto::make_signed_data_raw(signature).unchecked_into())
         })))
     }
 
+    #[wasm_bindgen(js_name = "signTypedData")]
+    pub fn sign_typed_data(
+        &self,
+        params: crate::helpers::abi::ParamsList,
+        tokens: crate::helpers::abi::TokensObject,
+        abi_version: Option<String>,
+        key_password: JsKeyPassword,
+        transport: &crate::transport::Transport,
+    ) -> Result<PromiseSignedTypedData, JsValue> {
+        let cell = crate::helpers::abi::pack_tokens_into_cell(params, tokens, abi_version)?;
+        let inner = self.inner.clone();
+        let state = self.state.clone();
+        let handle = transport.handle.clone();
+        let cached_signature_id = transport.signature_id.clone();
+        let key_password =
+            JsValue::into_serde::<ParsedKeyPassword>(&key_password).handle_error()?;
+
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let public_key = key_password.public_key()?;
+            let signature_id =
+                crate::transport::resolve_signature_id(&handle, &cached_signature_id).await?;
+            let hash = cell.repr_hash();
+            let record = key_password
+                .audit_record(AuditOperation::SignTypedData, None)?
+                .with_message_hash(hash.as_slice());
+            let signature = sign_data(
+                &inner,
+                &state,
+                key_password,
+                hash.as_slice(),
+                signature_id,
+                None,
+            )
+            .await?;
+            state.touch_key(&public_key).await;
+            state.audit(record).await;
+
+            crate::crypto::make_signed_typed_data(&cell, signature).map(JsValue::from)
+        })))
+    }
+
+    #[wasm_bindgen(js_name = "signOwnershipProof")]
+    pub fn sign_ownership_proof(
+        &self,
+        params: crate::crypto::JsOwnershipProofParams,
+        key_password: JsKeyPassword,
+        transport: &crate::transport::Transport,
+    ) -> Result<PromiseOwnershipProof, JsValue> {
+        let params = crate::crypto::parse_ownership_proof_params(params)?;
+        let hash = params.hash()?;
+        let inner = self.inner.clone();
+        let state = self.state.clone();
+        let handle = transport.handle.clone();
+        let cached_signature_id = transport.signature_id.clone();
+        let key_password =
+            JsValue::into_serde::<ParsedKeyPassword>(&key_password).handle_error()?;
+
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let public_key = key_password.public_key()?;
+            let signature_id =
+                crate::transport::resolve_signature_id(&handle, &cached_signature_id).await?;
+            let record = key_password
+                .audit_record(AuditOperation::SignOwnershipProof, None)?
+                .with_message_hash(&hash);
+            let signature =
+                sign_data(&inner, &state, key_password, &hash, signature_id, None).await?;
+            state.touch_key(&public_key).await;
+            state.audit(record).await;
+
+            Ok(
+                crate::crypto::make_ownership_proof(params, &public_key, signature)
+                    .unchecked_into(),
+            )
+        })))
+    }
+
     #[wasm_bindgen(js_name = "removeKey")]
     pub fn remove_key(&self, public_key: &str) -> Result<PromiseOptionKeyStoreEntry, JsValue> {
         let public_key = parse_public_key(public_key)?;
 
         let inner = self.inner.clone();
+        let state = self.state.clone();
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
-            Ok(match inner.remove_key(&public_key).await.handle_error()? {
-                Some(entry) => make_key_store_entry(entry).unchecked_into(),
-                None => JsValue::undefined(),
-            })
+            let revision = state.revision.begin().await.handle_error()?;
+
+            // KDF settings are removed with the last key which uses them
+            let entries = inner.get_entries().await;
+            let _staged = entries
+                .iter()
+                .find(|entry| entry.public_key == public_key)
+                .map(|entry| entry.master_key)
+                .filter(|cache_id| {
+                    !entries
+                        .iter()
+                        .any(|item| item.master_key == *cache_id && item.public_key != public_key)
+                })
+                .map(|cache_id| {
+                    state.storage.stage(revision.lock(), |settings| {
+                        settings.remove(&hex::encode(cache_id.as_bytes()));
+                    })
+                });
+
+            let entry = match inner.remove_key(&public_key).await.handle_error()? {
+                Some(entry) => entry,
+                None => return Ok(JsValue::undefined()),
+            };
+
+            // NOTE: derived keys share the password with the remaining keys of the same master key
+            if !inner
+                .get_entries()
+                .await
+                .iter()
+                .any(|item| item.master_key == entry.master_key)
+            {
+                state.forget_password(&entry.master_key);
+            }
+
+            let entry = make_key_store_entry(entry, &state);
+            state.sessions.remove(&public_key);
+            state
+                .metadata
+                .remove(revision.lock(), &public_key)
+                .await
+                .handle_error()?;
+            revision.commit().await.handle_error()?;
+
+            state.notify(|handler| handler.on_key_removed(entry.clone()));
+            Ok(entry.unchecked_into())
         })))
     }
 
     #[wasm_bindgen]
     pub fn clear(&self) -> PromiseVoid {
         let inner = self.inner.clone();
+        let state = self.state.clone();
 
         JsCast::unchecked_into(future_to_promise(async move {
+            let revision = state.revision.begin().await.handle_error()?;
+
+            let entries = inner.get_entries().await;
+            let entries = entries
+                .into_iter()
+                .map(|entry| make_key_store_entry(entry, &state))
+                .collect::<Vec<_>>();
+
+            let staged = state
+                .storage
+                .stage(revision.lock(), |settings| settings.clear());
             inner.clear().await.handle_error()?;
+            drop(staged);
             inner.password_cache().clear();
+            let cleared_passwords = state.password_cache.clear();
+            state.sessions.clear();
+            state.metadata.clear(revision.lock()).await.handle_error()?;
+            revision.commit().await.handle_error()?;
+
+            for entry in entries {
+                state.notify(|handler| handler.on_key_removed(entry));
+            }
+            for cache_id in cleared_passwords {
+                state.notify_password_cache_changed(&cache_id, false);
+            }
+            Ok(JsValue::undefined())
+        }))
+    }
+
+    #[wasm_bindgen(js_name = "createSessionKey")]
+    pub fn create_session_key(
+        &self,
+        params: JsSessionKeyParams,
+    ) -> Result<PromiseKeyStoreEntry, JsValue> {
+        use nt::crypto::*;
+        use rand::Rng;
+
+        let inner = self.inner.clone();
+        let state = self.state.clone();
+        let params = JsValue::into_serde::<ParsedSessionKeyParams>(&params).handle_error()?;
+        let name = params
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Session {}", params.origin));
+        let policy = params.into_policy()?;
+        if policy.is_expired(nt_utils::now_ms_u64()) {
+            return Err(SessionKeyError::InvalidExpiration).handle_error();
+        }
+
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let mnemonic_type = MnemonicType::Labs(0);
+            let phrase = generate_key(mnemonic_type).words.join(" ");
+            let password = hex::encode(rand::thread_rng().gen::<[u8; 32]>());
+
+            let revision = state.revision.begin().await.handle_error()?;
+
+            let entry = inner
+                .add_key::<EncryptedKeySigner>(EncryptedKeyCreateInput {
+                    name: Some(name),
+                    phrase: phrase.into(),
+                    mnemonic_type,
+                    password: explicit_password(password.clone()),
+                })
+                .await
+                .handle_error()?;
+            state.sessions.insert(&entry.public_key, password);
+
+            let created_at = nt_utils::now_ms_u64();
+            state
+                .metadata
+                .update(revision.lock(), &entry.public_key, |metadata| {
+                    metadata.created_at = Some(created_at);
+                    metadata.session = Some(policy);
+                })
+                .await
+                .handle_error()?;
+            revision.commit().await.handle_error()?;
+
+            let entry = make_key_store_entry(entry, &state);
+            state.notify(|handler| handler.on_key_added(entry.clone()));
+            Ok(JsValue::from(entry))
+        })))
+    }
+
+    #[wasm_bindgen(js_name = "signWithSessionKey")]
+    pub fn sign_with_session_key(
+        &self,
+        message: &crate::crypto::UnsignedMessage,
+        origin: &str,
+        public_key: &str,
+        transport: &crate::transport::Transport,
+    ) -> Result<PromiseSignedMessage, JsValue> {
+        use nt::crypto::*;
+        use ton_block::Serializable;
+
+        let public_key = parse_public_key(public_key)?;
+        let policy = self
+            .state
+            .metadata
+            .get(&public_key)
+            .session
+            .ok_or(SessionKeyError::NotFound)
+            .handle_error()?;
+
+        let request = SessionSignRequest::from_message(message).handle_error()?;
+        policy
+            .check(origin, nt_utils::now_ms_u64(), &request)
+            .handle_error()?;
+
+        let password = self
+            .state
+            .sessions
+            .get(&public_key)
+            .ok_or(SessionKeyError::Locked)
+            .handle_error()?;
+
+        let (destination, value) = audit_message_transfers(message);
+        let record = AuditRecord::new(
+            AuditOperation::Sign,
+            &public_key,
+            ENCRYPTED_SIGNER,
+            Some(origin.to_owned()),
+        )
+        .with_destination(destination, value);
+
+        let message = message.inner.clone();
+        let origin = origin.to_owned();
+        let inner = self.inner.clone();
+        let state = self.state.clone();
+        let handle = transport.handle.clone();
+        let cached_signature_id = transport.signature_id.clone();
+
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let signature_id =
+                crate::transport::resolve_signature_id(&handle, &cached_signature_id).await?;
+
+            let hash = UnsignedMessage::hash(message.as_ref());
+            let data = crate::crypto::extend_with_signature_id(hash, signature_id);
+            let input = EncryptedKeyPassword {
+                public_key,
+                password: explicit_password(password),
+            };
+            let signature = inner
+                .sign::<EncryptedKeySigner>(&data, input)
+                .await
+                .handle_error()?;
+            let message = message.sign(&signature).handle_error()?;
+
+            // Limits are checked again together with the reservation, so concurrent
+            // requests can't exceed them. The value stays reserved until the message
+            // is settled with `settleSessionKeyMessage`
+            let message_hash = {
+                let cell: ton_types::Cell =
+                    message.message.write_to_new_cell().handle_error()?.into();
+                cell.repr_hash().to_hex_string()
+            };
+            let value = request.total_value();
+            let expire_at = message.expire_at;
+            update_session_policy(&state, &public_key, |session| {
+                session.check(&origin, nt_utils::now_ms_u64(), &request)?;
+                session.reserve(message_hash, value, expire_at);
+                Ok(())
+            })
+            .await?;
+
+            state.touch_key(&public_key).await;
+            state.audit(record.with_message_hash(hash)).await;
+
+            crate::crypto::make_signed_message(message).map(JsValue::from)
+        })))
+    }
+
+    /// Settles the value reserved for the message signed with a session key.
+    /// Value of the expired message is released, so it doesn't count towards the limits.
+    ///
+    /// Does nothing if the message wasn't signed with a session key
+    #[wasm_bindgen(js_name = "settleSessionKeyMessage")]
+    pub fn settle_session_key_message(&self, message_hash: &str, delivered: bool) -> PromiseVoid {
+        let message_hash = message_hash.to_owned();
+        let inner = self.inner.clone();
+        let state = self.state.clone();
+
+        JsCast::unchecked_into(future_to_promise(async move {
+            let public_key = inner
+                .get_entries()
+                .await
+                .into_iter()
+                .map(|entry| entry.public_key)
+                .find(|public_key| {
+                    matches!(
+                        state.metadata.get(public_key).session,
+                        Some(session) if session.is_reserved(&message_hash)
+                    )
+                });
+
+            if let Some(public_key) = public_key {
+                update_session_policy(&state, &public_key, |session| {
+                    session.settle(&message_hash, delivered);
+                    Ok(())
+                })
+                .await?;
+            }
+
             Ok(JsValue::undefined())
         }))
     }
 
+    /// Removes session keys which are expired or can't be used after reload
+    #[wasm_bindgen(js_name = "removeExpiredSessionKeys")]
+    pub fn remove_expired_session_keys(&self) -> PromiseKeyStoreEntries {
+        let inner = self.inner.clone();
+        let state = self.state.clone();
+
+        JsCast::unchecked_into(future_to_promise(async move {
+            let now = nt_utils::now_ms_u64();
+            let expired = inner
+                .get_entries()
+                .await
+                .into_iter()
+                .filter(
+                    |entry| match state.metadata.get(&entry.public_key).session {
+                        Some(session) => {
+                            session.is_expired(now) || !state.sessions.contains(&entry.public_key)
+                        }
+                        None => false,
+                    },
+                )
+                .collect::<Vec<_>>();
+
+            if expired.is_empty() {
+                return Ok(js_sys::Array::new().unchecked_into());
+            }
+
+            let revision = state.revision.begin().await.handle_error()?;
+
+            let mut removed = Vec::with_capacity(expired.len());
+            for entry in expired {
+                let public_key = entry.public_key;
+                if inner
+                    .remove_key(&public_key)
+                    .await
+                    .handle_error()?
+                    .is_none()
+                {
+                    continue;
+                }
+
+                removed.push(make_key_store_entry(entry, &state));
+                state.sessions.remove(&public_key);
+                state
+                    .metadata
+                    .remove(revision.lock(), &public_key)
+                    .await
+                    .handle_error()?;
+            }
+            revision.commit().await.handle_error()?;
+
+            for entry in &removed {
+                state.notify(|handler| handler.on_key_removed(entry.clone()));
+            }
+
+            Ok(removed
+                .into_iter()
+                .map(JsValue::from)
+                .collect::<js_sys::Array>()
+                .unchecked_into())
+        }))
+    }
+
+    #[wasm_bindgen(js_name = "getAuditLog")]
+    pub fn get_audit_log(
+        &self,
+        filter: Option<JsAuditLogFilter>,
+    ) -> Result<PromiseAuditLog, JsValue> {
+        let filter = filter
+            .map(|filter| JsValue::into_serde::<ParsedAuditLogFilter>(&filter))
+            .transpose()
+            .handle_error()?
+            .unwrap_or_default();
+        let public_key = filter
+            .public_key
+            .as_deref()
+            .map(parse_public_key)
+            .transpose()?
+            .map(|public_key| hex::encode(public_key.as_bytes()));
+
+        let state = self.state.clone();
+
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let log = state.audit_log.load().await.handle_error()?;
+            let invalid_entry = log.find_invalid_entry();
+
+            let entries = log
+                .entries
+                .into_iter()
+                .filter(|entry| {
+                    let record = &entry.record;
+                    public_key
+                        .as_ref()
+                        .map_or(true, |key| *key == record.public_key)
+                        && filter
+                            .operation
+                            .map_or(true, |operation| operation == record.operation)
+                        && (filter.origin.is_none() || filter.origin == record.origin)
+                        && filter.since.map_or(true, |since| record.timestamp >= since)
+                        && filter.until.map_or(true, |until| record.timestamp < until)
+                })
+                .skip(filter.offset.unwrap_or_default())
+                .take(filter.limit.unwrap_or(usize::MAX))
+                .map(|entry| JsValue::from_serde(&entry).trust_me())
+                .collect::<js_sys::Array>();
+
+            Ok(ObjectBuilder::new()
+                .set("entries", entries)
+                .set("verified", invalid_entry.is_none())
+                .set("invalidIndex", invalid_entry.map(|index| index as f64))
+                .set("firstIndex", log.head.first_index as f64)
+                .build())
+        })))
+    }
+
     #[wasm_bindgen(js_name = "getKeys")]
-    pub fn get_stored_keys(&self) -> PromiseKeyStoreEntries {
+    pub fn get_stored_keys(&self, filter: Option<JsKeysFilter>) -> PromiseKeyStoreEntries {
+        let filter = filter.map(|filter| JsValue::into_serde::<ParsedKeysFilter>(&filter));
+
         let inner = self.inner.clone();
+        let state = self.state.clone();
 
         JsCast::unchecked_into(future_to_promise(async move {
-            let keys = inner.get_entries().await;
+            let filter = filter.transpose().handle_error()?.unwrap_or_default();
+
+            let mut keys = inner
+                .get_entries()
+                .await
+                .into_iter()
+                .map(|entry| {
+                    let metadata = state.metadata.get(&entry.public_key);
+                    (entry, metadata)
+                })
+                .filter(|(_, metadata)| match &filter.tag {
+                    Some(tag) => metadata.tags.contains(tag),
+                    None => true,
+                })
+                .collect::<Vec<_>>();
+            keys.sort_by_key(|(_, metadata)| metadata.order.unwrap_or(u32::MAX));
 
             Ok(keys
-                .iter()
-                .cloned()
-                .map(make_key_store_entry)
+                .into_iter()
+                .map(|(entry, _)| make_key_store_entry(entry, &state))
                 .map(JsValue::from)
                 .collect::<js_sys::Array>()
                 .unchecked_into())
@@ -505,13 +1732,330 @@ impl KeyStore {
     }
 }
 
+fn audit_message_transfers(
+    message: &crate::crypto::UnsignedMessage,
+) -> (Option<String>, Option<String>) {
+    match SessionSignRequest::from_message(message) {
+        Ok(request) if !request.transfers.is_empty() => {
+            let destination = request
+                .transfers
+                .iter()
+                .map(|(destination, _)| destination.as_str())
+                .collect::<Vec<_>>()
+                .join(",");
+            (Some(destination), Some(request.total_value().to_string()))
+        }
+        _ => (None, None),
+    }
+}
+
+/// Resolves the public key of the wallet owner from its state.
+/// Multisig wallets use their deployer key while it is still a custodian
+async fn get_wallet_public_key(
+    clock: &dyn nt::utils::Clock,
+    transport: &dyn nt::transport::Transport,
+    address: &ton_block::MsgAddressInt,
+) -> Result<ed25519_dalek::PublicKey, JsValue> {
+    let contract = match transport.get_contract_state(address).await.handle_error()? {
+        nt::transport::models::RawContractState::Exists(contract) => contract,
+        nt::transport::models::RawContractState::NotExists => {
+            return Err(EncryptedCommentError::RecipientNotDeployed).handle_error()
+        }
+    };
+
+    let (public_key, wallet_type) =
+        nt::core::ton_wallet::extract_wallet_init_data(&contract).handle_error()?;
+    let custodians =
+        nt::core::ton_wallet::get_wallet_custodians(clock, &contract, &public_key, wallet_type)
+            .handle_error()?;
+
+    match custodians.first() {
+        Some(custodian)
+            if !custodians
+                .iter()
+                .any(|item| item.as_slice() == public_key.as_bytes()) =>
+        {
+            ed25519_dalek::PublicKey::from_bytes(custodian.as_slice()).handle_error()
+        }
+        _ => Ok(public_key),
+    }
+}
+
+async fn update_key_metadata<F>(
+    key_store: &nt::core::keystore::KeyStore,
+    state: &KeyStoreState,
+    public_key: ed25519_dalek::PublicKey,
+    f: F,
+) -> Result<KeyStoreEntry, JsValue>
+where
+    F: FnOnce(&mut KeyMetadata),
+{
+    let entry = key_store
+        .get_entries()
+        .await
+        .into_iter()
+        .find(|entry| entry.public_key == public_key)
+        .ok_or(KeyStoreError::KeyNotFound)
+        .handle_error()?;
+
+    let revision = state.revision.begin().await.handle_error()?;
+    state
+        .metadata
+        .update(revision.lock(), &public_key, f)
+        .await
+        .handle_error()?;
+    revision.commit().await.handle_error()?;
+
+    Ok(make_key_store_entry(entry, state))
+}
+
+/// Updates the session key policy under the keystore lock,
+/// so that changes from other contexts are not lost
+async fn update_session_policy<F>(
+    state: &KeyStoreState,
+    public_key: &ed25519_dalek::PublicKey,
+    f: F,
+) -> Result<(), JsValue>
+where
+    F: FnOnce(&mut SessionKeyPolicy) -> Result<(), SessionKeyError>,
+{
+    let lock = state.revision.lock().await.handle_error()?;
+
+    let mut result = Err(SessionKeyError::NotFound);
+    state
+        .metadata
+        .update(&lock, public_key, |metadata| {
+            if let Some(session) = &mut metadata.session {
+                result = f(session);
+            }
+        })
+        .await
+        .handle_error()?;
+    drop(lock);
+
+    result.handle_error()
+}
+
+async fn find_cache_id(
+    key_store: &nt::core::keystore::KeyStore,
+    public_key: &ed25519_dalek::PublicKey,
+) -> Option<ed25519_dalek::PublicKey> {
+    key_store
+        .get_entries()
+        .await
+        .into_iter()
+        .find(|entry| &entry.public_key == public_key)
+        .map(|entry| entry.master_key)
+}
+
+/// Key with its own password. Derived keys share the password of their master key
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum PasswordTarget {
+    MasterKey(ed25519_dalek::PublicKey),
+    EncryptedKey(ed25519_dalek::PublicKey),
+}
+
+impl PasswordTarget {
+    fn cache_id(&self) -> &ed25519_dalek::PublicKey {
+        match self {
+            Self::MasterKey(public_key) | Self::EncryptedKey(public_key) => public_key,
+        }
+    }
+
+    async fn change_password(
+        &self,
+        key_store: &nt::core::keystore::KeyStore,
+        old_password: String,
+        new_password: String,
+    ) -> anyhow::Result<nt::core::keystore::KeyStoreEntry> {
+        use nt::crypto::*;
+
+        match *self {
+            Self::MasterKey(master_key) => {
+                let input = DerivedKeyUpdateParams::ChangePassword {
+                    master_key,
+                    old_password: explicit_password(old_password),
+                    new_password: explicit_password(new_password),
+                };
+                key_store.update_key::<DerivedKeySigner>(input).await
+            }
+            Self::EncryptedKey(public_key) if is_raw_key(key_store, &public_key).await => {
+                let input = RawKeyUpdateParams::ChangePassword {
+                    public_key,
+                    old_password: explicit_password(old_password),
+                    new_password: explicit_password(new_password),
+                };
+                key_store.update_key::<RawKeySigner>(input).await
+            }
+            Self::EncryptedKey(public_key) => {
+                let input = EncryptedKeyUpdateParams::ChangePassword {
+                    public_key,
+                    old_password: explicit_password(old_password),
+                    new_password: explicit_password(new_password),
+                };
+                key_store.update_key::<EncryptedKeySigner>(input).await
+            }
+        }
+    }
+}
+
+/// Re-encrypts the key with the new password and KDF settings.
+///
+/// The inner result contains the error of the core keystore, e.g. for the invalid old password
+async fn change_key_encryption(
+    key_store: &nt::core::keystore::KeyStore,
+    state: &KeyStoreState,
+    lock: &KeyStoreLock<'_>,
+    target: PasswordTarget,
+    old_password: String,
+    new_password: &str,
+    encryption: KeyEncryption,
+) -> Result<anyhow::Result<nt::core::keystore::KeyStoreEntry>, JsValue> {
+    let cache_id = target.cache_id();
+    let old_password = state.harden_password(cache_id, old_password)?;
+    let new_password = encryption.harden(new_password).handle_error()?;
+
+    state.forget_password(cache_id);
+
+    // New settings are written in the same update as the re-encrypted key
+    let _staged = state.storage.stage(lock, |settings| {
+        settings.insert(hex::encode(cache_id.as_bytes()), encryption);
+    });
+    Ok(target
+        .change_password(key_store, old_password, new_password)
+        .await)
+}
+
+/// Signers derive keys from english phrases only, so the phrases from
+/// other wordlists can't be imported as master keys
+async fn add_master_key(
+    key_store: &nt::core::keystore::KeyStore,
+    name: Option<String>,
+    phrase: &str,
+    language: Bip39Language,
+    password: nt::crypto::Password,
+) -> anyhow::Result<nt::crypto::SignerEntry> {
+    use nt::crypto::*;
+
+    match prepare_import_phrase(phrase, language)? {
+        ImportPhrase::English(phrase) => {
+            let input = DerivedKeyCreateInput::Import {
+                key_name: name,
+                phrase: phrase.into(),
+                password,
+            };
+            key_store.add_key::<DerivedKeySigner>(input).await
+        }
+        ImportPhrase::Native { .. } => Err(KeyStoreError::NativeMasterKeyNotSupported.into()),
+    }
+}
+
+/// Signers derive keys from english phrases only, so for the phrases
+/// from other wordlists only the key of the imported account is stored.
+///
+/// NOTE: such keys are listed as `encrypted_key`, since no other accounts can be derived
+async fn add_native_phrase_key(
+    key_store: &nt::core::keystore::KeyStore,
+    name: Option<String>,
+    phrase: String,
+    language: Bip39Language,
+    mnemonic_type: nt::crypto::MnemonicType,
+    password: nt::crypto::Password,
+) -> anyhow::Result<nt::crypto::SignerEntry> {
+    use nt::crypto::*;
+
+    let account_id = match mnemonic_type {
+        MnemonicType::Labs(account_id) => account_id,
+        MnemonicType::Legacy => return Err(MnemonicError::UnsupportedLanguage.into()),
+    };
+
+    let input = RawKeyCreateInput {
+        name,
+        secret: phrase.into_bytes(),
+        format: RawKeyFormat::Phrase {
+            language,
+            account_id,
+        },
+        password,
+    };
+    key_store.add_key::<RawKeySigner>(input).await
+}
+
+/// Raw keys share the `encrypted_key` API with the keys of `EncryptedKeySigner`
+async fn is_raw_key(
+    key_store: &nt::core::keystore::KeyStore,
+    public_key: &ed25519_dalek::PublicKey,
+) -> bool {
+    key_store
+        .get_entries()
+        .await
+        .into_iter()
+        .any(|entry| entry.signer_name == RAW_SIGNER && &entry.public_key == public_key)
+}
+
+fn join_public_keys(public_keys: &[ed25519_dalek::PublicKey]) -> String {
+    public_keys
+        .iter()
+        .map(|public_key| hex::encode(public_key.as_bytes()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub async fn get_derived_public_keys(
+    key_store: &nt::core::keystore::KeyStore,
+    state: &KeyStoreState,
+    master_key: ed25519_dalek::PublicKey,
+    password: Option<String>,
+    cache: bool,
+    offset: u16,
+    limit: u16,
+) -> Result<Vec<ed25519_dalek::PublicKey>, JsValue> {
+    use nt::crypto::*;
+
+    let (password, action) = state
+        .prepare_password(key_store, &master_key, password, cache)
+        .await?;
+
+    let input = DerivedKeyGetPublicKeys {
+        master_key,
+        password,
+        limit,
+        offset,
+    };
+    let public_keys = key_store
+        .get_public_keys::<DerivedKeySigner>(input)
+        .await
+        .handle_error()?;
+
+    state.commit_password(&master_key, action);
+    Ok(public_keys)
+}
+
+fn verify_signature(
+    public_key: &ed25519_dalek::PublicKey,
+    data: &[u8],
+    signature: [u8; 64],
+) -> Result<[u8; 64], JsValue> {
+    use ed25519_dalek::Verifier;
+
+    match public_key.verify(data, &ed25519_dalek::Signature::from(signature)) {
+        Ok(()) => Ok(signature),
+        Err(_) => Err(KeyStoreError::InvalidSignature).handle_error(),
+    }
+}
+
 async fn sign_data(
     key_store: &nt::core::keystore::KeyStore,
+    state: &KeyStoreState,
     key_password: ParsedKeyPassword,
     data: &[u8],
+    signature_id: Option<i32>,
+    ledger_context: Option<crate::external::ParsedLedgerSigningContext>,
 ) -> Result<[u8; 64], JsValue> {
     use nt::crypto::*;
 
+    let extended = crate::crypto::extend_with_signature_id(data, signature_id);
+
     match key_password {
         ParsedKeyPassword::MasterKey {
             master_key,
@@ -519,12 +2063,24 @@ async fn sign_data(
             password,
             cache,
         } => {
+            let master_key = parse_public_key(&master_key)?;
+            let public_key = parse_public_key(&public_key)?;
+            let (password, action) = state
+                .prepare_password(key_store, &master_key, password, cache)
+                .await?;
+
             let input = DerivedKeySignParams::ByPublicKey {
-                public_key: parse_public_key(&public_key)?,
-                master_key: parse_public_key(&master_key)?,
-                password: cached_password(password, cache),
+                public_key,
+                master_key,
+                password,
             };
-            key_store.sign::<DerivedKeySigner>(data, input).await
+            let signature = key_store
+                .sign::<DerivedKeySigner>(&extended, input)
+                .await
+                .handle_error()?;
+
+            state.commit_password(&master_key, action);
+            Ok(signature)
         }
         ParsedKeyPassword::EncryptedKey {
             public_key,
@@ -532,24 +2088,141 @@ async fn sign_data(
             cache,
         } => {
             let public_key = parse_public_key(&public_key)?;
+            let (password, action) = state
+                .prepare_password(key_store, &public_key, password, cache)
+                .await?;
+
             let input = EncryptedKeyPassword {
                 public_key,
-                password: cached_password(password, cache),
+                password,
             };
-            key_store.sign::<EncryptedKeySigner>(data, input).await
+            let signature = if is_raw_key(key_store, &public_key).await {
+                key_store.sign::<RawKeySigner>(&extended, input).await
+            } else {
+                key_store.sign::<EncryptedKeySigner>(&extended, input).await
+            }
+            .handle_error()?;
+
+            state.commit_password(&public_key, action);
+            Ok(signature)
+        }
+        ParsedKeyPassword::LedgerKey(input) => {
+            // NOTE: signer is bypassed, because it can't pass the device and the detailed
+            // context. Device app signs only 32-byte hashes, so it can't use the signature id
+            if signature_id.is_some() {
+                return Err(KeyStoreError::LedgerSignatureIdNotSupported).handle_error();
+            }
+
+            let account_id = key_store
+                .get_entries()
+                .await
+                .into_iter()
+                .find(|entry| {
+                    entry.signer_name == LEDGER_SIGNER && entry.public_key == input.public_key
+                })
+                .map(|entry| entry.account_id)
+                .ok_or(KeyStoreError::KeyNotFound)
+                .handle_error()?;
+            let device = state.metadata.get(&input.public_key).ledger_device;
+
+            let signature = state
+                .ledger_connection
+                .sign_with_context(
+                    account_id,
+                    device,
+                    data,
+                    input.context.as_ref(),
+                    ledger_context,
+                )
+                .await
+                .handle_error()?;
+
+            // Device must sign exactly what the other signers would
+            verify_signature(&input.public_key, data, signature)
+        }
+    }
+}
+
+async fn encrypt_data(
+    key_store: &nt::core::keystore::KeyStore,
+    state: &KeyStoreState,
+    data: &[u8],
+    key_password: ParsedKeyPassword,
+    public_keys: &[ed25519_dalek::PublicKey],
+    algorithm: nt::crypto::EncryptionAlgorithm,
+) -> Result<Vec<nt::crypto::EncryptedData>, JsValue> {
+    use nt::crypto::*;
+
+    match key_password {
+        ParsedKeyPassword::MasterKey {
+            master_key,
+            public_key,
+            password,
+            cache,
+        } => {
+            let master_key = parse_public_key(&master_key)?;
+            let public_key = parse_public_key(&public_key)?;
+            let (password, action) = state
+                .prepare_password(key_store, &master_key, password, cache)
+                .await?;
+
+            let input = DerivedKeySignParams::ByPublicKey {
+                master_key,
+                public_key,
+                password,
+            };
+            let encrypted = key_store
+                .encrypt::<DerivedKeySigner>(data, public_keys, algorithm, input)
+                .await
+                .handle_error()?;
+
+            state.commit_password(&master_key, action);
+            Ok(encrypted)
+        }
+        ParsedKeyPassword::EncryptedKey {
+            public_key,
+            password,
+            cache,
+        } => {
+            let public_key = parse_public_key(&public_key)?;
+            let (password, action) = state
+                .prepare_password(key_store, &public_key, password, cache)
+                .await?;
+
+            let input = EncryptedKeyPassword {
+                public_key,
+                password,
+            };
+            let encrypted = if is_raw_key(key_store, &public_key).await {
+                key_store
+                    .encrypt::<RawKeySigner>(data, public_keys, algorithm, input)
+                    .await
+            } else {
+                key_store
+                    .encrypt::<EncryptedKeySigner>(data, public_keys, algorithm, input)
+                    .await
+            }
+            .handle_error()?;
+
+            state.commit_password(&public_key, action);
+            Ok(encrypted)
+        }
+        ParsedKeyPassword::LedgerKey(input) => {
+            state.check_ledger_device(&input.public_key).await?;
+            key_store
+                .encrypt::<LedgerKeySigner>(data, public_keys, algorithm, input)
+                .await
+                .handle_error()
         }
-        ParsedKeyPassword::LedgerKey(input) => key_store.sign::<LedgerKeySigner>(data, input).await,
     }
-    .handle_error()
 }
 
-async fn encrypt_data(
+async fn decrypt_data(
     key_store: &nt::core::keystore::KeyStore,
-    data: &[u8],
+    state: &KeyStoreState,
+    data: nt::crypto::EncryptedData,
     key_password: ParsedKeyPassword,
-    public_keys: &[ed25519_dalek::PublicKey],
-    algorithm: nt::crypto::EncryptionAlgorithm,
-) -> Result<Vec<nt::crypto::EncryptedData>, JsValue> {
+) -> Result<Vec<u8>, JsValue> {
     use nt::crypto::*;
 
     match key_password {
@@ -559,74 +2232,158 @@ async fn encrypt_data(
             password,
             cache,
         } => {
+            let master_key = parse_public_key(&master_key)?;
+            let public_key = parse_public_key(&public_key)?;
+            let (password, action) = state
+                .prepare_password(key_store, &master_key, password, cache)
+                .await?;
+
             let input = DerivedKeySignParams::ByPublicKey {
-                master_key: parse_public_key(&public_key)?,
-                public_key: parse_public_key(&master_key)?,
-                password: cached_password(password, cache),
+                master_key,
+                public_key,
+                password,
             };
-            key_store
-                .encrypt::<DerivedKeySigner>(data, public_keys, algorithm, input)
+            let decrypted = key_store
+                .decrypt::<DerivedKeySigner>(&data, input)
                 .await
+                .handle_error()?;
+
+            state.commit_password(&master_key, action);
+            Ok(decrypted)
         }
         ParsedKeyPassword::EncryptedKey {
             public_key,
             password,
             cache,
         } => {
+            let public_key = parse_public_key(&public_key)?;
+            let (password, action) = state
+                .prepare_password(key_store, &public_key, password, cache)
+                .await?;
+
             let input = EncryptedKeyPassword {
-                public_key: parse_public_key(&public_key)?,
-                password: cached_password(password, cache),
+                public_key,
+                password,
             };
-            key_store
-                .encrypt::<EncryptedKeySigner>(data, public_keys, algorithm, input)
-                .await
+            let decrypted = if is_raw_key(key_store, &public_key).await {
+                key_store.decrypt::<RawKeySigner>(&data, input).await
+            } else {
+                key_store.decrypt::<EncryptedKeySigner>(&data, input).await
+            }
+            .handle_error()?;
+
+            state.commit_password(&public_key, action);
+            Ok(decrypted)
         }
         ParsedKeyPassword::LedgerKey(input) => {
+            state.check_ledger_device(&input.public_key).await?;
             key_store
-                .encrypt::<LedgerKeySigner>(data, public_keys, algorithm, input)
+                .decrypt::<LedgerKeySigner>(&data, input)
                 .await
+                .handle_error()
         }
     }
-    .handle_error()
 }
 
-async fn decrypt_data(
+/// Exports the account keypair for the algorithms which the core signers don't support.
+///
+/// Core signers can only export the whole phrase, so the account secret is derived here
+/// and the phrase is dropped right away. Secret key is zeroized when the keypair is dropped
+async fn export_keypair(
     key_store: &nt::core::keystore::KeyStore,
-    data: nt::crypto::EncryptedData,
+    state: &KeyStoreState,
     key_password: ParsedKeyPassword,
-) -> Result<Vec<u8>, JsValue> {
+    origin: Option<String>,
+) -> Result<ed25519_dalek::Keypair, JsValue> {
     use nt::crypto::*;
+    use zeroize::Zeroize;
 
-    match key_password {
+    // Secret leaves the signer, so it is recorded as an export
+    let record = key_password.audit_record(AuditOperation::ExportKey, origin)?;
+
+    let (keypair, cache_id, action) = match key_password {
         ParsedKeyPassword::MasterKey {
             master_key,
             public_key,
             password,
             cache,
         } => {
-            let input = DerivedKeySignParams::ByPublicKey {
-                master_key: parse_public_key(&public_key)?,
-                public_key: parse_public_key(&master_key)?,
-                password: cached_password(password, cache),
+            let master_key = parse_public_key(&master_key)?;
+            let public_key = parse_public_key(&public_key)?;
+            let account_id = key_store
+                .get_entries()
+                .await
+                .into_iter()
+                .find(|entry| {
+                    entry.signer_name == DERIVED_SIGNER
+                        && entry.master_key == master_key
+                        && entry.public_key == public_key
+                })
+                .map(|entry| entry.account_id)
+                .ok_or(KeyStoreError::KeyNotFound)
+                .handle_error()?;
+            let (password, action) = state
+                .prepare_password(key_store, &master_key, password, cache)
+                .await?;
+
+            let input = DerivedKeyExportParams {
+                master_key,
+                password,
             };
-            key_store.decrypt::<DerivedKeySigner>(&data, input).await
+            let output = key_store
+                .export_key::<DerivedKeySigner>(input)
+                .await
+                .handle_error()?;
+            let keypair =
+                derive_from_phrase(output.phrase.unsecure(), MnemonicType::Labs(account_id))
+                    .handle_error()?;
+            (keypair, master_key, action)
         }
         ParsedKeyPassword::EncryptedKey {
             public_key,
             password,
             cache,
         } => {
+            let public_key = parse_public_key(&public_key)?;
+            let (password, action) = state
+                .prepare_password(key_store, &public_key, password, cache)
+                .await?;
+
             let input = EncryptedKeyPassword {
-                public_key: parse_public_key(&public_key)?,
-                password: cached_password(password, cache),
+                public_key,
+                password,
+            };
+            let keypair = if is_raw_key(key_store, &public_key).await {
+                let mut output = key_store
+                    .export_key::<RawKeySigner>(input)
+                    .await
+                    .handle_error()?;
+                let secret = ed25519_dalek::SecretKey::from_bytes(&output.secret);
+                output.secret.zeroize();
+                output.phrase.zeroize();
+                let secret = secret.handle_error()?;
+                ed25519_dalek::Keypair {
+                    public: ed25519_dalek::PublicKey::from(&secret),
+                    secret,
+                }
+            } else {
+                let output = key_store
+                    .export_key::<EncryptedKeySigner>(input)
+                    .await
+                    .handle_error()?;
+                derive_from_phrase(output.phrase.unsecure(), output.mnemonic_type).handle_error()?
             };
-            key_store.decrypt::<EncryptedKeySigner>(&data, input).await
+            (keypair, public_key, action)
         }
-        ParsedKeyPassword::LedgerKey(input) => {
-            key_store.decrypt::<LedgerKeySigner>(&data, input).await
+        // Ledger doesn't export the secret key
+        ParsedKeyPassword::LedgerKey(_) => {
+            return Err(encryption::EncryptionError::UnsupportedAlgorithm).handle_error()
         }
-    }
-    .handle_error()
+    };
+
+    state.commit_password(&cache_id, action);
+    state.audit(record).await;
+    Ok(keypair)
 }
 
 #[wasm_bindgen]
@@ -649,6 +2406,12 @@ extern "C" {
     #[wasm_bindgen(typescript_type = "Promise<SignedDataRaw>")]
     pub type PromiseSignedDataRaw;
 
+    #[wasm_bindgen(typescript_type = "Promise<SignedTypedData>")]
+    pub type PromiseSignedTypedData;
+
+    #[wasm_bindgen(typescript_type = "Promise<OwnershipProof>")]
+    pub type PromiseOwnershipProof;
+
     #[wasm_bindgen(typescript_type = "Promise<Array<KeyStoreEntry>>")]
     pub type PromiseKeyStoreEntries;
 
@@ -660,6 +2423,12 @@ extern "C" {
 
     #[wasm_bindgen(typescript_type = "Promise<KeyStore>")]
     pub type PromiseKeyStore;
+
+    #[wasm_bindgen(typescript_type = "Promise<KeyStoreVerification>")]
+    pub type PromiseKeyStoreVerification;
+
+    #[wasm_bindgen(typescript_type = "Promise<AuditLog>")]
+    pub type PromiseAuditLog;
 }
 
 fn parse_public_key_list(
@@ -684,8 +2453,7 @@ fn parse_public_key_list(
 
 #[wasm_bindgen(typescript_custom_section)]
 const ENCRYPTION_ALGORITHM: &str = r#"
-export type EncryptionAlgorithm =
-    | 'ChaCha20Poly1305';
+export type EncryptionAlgorithm = 'ChaCha20Poly1305' | 'XSalsa20Poly1305' | 'AES256GCM';
 "#;
 
 #[wasm_bindgen(typescript_custom_section)]
@@ -696,31 +2464,114 @@ export type EncryptedData = {
     recipientPublicKey: string;
     data: string;
     nonce: string;
+    /**
+     * Content key sealed for the recipient with NaCl box (only for `AES256GCM`).
+     * Content is encrypted once, so `data` and `nonce` are the same for all recipients
+     */
+    wrappedKey?: WrappedKey;
+};
+
+export type WrappedKey = {
+    data: string;
+    nonce: string;
 };
 "#;
 
-pub fn parse_encrypted_data(data: EncryptedData) -> Result<nt::crypto::EncryptedData, JsValue> {
-    JsValue::into_serde::<nt::crypto::EncryptedData>(&data).handle_error()
+pub enum ParsedEncryptedData {
+    Native(nt::crypto::EncryptedData),
+    Sealed(SealedData),
+}
+
+pub fn parse_encrypted_data(data: EncryptedData) -> Result<ParsedEncryptedData, JsValue> {
+    use std::str::FromStr;
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ParsedData {
+        algorithm: String,
+        source_public_key: String,
+        recipient_public_key: String,
+        data: String,
+        nonce: String,
+        #[serde(default)]
+        wrapped_key: Option<ParsedWrappedKey>,
+    }
+
+    #[derive(Deserialize)]
+    struct ParsedWrappedKey {
+        data: String,
+        nonce: String,
+    }
+
+    let parsed = JsValue::into_serde::<ParsedData>(&data).handle_error()?;
+    let algorithm = EncryptionAlgorithm::from_str(&parsed.algorithm).handle_error()?;
+    if algorithm.is_native() {
+        return JsValue::into_serde::<nt::crypto::EncryptedData>(&data)
+            .handle_error()
+            .map(ParsedEncryptedData::Native);
+    }
+
+    Ok(ParsedEncryptedData::Sealed(SealedData {
+        algorithm,
+        source_public_key: parse_public_key(&parsed.source_public_key)?,
+        recipient_public_key: parse_public_key(&parsed.recipient_public_key)?,
+        data: base64::decode(parsed.data).handle_error()?,
+        nonce: base64::decode(parsed.nonce).handle_error()?,
+        wrapped_key: parsed
+            .wrapped_key
+            .map(|wrapped_key| {
+                Ok::<_, JsValue>(WrappedKey {
+                    data: base64::decode(wrapped_key.data).handle_error()?,
+                    nonce: base64::decode(wrapped_key.nonce).handle_error()?,
+                })
+            })
+            .transpose()?,
+    }))
 }
 
-pub fn make_encrypted_data(encrypted_data: nt::crypto::EncryptedData) -> EncryptedData {
+pub fn make_encrypted_data(data: nt::crypto::EncryptedData) -> EncryptedData {
+    let algorithm = match data.algorithm {
+        nt::crypto::EncryptionAlgorithm::ChaCha20Poly1305 => EncryptionAlgorithm::ChaCha20Poly1305,
+    };
+
     ObjectBuilder::new()
+        .set("algorithm", algorithm.as_str())
         .set(
-            "algorithm",
-            match encrypted_data.algorithm {
-                nt::crypto::EncryptionAlgorithm::ChaCha20Poly1305 => "ChaCha20Poly1305",
-            },
+            "sourcePublicKey",
+            hex::encode(data.source_public_key.as_bytes()),
+        )
+        .set(
+            "recipientPublicKey",
+            hex::encode(data.recipient_public_key.as_bytes()),
         )
+        .set("data", base64::encode(data.data))
+        .set("nonce", base64::encode(data.nonce))
+        .build()
+        .unchecked_into()
+}
+
+fn make_sealed_data(data: SealedData) -> EncryptedData {
+    ObjectBuilder::new()
+        .set("algorithm", data.algorithm.as_str())
         .set(
             "sourcePublicKey",
-            hex::encode(encrypted_data.source_public_key.as_bytes()),
+            hex::encode(data.source_public_key.as_bytes()),
         )
         .set(
             "recipientPublicKey",
-            hex::encode(encrypted_data.recipient_public_key.as_bytes()),
+            hex::encode(data.recipient_public_key.as_bytes()),
+        )
+        .set("data", base64::encode(data.data))
+        .set("nonce", base64::encode(data.nonce))
+        .set(
+            "wrappedKey",
+            data.wrapped_key.map(|wrapped_key| {
+                ObjectBuilder::new()
+                    .set("data", base64::encode(wrapped_key.data))
+                    .set("nonce", base64::encode(wrapped_key.nonce))
+                    .build()
+            }),
         )
-        .set("data", base64::encode(encrypted_data.data))
-        .set("nonce", base64::encode(encrypted_data.nonce))
         .build()
         .unchecked_into()
 }
@@ -728,12 +2579,19 @@ pub fn make_encrypted_data(encrypted_data: nt::crypto::EncryptedData) -> Encrypt
 const DERIVED_SIGNER: &str = "master_key";
 const ENCRYPTED_SIGNER: &str = "encrypted_key";
 const LEDGER_SIGNER: &str = "ledger_key";
+/// Raw keys are exposed as `encrypted_key`
+const RAW_SIGNER: &str = "raw_key";
 
 #[wasm_bindgen(typescript_custom_section)]
 const NEW_KEY: &str = r#"
 export type NewKey =
-    | EnumItem<'master_key', { name?: string, params: MasterKeyParams | DerivedKeyParams, password: string }>
-    | EnumItem<'encrypted_key', { name?: string, phrase: string, mnemonicType: MnemonicType, password: string }>
+    | EnumItem<'master_key', { name?: string, params: MasterKeyParams | DerivedKeyParams | MasterKeySharesParams, password: string }>
+    | EnumItem<'encrypted_key', { name?: string, password: string } & (
+        | { phrase: string, mnemonicType: MnemonicType, language?: Bip39Language }
+        | { shares: string[], mnemonicType: MnemonicType, language?: Bip39Language }
+        | { secretKey: string }
+        | { keypair: RawKeypair }
+    )>
     | EnumItem<'ledger_key', { name?: string, accountId: number }>;
 "#;
 
@@ -758,8 +2616,14 @@ enum ParsedNewKey {
     EncryptedKey {
         #[serde(default)]
         name: Option<String>,
-        phrase: String,
-        mnemonic_type: crate::crypto::ParsedMnemonicType,
+        #[serde(flatten)]
+        secret: ParsedEncryptedKeySecret,
+        /// Required for the phrase and shares
+        #[serde(default)]
+        mnemonic_type: Option<crate::crypto::ParsedMnemonicType>,
+        /// Wordlist of the phrase and shares, english by default
+        #[serde(default)]
+        language: Bip39Language,
         password: String,
     },
     #[serde(rename_all = "camelCase")]
@@ -772,17 +2636,71 @@ enum ParsedNewKey {
 
 #[wasm_bindgen(typescript_custom_section)]
 const NEW_MASTER_KEY_PARAMS: &str = r#"
-export type MasterKeyParams = { phrase: string };
+export type MasterKeyParams = { phrase: string, language?: Bip39Language };
 export type DerivedKeyParams = { masterKey: string, accountId: number };
+export type MasterKeySharesParams = { shares: string[], language?: Bip39Language };
 "#;
 
 #[derive(Deserialize)]
 #[serde(untagged)]
 enum ParsedNewMasterKeyParams {
     #[serde(rename_all = "camelCase")]
-    MasterKeyParams { phrase: String },
+    MasterKeyParams {
+        phrase: String,
+        #[serde(default)]
+        language: Bip39Language,
+    },
     #[serde(rename_all = "camelCase")]
     DerivedKeyParams { master_key: String, account_id: u16 },
+    #[serde(rename_all = "camelCase")]
+    MasterKeySharesParams {
+        shares: Vec<String>,
+        #[serde(default)]
+        language: Bip39Language,
+    },
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const RAW_KEYPAIR: &str = r#"
+export type RawKeypair = { public: string, secret: string };
+"#;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ParsedEncryptedKeySecret {
+    Phrase {
+        phrase: String,
+    },
+    Shares {
+        shares: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    SecretKey {
+        secret_key: String,
+    },
+    Keypair {
+        keypair: RawKeypair,
+    },
+}
+
+impl ParsedEncryptedKeySecret {
+    fn import(self) -> anyhow::Result<ImportedSecret> {
+        Ok(match self {
+            Self::Phrase { phrase } => ImportedSecret::Phrase(phrase),
+            Self::Shares { shares } => ImportedSecret::Phrase(combine_shares(&shares)?),
+            Self::SecretKey { secret_key } => {
+                ImportedSecret::RawKey(parse_secret_key(&secret_key)?, RawKeyFormat::Hex)
+            }
+            Self::Keypair { keypair } => {
+                ImportedSecret::RawKey(parse_keypair(&keypair)?, RawKeyFormat::Keypair)
+            }
+        })
+    }
+}
+
+enum ImportedSecret {
+    Phrase(String),
+    RawKey(ed25519_dalek::SecretKey, RawKeyFormat),
 }
 
 #[wasm_bindgen(typescript_custom_section)]
@@ -872,11 +2790,27 @@ enum ParsedExportKey {
     },
 }
 
+impl ParsedExportKey {
+    fn audit_record(&self, origin: Option<String>) -> Result<AuditRecord, JsValue> {
+        let (public_key, signer) = match self {
+            Self::MasterKey { master_key, .. } => (master_key, DERIVED_SIGNER),
+            Self::EncryptedKey { public_key, .. } => (public_key, ENCRYPTED_SIGNER),
+        };
+        let public_key = parse_public_key(public_key)?;
+        Ok(AuditRecord::new(
+            AuditOperation::ExportKey,
+            &public_key,
+            signer,
+            origin,
+        ))
+    }
+}
+
 #[wasm_bindgen(typescript_custom_section)]
 const GET_PUBLIC_KEYS: &str = r#"
 export type GetPublicKeys =
     | EnumItem<'master_key', { masterKey: string, password?: string, cache?: boolean, offset: number, limit: number }>
-    | EnumItem<'ledger_key', { offset: number, limit: number }>;
+    | EnumItem<'ledger_key', { offset: number, limit: number, device?: string }>;
 "#;
 
 #[wasm_bindgen]
@@ -892,13 +2826,19 @@ enum ParsedGetPublicKeys {
     MasterKey {
         master_key: String,
         password: Option<String>,
-        #[serde(default, deserialize_with = "deserialize_cache_behavior")]
-        cache: nt::crypto::PasswordCacheBehavior,
+        #[serde(default)]
+        cache: bool,
         offset: u16,
         limit: u16,
     },
     #[serde(rename_all = "camelCase")]
-    LedgerKey { offset: u16, limit: u16 },
+    LedgerKey {
+        offset: u16,
+        limit: u16,
+        /// Fingerprint of the device to query the keys from
+        #[serde(default)]
+        device: Option<String>,
+    },
 }
 
 #[wasm_bindgen]
@@ -920,7 +2860,9 @@ fn make_public_keys_list(public_keys: Vec<ed25519_dalek::PublicKey>) -> JsValue
 const EXPORTED_KEY: &str = r#"
 export type ExportedKey =
     | { type: 'master_key', phrase: string  }
-    | { type: 'encrypted_key', phrase: string, mnemonicType: MnemonicType };
+    | { type: 'encrypted_key', phrase: string, mnemonicType: MnemonicType }
+    | { type: 'encrypted_key', secretKey: string }
+    | { type: 'encrypted_key', keypair: RawKeypair };
 "#;
 
 #[wasm_bindgen]
@@ -932,6 +2874,86 @@ extern "C" {
     pub type PromiseExportedKey;
 }
 
+#[wasm_bindgen(typescript_custom_section)]
+const EXPORTED_KEY_SHARES: &str = r#"
+export type ExportedKeyShares =
+    | { type: 'master_key', shares: string[] }
+    | { type: 'encrypted_key', shares: string[], mnemonicType: MnemonicType };
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Promise<ExportedKeyShares>")]
+    pub type PromiseExportedKeyShares;
+}
+
+enum ExportedPhrase {
+    MasterKey(nt::crypto::DerivedKeyExportOutput),
+    EncryptedKey(nt::crypto::EncryptedKeyExportOutput),
+    RawKey(RawKeyExportOutput),
+}
+
+impl ExportedPhrase {
+    fn phrase(&self) -> Result<&str, JsValue> {
+        match self {
+            Self::MasterKey(output) => Ok(output.phrase.unsecure()),
+            Self::EncryptedKey(output) => Ok(output.phrase.unsecure()),
+            Self::RawKey(_) => Err(KeyStoreError::RawKeySharesNotSupported).handle_error(),
+        }
+    }
+}
+
+async fn export_phrase(
+    key_store: &nt::core::keystore::KeyStore,
+    state: &KeyStoreState,
+    export_key: ParsedExportKey,
+) -> Result<ExportedPhrase, JsValue> {
+    use nt::crypto::*;
+
+    match export_key {
+        ParsedExportKey::MasterKey {
+            master_key,
+            password,
+        } => {
+            let master_key = parse_public_key(&master_key)?;
+            let password = state.harden_password(&master_key, password)?;
+
+            let input = DerivedKeyExportParams {
+                master_key,
+                password: explicit_password(password),
+            };
+            key_store
+                .export_key::<DerivedKeySigner>(input)
+                .await
+                .map(ExportedPhrase::MasterKey)
+        }
+        ParsedExportKey::EncryptedKey {
+            public_key,
+            password,
+        } => {
+            let public_key = parse_public_key(&public_key)?;
+            let password = state.harden_password(&public_key, password)?;
+
+            let input = EncryptedKeyPassword {
+                public_key,
+                password: explicit_password(password),
+            };
+            if is_raw_key(key_store, &public_key).await {
+                key_store
+                    .export_key::<RawKeySigner>(input)
+                    .await
+                    .map(ExportedPhrase::RawKey)
+            } else {
+                key_store
+                    .export_key::<EncryptedKeySigner>(input)
+                    .await
+                    .map(ExportedPhrase::EncryptedKey)
+            }
+        }
+    }
+    .handle_error()
+}
+
 fn make_exported_master_key(data: nt::crypto::DerivedKeyExportOutput) -> JsExportedKey {
     ObjectBuilder::new()
         .set("type", "master_key")
@@ -940,6 +2962,26 @@ fn make_exported_master_key(data: nt::crypto::DerivedKeyExportOutput) -> JsExpor
         .unchecked_into()
 }
 
+/// Raw keys are exported in the same format as they were imported
+fn make_exported_raw_key(data: RawKeyExportOutput) -> JsExportedKey {
+    let output = ObjectBuilder::new().set("type", "encrypted_key");
+    match data.format {
+        RawKeyFormat::Hex => output.set("secretKey", hex::encode(data.secret)),
+        RawKeyFormat::Keypair => output.set(
+            "keypair",
+            JsValue::from_serde(&make_keypair(&data.secret, &data.public_key)).trust_me(),
+        ),
+        RawKeyFormat::Phrase { account_id, .. } => {
+            output.set("phrase", data.phrase.unwrap_or_default()).set(
+                "mnemonicType",
+                crate::crypto::make_mnemonic_type(nt::crypto::MnemonicType::Labs(account_id)),
+            )
+        }
+    }
+    .build()
+    .unchecked_into()
+}
+
 fn make_exported_encrypted_key(data: nt::crypto::EncryptedKeyExportOutput) -> JsExportedKey {
     ObjectBuilder::new()
         .set("type", "encrypted_key")
@@ -975,19 +3017,52 @@ enum ParsedKeyPassword {
         master_key: String,
         public_key: String,
         password: Option<String>,
-        #[serde(default, deserialize_with = "deserialize_cache_behavior")]
-        cache: nt::crypto::PasswordCacheBehavior,
+        #[serde(default)]
+        cache: bool,
     },
     #[serde(rename_all = "camelCase")]
     EncryptedKey {
         public_key: String,
         password: Option<String>,
-        #[serde(default, deserialize_with = "deserialize_cache_behavior")]
-        cache: nt::crypto::PasswordCacheBehavior,
+        #[serde(default)]
+        cache: bool,
     },
     LedgerKey(nt::crypto::LedgerSignInput),
 }
 
+impl ParsedKeyPassword {
+    fn public_key(&self) -> Result<ed25519_dalek::PublicKey, JsValue> {
+        match self {
+            Self::MasterKey { public_key, .. } | Self::EncryptedKey { public_key, .. } => {
+                parse_public_key(public_key)
+            }
+            Self::LedgerKey(input) => Ok(input.public_key),
+        }
+    }
+
+    fn signer_name(&self) -> &'static str {
+        match self {
+            Self::MasterKey { .. } => DERIVED_SIGNER,
+            Self::EncryptedKey { .. } => ENCRYPTED_SIGNER,
+            Self::LedgerKey(_) => LEDGER_SIGNER,
+        }
+    }
+
+    fn audit_record(
+        &self,
+        operation: AuditOperation,
+        origin: Option<String>,
+    ) -> Result<AuditRecord, JsValue> {
+        let public_key = self.public_key()?;
+        Ok(AuditRecord::new(
+            operation,
+            &public_key,
+            self.signer_name(),
+            origin,
+        ))
+    }
+}
+
 #[wasm_bindgen(typescript_custom_section)]
 const MESSAGE: &str = r#"
 export type KeyStoreEntry = {
@@ -996,6 +3071,100 @@ export type KeyStoreEntry = {
     publicKey: string,
     masterKey: string,
     accountId: number,
+    passwordCachePolicy?: PasswordCachePolicy,
+    createdAt?: number,
+    lastUsedAt?: number,
+    tags: string[],
+    order?: number,
+    ledgerDevice?: string,
+    session?: SessionKeyInfo,
+};
+
+export type SessionKeyParams = {
+    origin: string,
+    name?: string,
+    expiresAt: number,
+    maxValuePerTransaction?: string,
+    maxTotalValue?: string,
+    allowedDestinations?: string[],
+    allowedMethods?: string[],
+};
+
+export type SessionKeyInfo = {
+    origin: string,
+    expiresAt: number,
+    maxValuePerTransaction?: string,
+    maxTotalValue?: string,
+    spent: string,
+    allowedDestinations?: string[],
+    allowedMethods?: string[],
+    unlocked: boolean,
+};
+
+export type KeysFilter = {
+    tag?: string,
+};
+
+export type AuditOperation =
+    | 'sign'
+    | 'signData'
+    | 'signDataRaw'
+    | 'signTypedData'
+    | 'signOwnershipProof'
+    | 'encryptData'
+    | 'decryptData'
+    | 'exportKey';
+
+export type AuditLogEntry = {
+    index: number,
+    operation: AuditOperation,
+    publicKey: string,
+    signer: 'master_key' | 'encrypted_key' | 'ledger_key',
+    messageHash?: string,
+    destination?: string,
+    value?: string,
+    origin?: string,
+    timestamp: number,
+    prevHash: string,
+    hash: string,
+};
+
+export type AuditLogFilter = {
+    publicKey?: string,
+    operation?: AuditOperation,
+    origin?: string,
+    since?: number,
+    until?: number,
+    offset?: number,
+    limit?: number,
+};
+
+export type AuditLog = {
+    entries: AuditLogEntry[],
+    verified: boolean,
+    invalidIndex?: number,
+    firstIndex: number,
+};
+
+export type KdfParams = {
+    memoryCost: number,
+    timeCost: number,
+    parallelism: number,
+};
+
+export type KeyStoreVerification = {
+    valid: boolean,
+    /**
+     * Public keys of the password protected keys which still use legacy KDF settings
+     */
+    legacyKeys: string[],
+};
+
+export type PasswordCachePolicy = {
+    never?: boolean,
+    lifetime?: number,
+    idleTimeout?: number,
+    maxUses?: number,
 };
 "#;
 
@@ -1003,32 +3172,180 @@ export type KeyStoreEntry = {
 extern "C" {
     #[wasm_bindgen(typescript_type = "KeyStoreEntry")]
     pub type KeyStoreEntry;
+
+    #[wasm_bindgen(typescript_type = "PasswordCachePolicy")]
+    pub type JsPasswordCachePolicy;
+
+    #[wasm_bindgen(typescript_type = "KdfParams")]
+    pub type JsKdfParams;
+
+    #[wasm_bindgen(typescript_type = "KeysFilter")]
+    pub type JsKeysFilter;
+
+    #[wasm_bindgen(typescript_type = "AuditLogFilter")]
+    pub type JsAuditLogFilter;
+
+    #[wasm_bindgen(typescript_type = "SessionKeyParams")]
+    pub type JsSessionKeyParams;
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ParsedSessionKeyParams {
+    origin: String,
+    #[serde(default)]
+    name: Option<String>,
+    expires_at: u64,
+    #[serde(default)]
+    max_value_per_transaction: Option<String>,
+    #[serde(default)]
+    max_total_value: Option<String>,
+    #[serde(default)]
+    allowed_destinations: Option<Vec<String>>,
+    #[serde(default)]
+    allowed_methods: Option<Vec<String>>,
+}
+
+impl ParsedSessionKeyParams {
+    fn into_policy(self) -> Result<SessionKeyPolicy, JsValue> {
+        use std::str::FromStr;
+
+        let parse_value = |value: Option<String>| {
+            value
+                .as_deref()
+                .map(u64::from_str)
+                .transpose()
+                .handle_error()
+        };
+
+        let allowed_destinations = self
+            .allowed_destinations
+            .map(|destinations| {
+                destinations
+                    .iter()
+                    .map(|address| parse_address(address).map(|address| address.to_string()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+
+        Ok(SessionKeyPolicy {
+            origin: self.origin,
+            expires_at: self.expires_at,
+            max_value_per_transaction: parse_value(self.max_value_per_transaction)?,
+            max_total_value: parse_value(self.max_total_value)?,
+            spent: 0,
+            allowed_destinations,
+            allowed_methods: self.allowed_methods,
+            reservations: Vec::new(),
+        })
+    }
+}
+
+fn make_session_key_info(session: SessionKeyPolicy, unlocked: bool) -> JsValue {
+    let make_list = |items: Option<Vec<String>>| {
+        items.map(|items| {
+            items
+                .into_iter()
+                .map(JsValue::from)
+                .collect::<js_sys::Array>()
+        })
+    };
+
+    ObjectBuilder::new()
+        .set("origin", session.origin)
+        .set("expiresAt", session.expires_at as f64)
+        .set(
+            "maxValuePerTransaction",
+            session
+                .max_value_per_transaction
+                .map(|value| value.to_string()),
+        )
+        .set(
+            "maxTotalValue",
+            session.max_total_value.map(|value| value.to_string()),
+        )
+        .set("spent", session.spent.to_string())
+        .set(
+            "allowedDestinations",
+            make_list(session.allowed_destinations),
+        )
+        .set("allowedMethods", make_list(session.allowed_methods))
+        .set("unlocked", unlocked)
+        .build()
+}
+
+#[derive(Default, Deserialize)]
+struct ParsedKeysFilter {
+    #[serde(default)]
+    tag: Option<String>,
 }
 
-fn make_key_store_entry(data: nt::core::keystore::KeyStoreEntry) -> KeyStoreEntry {
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ParsedAuditLogFilter {
+    #[serde(default)]
+    public_key: Option<String>,
+    #[serde(default)]
+    operation: Option<AuditOperation>,
+    #[serde(default)]
+    origin: Option<String>,
+    #[serde(default)]
+    since: Option<u64>,
+    #[serde(default)]
+    until: Option<u64>,
+    #[serde(default)]
+    offset: Option<usize>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+fn make_key_store_entry(
+    data: nt::core::keystore::KeyStoreEntry,
+    state: &KeyStoreState,
+) -> KeyStoreEntry {
+    let metadata = state.metadata.get(&data.public_key);
+
     ObjectBuilder::new()
         .set("name", data.name)
-        .set("signerName", data.signer_name)
+        .set(
+            "signerName",
+            match data.signer_name.as_str() {
+                RAW_SIGNER => ENCRYPTED_SIGNER.to_owned(),
+                _ => data.signer_name,
+            },
+        )
         .set("publicKey", hex::encode(data.public_key.as_bytes()))
         .set("masterKey", hex::encode(data.master_key.as_bytes()))
         .set("accountId", data.account_id)
+        .set(
+            "passwordCachePolicy",
+            metadata
+                .password_cache_policy
+                .map(|policy| JsValue::from_serde(&policy).trust_me()),
+        )
+        .set("createdAt", metadata.created_at.map(|time| time as f64))
+        .set("lastUsedAt", metadata.last_used_at.map(|time| time as f64))
+        .set(
+            "tags",
+            metadata
+                .tags
+                .into_iter()
+                .map(JsValue::from)
+                .collect::<js_sys::Array>(),
+        )
+        .set("order", metadata.order)
+        .set("ledgerDevice", metadata.ledger_device)
+        .set(
+            "session",
+            metadata.session.map(|session| {
+                let unlocked = state.sessions.contains(&data.public_key);
+                make_session_key_info(session, unlocked)
+            }),
+        )
         .build()
         .unchecked_into()
 }
 
-fn cached_password(
-    password: Option<String>,
-    cache_behavior: nt::crypto::PasswordCacheBehavior,
-) -> nt::crypto::Password {
-    match password {
-        Some(password) => nt::crypto::Password::Explicit {
-            password: password.into(),
-            cache_behavior,
-        },
-        None => nt::crypto::Password::FromCache,
-    }
-}
-
 fn explicit_password(password: String) -> nt::crypto::Password {
     nt::crypto::Password::Explicit {
         password: password.into(),
@@ -1036,19 +3353,26 @@ fn explicit_password(password: String) -> nt::crypto::Password {
     }
 }
 
-fn deserialize_cache_behavior<'de, D>(
-    deserializer: D,
-) -> Result<nt::crypto::PasswordCacheBehavior, <D as serde::Deserializer<'de>>::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let cache = bool::deserialize(deserializer)?;
-    Ok(if cache {
-        nt::crypto::PasswordCacheBehavior::Store(KEYSTORE_CACHE_DURATION)
-    } else {
-        nt::crypto::PasswordCacheBehavior::Remove
-    })
+const KEYSTORE_CACHE_GAP: Duration = Duration::from_secs(60); // 1 min
+
+#[derive(thiserror::Error, Debug)]
+enum KeyStoreError {
+    #[error("Key not found")]
+    KeyNotFound,
+    #[error("Invalid signature")]
+    InvalidSignature,
+    #[error("Mnemonic type is required for the phrase")]
+    MnemonicTypeRequired,
+    #[error("Raw keys can't be split into shares")]
+    RawKeySharesNotSupported,
+    #[error("Ledger can't sign with the network signature id")]
+    LedgerSignatureIdNotSupported,
+    #[error("Only english phrases can be imported as master keys")]
+    NativeMasterKeyNotSupported,
 }
 
-const KEYSTORE_CACHE_DURATION: Duration = Duration::from_secs(960); // 16 min
-const KEYSTORE_CACHE_GAP: Duration = Duration::from_secs(60); // 1 min
+#[derive(Copy, Clone)]
+enum KeyStoreEvent {
+    Added,
+    Renamed,
+}
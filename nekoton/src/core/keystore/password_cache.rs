@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use nt_utils::TrustMe;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordCachePolicy {
+    /// Never keep the password in memory
+    #[serde(default)]
+    pub never: bool,
+    /// Absolute lifetime of the cached password in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lifetime: Option<u32>,
+    /// Idle timeout in seconds. Resets on each use of the cached password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_timeout: Option<u32>,
+    /// Max number of uses of the cached password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_uses: Option<u32>,
+}
+
+impl PasswordCachePolicy {
+    pub fn lifetime(&self) -> Duration {
+        match self.lifetime {
+            Some(lifetime) => Duration::from_secs(lifetime as u64),
+            None => DEFAULT_CACHE_DURATION,
+        }
+    }
+
+    /// Combines policies of the keys which share the same cached password
+    pub fn strictest<I>(policies: I) -> Self
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        fn min(left: Option<u32>, right: Option<u32>) -> Option<u32> {
+            match (left, right) {
+                (Some(left), Some(right)) => Some(std::cmp::min(left, right)),
+                (left, right) => left.or(right),
+            }
+        }
+
+        policies
+            .into_iter()
+            .fold(Self::default(), |result, policy| Self {
+                never: result.never || policy.never,
+                lifetime: min(result.lifetime, policy.lifetime),
+                idle_timeout: min(result.idle_timeout, policy.idle_timeout),
+                max_uses: min(result.max_uses, policy.max_uses),
+            })
+    }
+
+    pub fn cache_behavior(&self, cache: bool) -> nt::crypto::PasswordCacheBehavior {
+        if cache && !self.never {
+            nt::crypto::PasswordCacheBehavior::Store(self.lifetime())
+        } else {
+            nt::crypto::PasswordCacheBehavior::Remove
+        }
+    }
+}
+
+/// Tracks cached passwords to enforce per-key policies on top of the core password cache
+#[derive(Default)]
+pub struct PasswordCacheTracker {
+    entries: Mutex<HashMap<[u8; 32], TrackedPassword>>,
+    groups: Mutex<HashMap<[u8; 32], CacheGroup>>,
+}
+
+impl PasswordCacheTracker {
+    /// Checks whether the password with the specified id can be used with the policy
+    pub fn prepare(
+        &self,
+        id: &[u8; 32],
+        policy: &PasswordCachePolicy,
+        password: Option<String>,
+        cache: bool,
+    ) -> Result<(nt::crypto::Password, CacheAction), PasswordCacheError> {
+        match password {
+            Some(password) => {
+                let cache_behavior = policy.cache_behavior(cache);
+                let action = match &cache_behavior {
+                    nt::crypto::PasswordCacheBehavior::Store(_) => CacheAction::Store,
+                    _ => CacheAction::Remove,
+                };
+                let password = nt::crypto::Password::Explicit {
+                    password: password.into(),
+                    cache_behavior,
+                };
+                Ok((password, action))
+            }
+            None => match self.remaining(id, policy, nt_utils::now_ms_u64()) {
+                Some(_) => Ok((nt::crypto::Password::FromCache, CacheAction::Use)),
+                None => Err(PasswordCacheError::PasswordNotCached),
+            },
+        }
+    }
+
+    /// Updates tracked state after successful operation.
+    /// Returns `true` if the password was stored or removed
+    pub fn commit(&self, id: &[u8; 32], action: CacheAction) -> bool {
+        let now = nt_utils::now_ms_u64();
+
+        let mut entries = self.entries.lock().trust_me();
+        match action {
+            CacheAction::Store => {
+                entries.insert(
+                    *id,
+                    TrackedPassword {
+                        stored_at: now,
+                        last_used_at: now,
+                        uses: 0,
+                    },
+                );
+                true
+            }
+            CacheAction::Remove => entries.remove(id).is_some(),
+            CacheAction::Use => {
+                if let Some(entry) = entries.get_mut(id) {
+                    entry.last_used_at = now;
+                    entry.uses += 1;
+                }
+                false
+            }
+        }
+    }
+
+    /// Returns the remaining time of the cached password
+    pub fn remaining(
+        &self,
+        id: &[u8; 32],
+        policy: &PasswordCachePolicy,
+        now: u64,
+    ) -> Option<Duration> {
+        if policy.never {
+            return None;
+        }
+
+        let entries = self.entries.lock().trust_me();
+        let entry = entries.get(id)?;
+
+        if matches!(policy.max_uses, Some(max_uses) if entry.uses >= max_uses) {
+            return None;
+        }
+
+        let mut expire_at = entry.stored_at + policy.lifetime().as_millis() as u64;
+        if let Some(idle_timeout) = policy.idle_timeout {
+            expire_at = std::cmp::min(expire_at, entry.last_used_at + idle_timeout as u64 * 1000);
+        }
+
+        expire_at
+            .checked_sub(now)
+            .filter(|&remaining| remaining > 0)
+            .map(Duration::from_millis)
+    }
+
+    /// Remembers the keys which share the password with the specified id
+    /// and their combined policy, so that the cache can be checked synchronously
+    pub fn update_group<I>(&self, id: &[u8; 32], policy: &PasswordCachePolicy, public_keys: I)
+    where
+        I: IntoIterator<Item = [u8; 32]>,
+    {
+        let mut groups = self.groups.lock().trust_me();
+        groups.retain(|_, group| &group.id != id);
+        for public_key in public_keys {
+            groups.insert(
+                public_key,
+                CacheGroup {
+                    id: *id,
+                    policy: policy.clone(),
+                },
+            );
+        }
+    }
+
+    /// Returns the remaining time of the password cached for the specified key
+    /// using the last known policy of its group
+    pub fn remaining_for_key(&self, public_key: &[u8; 32], now: u64) -> Option<Duration> {
+        let CacheGroup { id, policy } = self.groups.lock().trust_me().get(public_key)?.clone();
+        self.remaining(&id, &policy, now)
+    }
+
+    pub fn remove(&self, id: &[u8; 32]) -> bool {
+        self.entries.lock().trust_me().remove(id).is_some()
+    }
+
+    /// Returns ids of the removed passwords
+    pub fn clear(&self) -> Vec<[u8; 32]> {
+        self.entries
+            .lock()
+            .trust_me()
+            .drain()
+            .map(|(id, _)| id)
+            .collect()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum CacheAction {
+    Store,
+    Remove,
+    Use,
+}
+
+#[derive(Clone)]
+struct CacheGroup {
+    id: [u8; 32],
+    policy: PasswordCachePolicy,
+}
+
+struct TrackedPassword {
+    stored_at: u64,
+    last_used_at: u64,
+    uses: u32,
+}
+
+pub const DEFAULT_CACHE_DURATION: Duration = Duration::from_secs(960); // 16 min
+
+#[derive(thiserror::Error, Debug)]
+pub enum PasswordCacheError {
+    #[error("Password not cached")]
+    PasswordNotCached,
+}
@@ -0,0 +1,634 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use nt::crypto::{
+    EncryptedData, EncryptedKeyPassword, EncryptionAlgorithm, Password, SignerContext, SignerEntry,
+    SignerStorage,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use super::encryption::compute_shared_secret;
+use crate::crypto::mnemonic::{derive_from_phrase, Bip39Language};
+
+const NONCE_LEN: usize = 12;
+
+/// Signer for plain ed25519 secret keys, e.g. imported from CLI tooling,
+/// and for phrases which can't be derived by the core signers.
+///
+/// Keys are exposed through the `encrypted_key` API, since they
+/// are used the same way as the keys of `EncryptedKeySigner`
+#[derive(Default)]
+pub struct RawKeySigner {
+    keys: HashMap<[u8; 32], RawKey>,
+}
+
+impl RawKeySigner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn get_key(&self, public_key: &ed25519_dalek::PublicKey) -> Result<&RawKey> {
+        match self.keys.get(public_key.as_bytes()) {
+            Some(key) => Ok(key),
+            None => Err(RawKeyError::KeyNotFound.into()),
+        }
+    }
+
+    fn get_key_mut(&mut self, public_key: &ed25519_dalek::PublicKey) -> Result<&mut RawKey> {
+        match self.keys.get_mut(public_key.as_bytes()) {
+            Some(key) => Ok(key),
+            None => Err(RawKeyError::KeyNotFound.into()),
+        }
+    }
+
+    fn unlock(
+        &self,
+        ctx: SignerContext<'_>,
+        public_key: &ed25519_dalek::PublicKey,
+        password: Password,
+    ) -> Result<ed25519_dalek::Keypair> {
+        let key = self.get_key(public_key)?;
+        let password = ctx
+            .password_cache
+            .process_password(public_key.to_bytes(), password)?;
+        let keypair = key.decrypt(password.as_ref().unsecure())?;
+        password.proceed();
+        Ok(keypair)
+    }
+
+    fn unlock_secret(
+        &self,
+        ctx: SignerContext<'_>,
+        public_key: &ed25519_dalek::PublicKey,
+        password: Password,
+    ) -> Result<Vec<u8>> {
+        let key = self.get_key(public_key)?;
+        let password = ctx
+            .password_cache
+            .process_password(public_key.to_bytes(), password)?;
+        let secret = key.decrypt_secret(password.as_ref().unsecure())?;
+        password.proceed();
+        Ok(secret)
+    }
+}
+
+#[async_trait::async_trait]
+impl nt::crypto::Signer for RawKeySigner {
+    type CreateKeyInput = RawKeyCreateInput;
+    type ExportKeyInput = EncryptedKeyPassword;
+    type ExportKeyOutput = RawKeyExportOutput;
+    type GetPublicKeys = ();
+    type UpdateKeyInput = RawKeyUpdateParams;
+    type SignInput = EncryptedKeyPassword;
+
+    async fn add_key(
+        &mut self,
+        ctx: SignerContext<'_>,
+        input: Self::CreateKeyInput,
+    ) -> Result<SignerEntry> {
+        let public_key = input.format.derive_keypair(&input.secret)?.public;
+        if self.keys.contains_key(public_key.as_bytes()) {
+            return Err(RawKeyError::KeyAlreadyExists.into());
+        }
+
+        let name = input
+            .name
+            .unwrap_or_else(|| nt::crypto::default_key_name(public_key.as_bytes()));
+        let password = ctx
+            .password_cache
+            .process_password(public_key.to_bytes(), input.password)?;
+        let key = RawKey::new(
+            name,
+            &input.secret,
+            &public_key,
+            input.format,
+            password.as_ref().unsecure(),
+        )?;
+        let entry = key.signer_entry()?;
+
+        self.keys.insert(public_key.to_bytes(), key);
+        password.proceed();
+        Ok(entry)
+    }
+
+    async fn update_key(
+        &mut self,
+        ctx: SignerContext<'_>,
+        input: Self::UpdateKeyInput,
+    ) -> Result<SignerEntry> {
+        match input {
+            RawKeyUpdateParams::Rename { public_key, name } => {
+                let key = self.get_key_mut(&public_key)?;
+                key.name = name;
+                Ok(key.signer_entry()?)
+            }
+            RawKeyUpdateParams::ChangePassword {
+                public_key,
+                old_password,
+                new_password,
+            } => {
+                let secret = self.unlock_secret(ctx, &public_key, old_password)?;
+                let new_password = ctx
+                    .password_cache
+                    .process_password(public_key.to_bytes(), new_password)?;
+
+                let key = self.get_key_mut(&public_key)?;
+                *key = RawKey::new(
+                    key.name.clone(),
+                    &secret,
+                    &public_key,
+                    key.format,
+                    new_password.as_ref().unsecure(),
+                )?;
+                new_password.proceed();
+                Ok(key.signer_entry()?)
+            }
+        }
+    }
+
+    async fn export_key(
+        &self,
+        ctx: SignerContext<'_>,
+        input: Self::ExportKeyInput,
+    ) -> Result<Self::ExportKeyOutput> {
+        let key = self.get_key(&input.public_key)?;
+        let secret = self.unlock_secret(ctx, &input.public_key, input.password)?;
+        let keypair = key.keypair(&secret)?;
+
+        let phrase = match key.format {
+            RawKeyFormat::Phrase { .. } => {
+                Some(String::from_utf8(secret).map_err(|_| RawKeyError::InvalidPhrase)?)
+            }
+            RawKeyFormat::Hex | RawKeyFormat::Keypair => None,
+        };
+
+        Ok(RawKeyExportOutput {
+            secret: keypair.secret.to_bytes(),
+            public_key: keypair.public.to_bytes(),
+            phrase,
+            format: key.format,
+        })
+    }
+
+    async fn get_public_keys(
+        &self,
+        _: SignerContext<'_>,
+        _: Self::GetPublicKeys,
+    ) -> Result<Vec<ed25519_dalek::PublicKey>> {
+        Err(RawKeyError::KeysCannotBeDerived.into())
+    }
+
+    async fn encrypt(
+        &self,
+        ctx: SignerContext<'_>,
+        data: &[u8],
+        public_keys: &[ed25519_dalek::PublicKey],
+        algorithm: EncryptionAlgorithm,
+        input: Self::SignInput,
+    ) -> Result<Vec<EncryptedData>> {
+        let keypair = self.unlock(ctx, &input.public_key, input.password)?;
+
+        let mut rng = rand::thread_rng();
+        public_keys
+            .iter()
+            .map(|recipient_public_key| -> Result<EncryptedData> {
+                let shared_secret = compute_shared_secret(&keypair.secret, recipient_public_key)?;
+                let nonce = rng.gen::<[u8; NONCE_LEN]>();
+                let data = match algorithm {
+                    EncryptionAlgorithm::ChaCha20Poly1305 => {
+                        ChaCha20Poly1305::new(Key::from_slice(shared_secret.as_ref()))
+                            .encrypt(Nonce::from_slice(&nonce), data)
+                            .map_err(|_| RawKeyError::FailedToEncryptData)?
+                    }
+                };
+
+                Ok(EncryptedData {
+                    algorithm,
+                    source_public_key: keypair.public,
+                    recipient_public_key: *recipient_public_key,
+                    data,
+                    nonce: nonce.to_vec(),
+                })
+            })
+            .collect()
+    }
+
+    async fn decrypt(
+        &self,
+        ctx: SignerContext<'_>,
+        data: &EncryptedData,
+        input: Self::SignInput,
+    ) -> Result<Vec<u8>> {
+        if data.recipient_public_key != input.public_key || data.nonce.len() != NONCE_LEN {
+            return Err(RawKeyError::FailedToDecryptData.into());
+        }
+
+        let keypair = self.unlock(ctx, &input.public_key, input.password)?;
+        let shared_secret = compute_shared_secret(&keypair.secret, &data.source_public_key)?;
+        match data.algorithm {
+            EncryptionAlgorithm::ChaCha20Poly1305 => {
+                ChaCha20Poly1305::new(Key::from_slice(shared_secret.as_ref()))
+                    .decrypt(Nonce::from_slice(&data.nonce), data.data.as_slice())
+                    .map_err(|_| anyhow::Error::from(RawKeyError::FailedToDecryptData))
+            }
+        }
+    }
+
+    async fn sign(
+        &self,
+        ctx: SignerContext<'_>,
+        data: &[u8],
+        input: Self::SignInput,
+    ) -> Result<[u8; ed25519_dalek::SIGNATURE_LENGTH]> {
+        use ed25519_dalek::Signer;
+
+        let keypair = self.unlock(ctx, &input.public_key, input.password)?;
+        Ok(keypair.sign(data).to_bytes())
+    }
+}
+
+#[async_trait::async_trait]
+impl SignerStorage for RawKeySigner {
+    fn load_state(&mut self, data: &str) -> Result<()> {
+        let keys = serde_json::from_str::<Vec<RawKey>>(data)?;
+        self.keys = keys
+            .into_iter()
+            .map(|key| Ok((key.public_key()?.to_bytes(), key)))
+            .collect::<Result<_>>()?;
+        Ok(())
+    }
+
+    fn store_state(&self) -> String {
+        serde_json::to_string(&self.keys.values().collect::<Vec<_>>()).unwrap_or_default()
+    }
+
+    fn get_entries(&self) -> Vec<SignerEntry> {
+        self.keys
+            .values()
+            .filter_map(|key| key.signer_entry().ok())
+            .collect()
+    }
+
+    async fn remove_key(&mut self, public_key: &ed25519_dalek::PublicKey) -> Option<SignerEntry> {
+        let key = self.keys.remove(public_key.as_bytes())?;
+        key.signer_entry().ok()
+    }
+
+    async fn clear(&mut self) {
+        self.keys.clear();
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawKeyCreateInput {
+    pub name: Option<String>,
+    /// Secret key bytes, or UTF-8 phrase for `RawKeyFormat::Phrase`
+    pub secret: Vec<u8>,
+    pub format: RawKeyFormat,
+    pub password: Password,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawKeyExportOutput {
+    pub secret: [u8; ed25519_dalek::SECRET_KEY_LENGTH],
+    pub public_key: [u8; ed25519_dalek::PUBLIC_KEY_LENGTH],
+    /// Imported phrase for `RawKeyFormat::Phrase`
+    pub phrase: Option<String>,
+    pub format: RawKeyFormat,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RawKeyUpdateParams {
+    #[serde(rename_all = "camelCase")]
+    Rename {
+        #[serde(with = "nt_utils::serde_public_key")]
+        public_key: ed25519_dalek::PublicKey,
+        name: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    ChangePassword {
+        #[serde(with = "nt_utils::serde_public_key")]
+        public_key: ed25519_dalek::PublicKey,
+        old_password: Password,
+        new_password: Password,
+    },
+}
+
+/// Format in which the key was imported, so that it is exported the same way
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RawKeyFormat {
+    /// Hex encoded secret key
+    Hex,
+    /// Keypair JSON of tonos-cli: `{ "public": "...", "secret": "..." }`
+    Keypair,
+    /// Labs phrase from a non-english wordlist.
+    ///
+    /// NOTE: only the key of the imported account is stored,
+    /// other accounts can't be derived from it
+    #[serde(rename_all = "camelCase")]
+    Phrase {
+        language: Bip39Language,
+        account_id: u16,
+    },
+}
+
+impl RawKeyFormat {
+    fn derive_keypair(&self, secret: &[u8]) -> Result<ed25519_dalek::Keypair, RawKeyError> {
+        match *self {
+            Self::Hex | Self::Keypair => {
+                let secret = ed25519_dalek::SecretKey::from_bytes(secret)
+                    .map_err(|_| RawKeyError::InvalidSecretKey)?;
+                let public = ed25519_dalek::PublicKey::from(&secret);
+                Ok(ed25519_dalek::Keypair { secret, public })
+            }
+            Self::Phrase {
+                language,
+                account_id,
+            } => {
+                let phrase = std::str::from_utf8(secret).map_err(|_| RawKeyError::InvalidPhrase)?;
+                derive_from_phrase(phrase, nt::crypto::MnemonicType::Labs(account_id), language)
+                    .map_err(|_| RawKeyError::InvalidPhrase)
+            }
+        }
+    }
+}
+
+/// Tonos-cli keypair JSON
+#[derive(Serialize, Deserialize)]
+pub struct RawKeypair {
+    pub public: String,
+    pub secret: String,
+}
+
+/// Parses hex encoded ed25519 secret key
+pub fn parse_secret_key(secret: &str) -> Result<ed25519_dalek::SecretKey, RawKeyError> {
+    let secret = hex::decode(secret.trim()).map_err(|_| RawKeyError::InvalidSecretKey)?;
+    ed25519_dalek::SecretKey::from_bytes(&secret).map_err(|_| RawKeyError::InvalidSecretKey)
+}
+
+/// Parses tonos-cli keypair and checks that the public key matches the secret key
+pub fn parse_keypair(keypair: &RawKeypair) -> Result<ed25519_dalek::SecretKey, RawKeyError> {
+    let secret = parse_secret_key(&keypair.secret)?;
+    let public_key = hex::decode(keypair.public.trim()).map_err(|_| RawKeyError::InvalidKeypair)?;
+    if ed25519_dalek::PublicKey::from(&secret).as_bytes() != public_key.as_slice() {
+        return Err(RawKeyError::InvalidKeypair);
+    }
+    Ok(secret)
+}
+
+pub fn make_keypair(secret: &[u8; 32], public_key: &[u8; 32]) -> RawKeypair {
+    RawKeypair {
+        public: hex::encode(public_key),
+        secret: hex::encode(secret),
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawKey {
+    name: String,
+    /// Hex encoded public key
+    public_key: String,
+    format: RawKeyFormat,
+    /// Hex encoded nonce
+    nonce: String,
+    /// Hex encoded secret key or phrase, encrypted with the hardened password.
+    ///
+    /// NOTE: password is already hardened with the KDF settings of the key,
+    /// which are stored next to it, so it is used as the key as is
+    encrypted_secret: String,
+}
+
+impl RawKey {
+    fn new(
+        name: String,
+        secret: &[u8],
+        public_key: &ed25519_dalek::PublicKey,
+        format: RawKeyFormat,
+        password: &str,
+    ) -> Result<Self, RawKeyError> {
+        let nonce = rand::thread_rng().gen::<[u8; NONCE_LEN]>();
+        let encrypted_secret = make_cipher(password)?
+            .encrypt(Nonce::from_slice(&nonce), secret)
+            .map_err(|_| RawKeyError::FailedToEncryptData)?;
+
+        Ok(Self {
+            name,
+            public_key: hex::encode(public_key.as_bytes()),
+            format,
+            nonce: hex::encode(nonce),
+            encrypted_secret: hex::encode(encrypted_secret),
+        })
+    }
+
+    fn public_key(&self) -> Result<ed25519_dalek::PublicKey, RawKeyError> {
+        let public_key = hex::decode(&self.public_key).map_err(|_| RawKeyError::InvalidKeypair)?;
+        ed25519_dalek::PublicKey::from_bytes(&public_key).map_err(|_| RawKeyError::InvalidKeypair)
+    }
+
+    fn decrypt(&self, password: &str) -> Result<ed25519_dalek::Keypair, RawKeyError> {
+        self.keypair(&self.decrypt_secret(password)?)
+    }
+
+    fn decrypt_secret(&self, password: &str) -> Result<Vec<u8>, RawKeyError> {
+        let nonce = hex::decode(&self.nonce).map_err(|_| RawKeyError::FailedToDecryptData)?;
+        let encrypted_secret =
+            hex::decode(&self.encrypted_secret).map_err(|_| RawKeyError::FailedToDecryptData)?;
+        if nonce.len() != NONCE_LEN {
+            return Err(RawKeyError::FailedToDecryptData);
+        }
+
+        make_cipher(password)?
+            .decrypt(Nonce::from_slice(&nonce), encrypted_secret.as_slice())
+            .map_err(|_| RawKeyError::InvalidPassword)
+    }
+
+    fn keypair(&self, secret: &[u8]) -> Result<ed25519_dalek::Keypair, RawKeyError> {
+        let keypair = self.format.derive_keypair(secret)?;
+        if keypair.public != self.public_key()? {
+            return Err(RawKeyError::InvalidKeypair);
+        }
+        Ok(keypair)
+    }
+
+    fn signer_entry(&self) -> Result<SignerEntry, RawKeyError> {
+        let public_key = self.public_key()?;
+        Ok(SignerEntry {
+            name: self.name.clone(),
+            public_key,
+            master_key: public_key,
+            account_id: 0,
+        })
+    }
+}
+
+/// Hardened password is the hex encoded key, see `KeyEncryption::harden`
+fn make_cipher(password: &str) -> Result<ChaCha20Poly1305, RawKeyError> {
+    let mut key = hex::decode(password).map_err(|_| RawKeyError::InvalidPassword)?;
+    let cipher = ChaCha20Poly1305::new_from_slice(&key).map_err(|_| RawKeyError::InvalidPassword);
+    key.zeroize();
+    cipher
+}
+
+#[derive(thiserror::Error, Debug, Copy, Clone)]
+pub enum RawKeyError {
+    #[error("Key not found")]
+    KeyNotFound,
+    #[error("Key already exists")]
+    KeyAlreadyExists,
+    #[error("Invalid secret key")]
+    InvalidSecretKey,
+    #[error("Invalid phrase")]
+    InvalidPhrase,
+    #[error("Public key doesn't match the secret key")]
+    InvalidKeypair,
+    #[error("Invalid password")]
+    InvalidPassword,
+    #[error("Raw keys can't be derived")]
+    KeysCannotBeDerived,
+    #[error("Failed to encrypt data")]
+    FailedToEncryptData,
+    #[error("Failed to decrypt data")]
+    FailedToDecryptData,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::kdf::{KdfParams, KeyEncryption};
+    use super::*;
+
+    const SECRET: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    const PUBLIC: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+    #[test]
+    fn parses_hex_and_keypair() {
+        let secret = parse_secret_key(SECRET).unwrap();
+        assert_eq!(
+            hex::encode(ed25519_dalek::PublicKey::from(&secret).as_bytes()),
+            PUBLIC
+        );
+
+        let keypair = RawKeypair {
+            public: PUBLIC.to_owned(),
+            secret: SECRET.to_owned(),
+        };
+        assert_eq!(
+            parse_keypair(&keypair).unwrap().as_bytes(),
+            secret.as_bytes()
+        );
+
+        let keypair = RawKeypair {
+            public: SECRET.to_owned(),
+            secret: SECRET.to_owned(),
+        };
+        assert!(matches!(
+            parse_keypair(&keypair),
+            Err(RawKeyError::InvalidKeypair)
+        ));
+        assert!(parse_secret_key("abcd").is_err());
+    }
+
+    fn hardened(password: &str) -> String {
+        let params = KdfParams {
+            memory_cost: 64,
+            time_cost: 1,
+            parallelism: 1,
+        };
+        let encryption = KeyEncryption {
+            salt: "00".repeat(16),
+            ..KeyEncryption::new(params).unwrap()
+        };
+        encryption.harden(password).unwrap()
+    }
+
+    #[test]
+    fn key_round_trip() {
+        let password = hardened("password");
+        let secret = parse_secret_key(SECRET).unwrap();
+        let key = RawKey::new(
+            "Key".to_owned(),
+            secret.as_bytes(),
+            &ed25519_dalek::PublicKey::from(&secret),
+            RawKeyFormat::Keypair,
+            &password,
+        )
+        .unwrap();
+        assert_eq!(key.public_key, PUBLIC);
+        assert_ne!(key.encrypted_secret, SECRET);
+
+        let keypair = key.decrypt(&password).unwrap();
+        assert_eq!(keypair.secret.as_bytes(), secret.as_bytes());
+        assert!(matches!(
+            key.decrypt(&hardened("Password")),
+            Err(RawKeyError::InvalidPassword)
+        ));
+        // Password must be hardened before it gets to the signer
+        assert!(matches!(
+            key.decrypt("password"),
+            Err(RawKeyError::InvalidPassword)
+        ));
+
+        let stored = serde_json::to_string(&key).unwrap();
+        let key = serde_json::from_str::<RawKey>(&stored).unwrap();
+        assert_eq!(key.format, RawKeyFormat::Keypair);
+        assert_eq!(
+            key.decrypt(&password).unwrap().secret.as_bytes(),
+            secret.as_bytes()
+        );
+
+        let exported = make_keypair(&keypair.secret.to_bytes(), &keypair.public.to_bytes());
+        assert_eq!(exported.secret, SECRET);
+        assert_eq!(exported.public, PUBLIC);
+    }
+
+    #[test]
+    fn phrase_key_round_trip() {
+        let language = Bip39Language::Japanese;
+        let mnemonic_type = nt::crypto::MnemonicType::Labs(1);
+        let phrase =
+            crate::crypto::mnemonic::generate_phrase(mnemonic_type, language, &Default::default())
+                .unwrap()
+                .phrase;
+
+        let format = RawKeyFormat::Phrase {
+            language,
+            account_id: 1,
+        };
+        let password = hardened("password");
+        let expected = derive_from_phrase(&phrase, mnemonic_type, language).unwrap();
+        let key = RawKey::new(
+            "Key".to_owned(),
+            phrase.as_bytes(),
+            &expected.public,
+            format,
+            &password,
+        )
+        .unwrap();
+
+        let stored = serde_json::to_string(&key).unwrap();
+        let key = serde_json::from_str::<RawKey>(&stored).unwrap();
+        assert_eq!(key.format, format);
+        assert_eq!(key.decrypt_secret(&password).unwrap(), phrase.as_bytes());
+        assert_eq!(key.decrypt(&password).unwrap().public, expected.public);
+
+        let other_account = RawKey {
+            format: RawKeyFormat::Phrase {
+                language,
+                account_id: 0,
+            },
+            ..key
+        };
+        assert!(matches!(
+            other_account.decrypt(&password),
+            Err(RawKeyError::InvalidKeypair)
+        ));
+    }
+}
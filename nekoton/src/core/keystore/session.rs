@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Mutex;
+
+use nt_utils::TrustMe;
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::{UnsignedMessage, UnsignedMessageKind};
+
+/// Restrictions of the ephemeral key, issued for a single origin
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionKeyPolicy {
+    pub origin: String,
+    /// Unix timestamp in milliseconds
+    pub expires_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_value_per_transaction: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_value: Option<u64>,
+    #[serde(default)]
+    pub spent: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_destinations: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_methods: Option<Vec<String>>,
+    /// Messages which are signed but not yet delivered or expired
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reservations: Vec<SessionReservation>,
+}
+
+impl SessionKeyPolicy {
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+
+    pub fn check(
+        &self,
+        origin: &str,
+        now: u64,
+        request: &SessionSignRequest,
+    ) -> Result<(), SessionKeyError> {
+        if self.origin != origin {
+            return Err(SessionKeyError::OriginMismatch);
+        }
+        if self.is_expired(now) {
+            return Err(SessionKeyError::Expired);
+        }
+
+        let has_value_limits =
+            self.max_value_per_transaction.is_some() || self.max_total_value.is_some();
+        if request.has_unknown_value && has_value_limits {
+            return Err(SessionKeyError::UnknownValue);
+        }
+
+        let value = request.total_value();
+        if matches!(self.max_value_per_transaction, Some(max) if value > max) {
+            return Err(SessionKeyError::TransactionLimitExceeded);
+        }
+        if matches!(self.max_total_value, Some(max) if self.spent.saturating_add(value) > max) {
+            return Err(SessionKeyError::TotalLimitExceeded);
+        }
+
+        if let Some(allowed) = &self.allowed_destinations {
+            if let Some((destination, _)) = request
+                .transfers
+                .iter()
+                .find(|(destination, _)| !allowed.contains(destination))
+            {
+                return Err(SessionKeyError::DestinationNotAllowed(destination.clone()));
+            }
+        }
+
+        if let (Some(allowed), Some(method)) = (&self.allowed_methods, &request.method) {
+            if !allowed.contains(method) {
+                return Err(SessionKeyError::MethodNotAllowed(method.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Counts the value of the signed message as spent until the message is settled
+    pub fn reserve(&mut self, message_hash: String, value: u64, expire_at: u32) {
+        self.spent = self.spent.saturating_add(value);
+        self.reservations.push(SessionReservation {
+            message_hash,
+            value,
+            expire_at,
+        });
+    }
+
+    pub fn is_reserved(&self, message_hash: &str) -> bool {
+        self.reservations
+            .iter()
+            .any(|reservation| reservation.message_hash == message_hash)
+    }
+
+    /// Removes the reservation of the message. Value of the undelivered message is released
+    pub fn settle(&mut self, message_hash: &str, delivered: bool) {
+        let index = match self
+            .reservations
+            .iter()
+            .position(|reservation| reservation.message_hash == message_hash)
+        {
+            Some(index) => index,
+            None => return,
+        };
+
+        let reservation = self.reservations.remove(index);
+        if !delivered {
+            self.spent = self.spent.saturating_sub(reservation.value);
+        }
+    }
+}
+
+/// Value of the signed message which is counted as spent until it is delivered or expires
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionReservation {
+    pub message_hash: String,
+    pub value: u64,
+    /// Unix timestamp in seconds
+    pub expire_at: u32,
+}
+
+/// Value and destinations of the message which is going to be signed with the session key
+pub struct SessionSignRequest {
+    pub transfers: Vec<(String, u64)>,
+    pub method: Option<String>,
+    /// Contract calls can send any value, which can't be decoded from the message
+    pub has_unknown_value: bool,
+}
+
+impl SessionSignRequest {
+    pub fn from_message(message: &UnsignedMessage) -> Result<Self, SessionKeyError> {
+        let description = crate::crypto::describe_unsigned_message(message.inner.as_ref(), None)
+            .map_err(|_| SessionKeyError::UnknownMessage)?;
+
+        let mut has_unknown_value = false;
+        let (transfers, method) = match &message.kind {
+            UnsignedMessageKind::Generic if !description.transfers.is_empty() => (
+                description
+                    .transfers
+                    .into_iter()
+                    .map(|transfer| {
+                        let value = u64::try_from(transfer.value).unwrap_or(u64::MAX);
+                        (transfer.destination, value)
+                    })
+                    .collect(),
+                None,
+            ),
+            UnsignedMessageKind::Transfers { transfers } => (
+                transfers
+                    .iter()
+                    .map(|transfer| {
+                        let value = u64::try_from(transfer.value).unwrap_or(u64::MAX);
+                        (transfer.destination.clone(), value)
+                    })
+                    .collect(),
+                None,
+            ),
+            UnsignedMessageKind::MultisigTransfer {
+                destination,
+                amount,
+                ..
+            } => (vec![(destination.clone(), *amount)], None),
+            // Contract is treated as a destination, so that calls can also be restricted.
+            // Value is counted as 0, since the value sent by the contract can't be decoded
+            // from the external message. Such calls are rejected for keys with value limits
+            UnsignedMessageKind::Call { method } => {
+                has_unknown_value = true;
+                (
+                    vec![(description.address.to_string(), 0)],
+                    Some(method.clone()),
+                )
+            }
+            UnsignedMessageKind::Deploy => (Vec::new(), None),
+            _ => return Err(SessionKeyError::UnknownMessage),
+        };
+
+        Ok(Self {
+            transfers,
+            method,
+            has_unknown_value,
+        })
+    }
+
+    pub fn total_value(&self) -> u64 {
+        self.transfers
+            .iter()
+            .fold(0u64, |total, (_, value)| total.saturating_add(*value))
+    }
+}
+
+/// Passwords of the session keys. They are never persisted,
+/// so session keys become unusable after the keystore is reloaded
+#[derive(Default)]
+pub struct SessionKeyPasswords {
+    passwords: Mutex<HashMap<[u8; 32], String>>,
+}
+
+impl SessionKeyPasswords {
+    pub fn insert(&self, public_key: &ed25519_dalek::PublicKey, password: String) {
+        self.passwords
+            .lock()
+            .trust_me()
+            .insert(public_key.to_bytes(), password);
+    }
+
+    pub fn get(&self, public_key: &ed25519_dalek::PublicKey) -> Option<String> {
+        self.passwords
+            .lock()
+            .trust_me()
+            .get(public_key.as_bytes())
+            .cloned()
+    }
+
+    pub fn contains(&self, public_key: &ed25519_dalek::PublicKey) -> bool {
+        self.passwords
+            .lock()
+            .trust_me()
+            .contains_key(public_key.as_bytes())
+    }
+
+    pub fn remove(&self, public_key: &ed25519_dalek::PublicKey) {
+        self.passwords
+            .lock()
+            .trust_me()
+            .remove(public_key.as_bytes());
+    }
+
+    pub fn clear(&self) {
+        self.passwords.lock().trust_me().clear();
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SessionKeyError {
+    #[error("Session key not found")]
+    NotFound,
+    #[error("Session key is locked")]
+    Locked,
+    #[error("Session key was issued for another origin")]
+    OriginMismatch,
+    #[error("Session key expired")]
+    Expired,
+    #[error("Invalid expiration time")]
+    InvalidExpiration,
+    #[error("Transaction value limit exceeded")]
+    TransactionLimitExceeded,
+    #[error("Total value limit exceeded")]
+    TotalLimitExceeded,
+    #[error("Destination is not allowed: {0}")]
+    DestinationNotAllowed(String),
+    #[error("Method is not allowed: {0}")]
+    MethodNotAllowed(String),
+    #[error("Message can't be signed with the session key")]
+    UnknownMessage,
+    #[error("Message value can't be checked against the session key limits")]
+    UnknownValue,
+}
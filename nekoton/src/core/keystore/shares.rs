@@ -0,0 +1,306 @@
+use std::convert::TryFrom;
+
+use rand::Rng;
+use sha2::Digest;
+
+const SHARES_VERSION: u8 = 0;
+const HEADER_LEN: usize = 6;
+const CHECKSUM_LEN: usize = 4;
+const ID_LEN: usize = 2;
+const DIGEST_LEN: usize = 4;
+
+/// BIP39 entropy without the checksum bits
+const SECRET_KIND_ENTROPY: u8 = 0;
+/// Packed word indices of phrases without BIP39 checksum (legacy phrases)
+const SECRET_KIND_WORDS: u8 = 1;
+
+/// Splits seed phrase into mnemonic shares, `threshold` of which are required to restore it.
+///
+/// Share id is random, so a single share reveals nothing about the secret. The shared
+/// data contains a digest of the secret, which is only known after combining the shares
+pub fn split_phrase(phrase: &str, threshold: u8, shares: u8) -> Result<Vec<String>, SharesError> {
+    if threshold == 0 || threshold > shares {
+        return Err(SharesError::InvalidThreshold);
+    }
+
+    let mut rng = rand::thread_rng();
+    let id = rng.gen::<[u8; ID_LEN]>();
+
+    let (kind, secret) = match bip39::Mnemonic::from_phrase(phrase, bip39::Language::English) {
+        Ok(mnemonic) => (SECRET_KIND_ENTROPY, mnemonic.entropy().to_vec()),
+        Err(_) => (SECRET_KIND_WORDS, pack_indices(&phrase_to_indices(phrase)?)),
+    };
+
+    let mut data = Vec::with_capacity(1 + secret.len() + DIGEST_LEN);
+    data.push(kind);
+    data.extend_from_slice(&secret);
+    data.extend_from_slice(&compute_digest(&id, &secret));
+    let data_len = u8::try_from(data.len()).map_err(|_| SharesError::InvalidPhrase)?;
+
+    let polynomials = data
+        .iter()
+        .map(|&byte| {
+            let mut coefficients = vec![byte];
+            coefficients.extend((1..threshold).map(|_| rng.gen::<u8>()));
+            coefficients
+        })
+        .collect::<Vec<_>>();
+
+    Ok((1..=shares)
+        .map(|x| {
+            let mut share = Vec::with_capacity(HEADER_LEN + data.len() + CHECKSUM_LEN);
+            share.push(SHARES_VERSION);
+            share.extend_from_slice(&id);
+            share.extend_from_slice(&[threshold, x, data_len]);
+            share.extend(
+                polynomials
+                    .iter()
+                    .map(|coefficients| evaluate(coefficients, x)),
+            );
+            share.extend_from_slice(&compute_checksum(&share));
+
+            let word_count = (share.len() * 8 + 10) / 11;
+            indices_to_phrase(&unpack_indices(&share, word_count))
+        })
+        .collect())
+}
+
+/// Restores seed phrase from the mnemonic shares
+pub fn combine_shares(shares: &[String]) -> Result<String, SharesError> {
+    let mut parsed = Vec::<ParsedShare>::with_capacity(shares.len());
+    for (position, share) in shares.iter().enumerate() {
+        let share = ParsedShare::parse(share).map_err(|e| match e {
+            SharesError::InvalidShareChecksum(_) => SharesError::InvalidShareChecksum(position),
+            e => e,
+        })?;
+
+        if let Some(first) = parsed.first() {
+            if !first.is_compatible(&share) {
+                return Err(SharesError::SharesMismatch);
+            }
+        }
+
+        match parsed.iter().find(|item| item.x == share.x) {
+            // Same share entered twice
+            Some(item) if item.data == share.data => {}
+            Some(_) => return Err(SharesError::ConflictingShares(position)),
+            None => parsed.push(share),
+        }
+    }
+
+    let first = parsed.first().ok_or(SharesError::NotEnoughShares)?;
+    let threshold = first.threshold as usize;
+    if parsed.len() < threshold {
+        return Err(SharesError::NotEnoughShares);
+    }
+    let parsed = &parsed[..threshold];
+
+    let data = (0..first.data.len())
+        .map(|i| {
+            parsed.iter().enumerate().fold(0, |acc, (j, share)| {
+                let basis = parsed
+                    .iter()
+                    .enumerate()
+                    .filter(|(m, _)| *m != j)
+                    .fold(1, |basis, (_, other)| {
+                        gf_mul(basis, gf_mul(other.x, gf_inv(other.x ^ share.x)))
+                    });
+                acc ^ gf_mul(share.data[i], basis)
+            })
+        })
+        .collect::<Vec<u8>>();
+
+    let (kind, data) = match data.split_first() {
+        Some((kind, data)) if data.len() > DIGEST_LEN => (kind, data),
+        _ => return Err(SharesError::InvalidShares),
+    };
+    let (secret, digest) = data.split_at(data.len() - DIGEST_LEN);
+    if compute_digest(&first.id, secret) != digest {
+        return Err(SharesError::InvalidShares);
+    }
+
+    match *kind {
+        SECRET_KIND_ENTROPY => bip39::Mnemonic::from_entropy(secret, bip39::Language::English)
+            .map(|mnemonic| mnemonic.phrase().to_owned())
+            .map_err(|_| SharesError::InvalidShares),
+        SECRET_KIND_WORDS => Ok(indices_to_phrase(&unpack_indices(
+            secret,
+            secret.len() * 8 / 11,
+        ))),
+        _ => Err(SharesError::InvalidShares),
+    }
+}
+
+struct ParsedShare {
+    id: [u8; ID_LEN],
+    threshold: u8,
+    x: u8,
+    data: Vec<u8>,
+}
+
+impl ParsedShare {
+    fn parse(share: &str) -> Result<Self, SharesError> {
+        let bytes = pack_indices(&phrase_to_indices(share)?);
+        if bytes.len() < HEADER_LEN || bytes[0] != SHARES_VERSION {
+            return Err(SharesError::InvalidShare);
+        }
+
+        let data_len = bytes[5] as usize;
+        let total_len = HEADER_LEN + data_len + CHECKSUM_LEN;
+        if bytes.len() < total_len {
+            return Err(SharesError::InvalidShare);
+        }
+
+        let (payload, checksum) = bytes[..total_len].split_at(total_len - CHECKSUM_LEN);
+        if compute_checksum(payload) != checksum {
+            return Err(SharesError::InvalidShareChecksum(0));
+        }
+
+        let (threshold, x) = (bytes[3], bytes[4]);
+        if threshold == 0 || x == 0 {
+            return Err(SharesError::InvalidShare);
+        }
+
+        Ok(Self {
+            id: [bytes[1], bytes[2]],
+            threshold,
+            x,
+            data: payload[HEADER_LEN..].to_vec(),
+        })
+    }
+
+    fn is_compatible(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.threshold == other.threshold
+            && self.data.len() == other.data.len()
+    }
+}
+
+/// Digest of the secret, which is shared along with it
+fn compute_digest(id: &[u8; ID_LEN], secret: &[u8]) -> [u8; DIGEST_LEN] {
+    let hash = sha2::Sha256::new()
+        .chain(b"shares")
+        .chain(id)
+        .chain(secret)
+        .finalize();
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn compute_checksum(data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hash = sha2::Sha256::digest(data);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn wordlist() -> &'static [&'static str] {
+    bip39::Language::English.wordlist().get_words_by_prefix("")
+}
+
+fn phrase_to_indices(phrase: &str) -> Result<Vec<u16>, SharesError> {
+    let wordlist = wordlist();
+    phrase
+        .split_whitespace()
+        .map(|word| {
+            wordlist
+                .binary_search(&word)
+                .map(|index| index as u16)
+                .map_err(|_| SharesError::UnknownWord(word.to_owned()))
+        })
+        .collect()
+}
+
+fn indices_to_phrase(indices: &[u16]) -> String {
+    let wordlist = wordlist();
+    indices
+        .iter()
+        .map(|&index| wordlist[index as usize])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Packs 11-bit word indices into bytes, padding the last byte with zeros
+fn pack_indices(indices: &[u16]) -> Vec<u8> {
+    let mut result = vec![0u8; (indices.len() * 11 + 7) / 8];
+    for (i, &index) in indices.iter().enumerate() {
+        for bit in 0..11 {
+            if index & (1 << (10 - bit)) != 0 {
+                let offset = i * 11 + bit;
+                result[offset / 8] |= 0x80 >> (offset % 8);
+            }
+        }
+    }
+    result
+}
+
+fn unpack_indices(data: &[u8], word_count: usize) -> Vec<u16> {
+    (0..word_count)
+        .map(|i| {
+            (0..11).fold(0u16, |index, bit| {
+                let offset = i * 11 + bit;
+                let value = data
+                    .get(offset / 8)
+                    .map(|byte| byte & (0x80 >> (offset % 8)) != 0)
+                    .unwrap_or_default();
+                (index << 1) | value as u16
+            })
+        })
+        .collect()
+}
+
+fn evaluate(coefficients: &[u8], x: u8) -> u8 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(0, |acc, &coefficient| gf_mul(acc, x) ^ coefficient)
+}
+
+/// Multiplication in GF(2^8) with the AES polynomial
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut result = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            result ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    result
+}
+
+fn gf_inv(a: u8) -> u8 {
+    // a^254 = a^-1
+    let (mut result, mut base, mut exp) = (1, a, 254u8);
+    while exp != 0 {
+        if exp & 1 != 0 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exp >>= 1;
+    }
+    result
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SharesError {
+    #[error("Invalid threshold")]
+    InvalidThreshold,
+    #[error("Invalid phrase")]
+    InvalidPhrase,
+    #[error("Unknown word: {0}")]
+    UnknownWord(String),
+    #[error("Invalid share")]
+    InvalidShare,
+    #[error("Invalid checksum of the share #{0}")]
+    InvalidShareChecksum(usize),
+    #[error("Shares belong to different secrets")]
+    SharesMismatch,
+    #[error("Share #{0} conflicts with another share with the same index")]
+    ConflictingShares(usize),
+    #[error("Not enough shares")]
+    NotEnoughShares,
+    #[error("Invalid shares")]
+    InvalidShares,
+}
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use nt_utils::TrustMe;
+use serde::{Deserialize, Serialize};
+
+use super::events::KeyStoreLock;
+use super::kdf::KeyEncryption;
+
+/// Core keystore data with the KDF settings of the key passwords.
+///
+/// Settings are stored in the same storage entry as the encrypted keys,
+/// so they are written, exported and restored together with them
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredKeyStore {
+    pub keystore: String,
+    /// KDF settings by the hex encoded cache id of the password
+    pub encryption: HashMap<String, KeyEncryption>,
+}
+
+impl StoredKeyStore {
+    /// Keystore data without KDF settings is loaded as is
+    pub fn parse(data: &str) -> Self {
+        match serde_json::from_str::<Self>(data) {
+            Ok(stored) => stored,
+            Err(_) => Self {
+                keystore: data.to_owned(),
+                encryption: Default::default(),
+            },
+        }
+    }
+
+    pub fn get(&self, cache_id: &ed25519_dalek::PublicKey) -> Option<&KeyEncryption> {
+        self.encryption.get(&hex::encode(cache_id.as_bytes()))
+    }
+}
+
+/// Storage of the core keystore which keeps the KDF settings next to the keys
+pub struct KeyStoreStorage {
+    storage: Arc<dyn nt::external::Storage>,
+    encryption: Mutex<HashMap<String, KeyEncryption>>,
+    staged: Mutex<Option<HashMap<String, KeyEncryption>>>,
+}
+
+impl KeyStoreStorage {
+    pub fn new(storage: Arc<dyn nt::external::Storage>) -> Self {
+        Self {
+            storage,
+            encryption: Default::default(),
+            staged: Default::default(),
+        }
+    }
+
+    pub fn get_encryption(&self, cache_id: &ed25519_dalek::PublicKey) -> Option<KeyEncryption> {
+        self.encryption
+            .lock()
+            .trust_me()
+            .get(&hex::encode(cache_id.as_bytes()))
+            .cloned()
+    }
+
+    /// Changes the KDF settings with the next write of the core keystore,
+    /// so that they are updated in the same write as the keys.
+    ///
+    /// Changes are discarded if the guard is dropped before the keystore is written
+    pub fn stage<F>(&self, _lock: &KeyStoreLock<'_>, f: F) -> StagedEncryption<'_>
+    where
+        F: FnOnce(&mut HashMap<String, KeyEncryption>),
+    {
+        let mut encryption = self.encryption.lock().trust_me().clone();
+        f(&mut encryption);
+        *self.staged.lock().trust_me() = Some(encryption);
+        StagedEncryption { storage: self }
+    }
+
+    /// Stores the KDF settings of the new password with a separate write
+    pub async fn set_encryption(
+        &self,
+        _lock: &KeyStoreLock<'_>,
+        cache_id: &ed25519_dalek::PublicKey,
+        encryption: KeyEncryption,
+    ) -> Result<()> {
+        let mut stored = self.load().await?;
+        stored
+            .encryption
+            .insert(hex::encode(cache_id.as_bytes()), encryption);
+        self.store(stored).await
+    }
+
+    async fn load(&self) -> Result<StoredKeyStore> {
+        Ok(self
+            .storage
+            .get(nt::core::keystore::KEYSTORE_STORAGE_KEY)
+            .await?
+            .map(|data| StoredKeyStore::parse(&data))
+            .unwrap_or_default())
+    }
+
+    async fn store(&self, stored: StoredKeyStore) -> Result<()> {
+        let data = serde_json::to_string(&stored)?;
+        self.storage
+            .set(nt::core::keystore::KEYSTORE_STORAGE_KEY, &data)
+            .await?;
+        *self.encryption.lock().trust_me() = stored.encryption;
+        Ok(())
+    }
+
+    fn take_staged(&self) -> HashMap<String, KeyEncryption> {
+        match self.staged.lock().trust_me().take() {
+            Some(encryption) => encryption,
+            None => self.encryption.lock().trust_me().clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl nt::external::Storage for KeyStoreStorage {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        if key != nt::core::keystore::KEYSTORE_STORAGE_KEY {
+            return self.storage.get(key).await;
+        }
+
+        let stored = match self.storage.get(key).await? {
+            Some(data) => StoredKeyStore::parse(&data),
+            None => {
+                self.encryption.lock().trust_me().clear();
+                return Ok(None);
+            }
+        };
+        *self.encryption.lock().trust_me() = stored.encryption;
+        Ok(Some(stored.keystore))
+    }
+
+    async fn set(&self, key: &str, value: &str) -> Result<()> {
+        if key != nt::core::keystore::KEYSTORE_STORAGE_KEY {
+            return self.storage.set(key, value).await;
+        }
+
+        self.store(StoredKeyStore {
+            keystore: value.to_owned(),
+            encryption: self.take_staged(),
+        })
+        .await
+    }
+
+    fn set_unchecked(&self, key: &str, value: &str) {
+        if key != nt::core::keystore::KEYSTORE_STORAGE_KEY {
+            return self.storage.set_unchecked(key, value);
+        }
+
+        let stored = StoredKeyStore {
+            keystore: value.to_owned(),
+            encryption: self.take_staged(),
+        };
+        self.storage
+            .set_unchecked(key, &serde_json::to_string(&stored).trust_me());
+        *self.encryption.lock().trust_me() = stored.encryption;
+    }
+
+    async fn remove(&self, key: &str) -> Result<()> {
+        self.storage.remove(key).await?;
+        if key == nt::core::keystore::KEYSTORE_STORAGE_KEY {
+            self.encryption.lock().trust_me().clear();
+        }
+        Ok(())
+    }
+
+    fn remove_unchecked(&self, key: &str) {
+        self.storage.remove_unchecked(key);
+        if key == nt::core::keystore::KEYSTORE_STORAGE_KEY {
+            self.encryption.lock().trust_me().clear();
+        }
+    }
+}
+
+/// KDF settings which are written with the next keystore update
+pub struct StagedEncryption<'a> {
+    storage: &'a KeyStoreStorage,
+}
+
+impl Drop for StagedEncryption<'_> {
+    fn drop(&mut self) {
+        self.storage.staged.lock().trust_me().take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::kdf::KdfParams;
+    use super::*;
+
+    const KEYSTORE: &str = r#"{"master_key":"{}"}"#;
+    const CACHE_ID: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+    #[test]
+    fn plain_keystore_is_loaded_without_settings() {
+        let stored = StoredKeyStore::parse(KEYSTORE);
+        assert_eq!(stored.keystore, KEYSTORE);
+        assert!(stored.encryption.is_empty());
+    }
+
+    #[test]
+    fn settings_are_stored_with_the_keys() {
+        let cache_id =
+            ed25519_dalek::PublicKey::from_bytes(&hex::decode(CACHE_ID).unwrap()).unwrap();
+        let encryption = KeyEncryption::new(KdfParams::default()).unwrap();
+
+        let mut stored = StoredKeyStore {
+            keystore: KEYSTORE.to_owned(),
+            encryption: Default::default(),
+        };
+        stored
+            .encryption
+            .insert(CACHE_ID.to_owned(), encryption.clone());
+
+        let stored = StoredKeyStore::parse(&serde_json::to_string(&stored).unwrap());
+        assert_eq!(stored.keystore, KEYSTORE);
+        assert_eq!(stored.get(&cache_id), Some(&encryption));
+    }
+}
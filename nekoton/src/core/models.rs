@@ -14,6 +14,7 @@ use crate::utils::*;
 const TRANSACTION_ADDITIONAL_INFO: &str = r#"
 export type TransactionAdditionalInfo =
     | EnumItem<'comment', string>
+    | EnumItem<'encrypted_comment', EncryptedData>
     | EnumItem<'depool_on_round_complete', DePoolOnRoundCompleteNotification>
     | EnumItem<'depool_receive_answer', DePoolReceiveAnswerNotification>
     | EnumItem<'token_wallet_deployed', TokenWalletDeployedNotification>
@@ -61,6 +62,43 @@ pub fn make_transaction_additional_info(
     )
 }
 
+/// Same as [`make_transaction_additional_info`], but also detects encrypted comments
+/// which are not recognized by the core parser
+pub fn make_transaction_additional_info_ext(
+    transaction: &models::Transaction,
+    data: Option<models::TransactionAdditionalInfo>,
+) -> Option<TransactionAdditionalInfo> {
+    let parse_body = |message: &models::Message| {
+        let body = message.body.as_ref()?;
+        crate::helpers::comment::parse_encrypted_comment_payload(body.data.clone().into())
+    };
+
+    match data {
+        Some(models::TransactionAdditionalInfo::WalletInteraction(data)) => {
+            let encrypted_comments = transaction.out_msgs.iter().filter_map(parse_body).collect();
+
+            Some(
+                ObjectBuilder::new()
+                    .set("type", "wallet_interaction")
+                    .set(
+                        "data",
+                        make_wallet_interaction_info_ext(data, encrypted_comments),
+                    )
+                    .build()
+                    .unchecked_into(),
+            )
+        }
+        Some(data) => make_transaction_additional_info(data),
+        None => parse_body(&transaction.in_msg).map(|data| {
+            ObjectBuilder::new()
+                .set("type", "encrypted_comment")
+                .set("data", crate::core::keystore::make_encrypted_data(data))
+                .build()
+                .unchecked_into()
+        }),
+    }
+}
+
 #[wasm_bindgen(typescript_custom_section)]
 const DEPOOL_ON_ROUND_COMPLETE_NOTIFICATION: &str = r#"
 export type DePoolOnRoundCompleteNotification = {
@@ -163,7 +201,8 @@ export type TonEventStatus =
 const WALLET_INTERACTION_INFO: &str = r#"
 export type WalletInteractionInfo = {
     knownPayload: KnownPayload | undefined,
-    method: WalletInteractionMethod
+    method: WalletInteractionMethod,
+    encryptedComments: EncryptedData[],
 }
 "#;
 
@@ -174,9 +213,37 @@ extern "C" {
 }
 
 pub fn make_wallet_interaction_info(data: models::WalletInteractionInfo) -> WalletInteractionInfo {
+    make_wallet_interaction_info_ext(data, Vec::new())
+}
+
+/// Same as [`make_wallet_interaction_info`], but also includes encrypted comments
+/// of all outgoing messages, which are not recognized by the core parser
+fn make_wallet_interaction_info_ext(
+    data: models::WalletInteractionInfo,
+    encrypted_comments: Vec<nt::crypto::EncryptedData>,
+) -> WalletInteractionInfo {
+    let encrypted_comments = encrypted_comments
+        .into_iter()
+        .map(crate::core::keystore::make_encrypted_data)
+        .collect::<Vec<_>>();
+
+    let known_payload = make_known_payload(data.known_payload).or_else(|| {
+        encrypted_comments
+            .first()
+            .cloned()
+            .map(make_encrypted_comment_payload)
+    });
+
     ObjectBuilder::new()
-        .set("knownPayload", make_known_payload(data.known_payload))
+        .set("knownPayload", known_payload)
         .set("method", make_wallet_interaction_method(data.method))
+        .set(
+            "encryptedComments",
+            encrypted_comments
+                .into_iter()
+                .map(JsValue::from)
+                .collect::<js_sys::Array>(),
+        )
         .build()
         .unchecked_into()
 }
@@ -217,6 +284,7 @@ pub fn make_wallet_interaction_method(
 const KNOWN_PAYLOAD: &str = r#"
 export type KnownPayload =
     | EnumItem<'comment', string>
+    | EnumItem<'encrypted_comment', EncryptedData>
     | EnumItem<'token_outgoing_transfer', { to: TransferRecipient, tokens: string }>
     | EnumItem<'token_swap_back', { tokens: string, callbackAddress: string }>;
 "#;
@@ -256,6 +324,24 @@ pub fn make_known_payload(data: Option<models::KnownPayload>) -> Option<KnownPay
     )
 }
 
+/// Parses known payload, including encrypted comments
+pub fn parse_known_payload(payload: ton_types::SliceData) -> Option<KnownPayload> {
+    match crate::helpers::comment::parse_encrypted_comment_payload(payload.clone()) {
+        Some(data) => Some(make_encrypted_comment_payload(
+            crate::core::keystore::make_encrypted_data(data),
+        )),
+        None => make_known_payload(nt::core::parsing::parse_payload(payload)),
+    }
+}
+
+fn make_encrypted_comment_payload(data: crate::core::keystore::EncryptedData) -> KnownPayload {
+    ObjectBuilder::new()
+        .set("type", "encrypted_comment")
+        .set("data", data)
+        .build()
+        .unchecked_into()
+}
+
 #[wasm_bindgen(typescript_custom_section)]
 const MULTISIG_TRANSACTION_INFO: &str = r#"
 export type MultisigTransactionInfo =
@@ -879,6 +965,140 @@ pub fn parse_transaction_executor_options(
     })
 }
 
+#[wasm_bindgen(typescript_custom_section)]
+const FEE_BREAKDOWN: &str = r#"
+export type FeeBreakdown = {
+    storageFee: string,
+    gasUsed: string,
+    gasFee: string,
+    actionFee: string,
+    fwdFee: string,
+    total: string,
+    exitCode?: number,
+    aborted: boolean,
+    balanceAfter: string,
+    outMessages: OutgoingMessageEstimate[],
+};
+
+/**
+ * Internal message produced by the estimated transaction. It is not executed
+ * at the destination, so its gas must be covered by `value`
+ */
+export type OutgoingMessageEstimate = {
+    destination: string,
+    value: string,
+    fwdFee: string,
+    bounce: boolean,
+    stateInitHash?: string,
+    knownPayload?: KnownPayload,
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "FeeBreakdown")]
+    pub type FeeBreakdown;
+
+    #[wasm_bindgen(typescript_type = "Promise<FeeBreakdown>")]
+    pub type PromiseFeeBreakdown;
+}
+
+/// Splits fees of the locally executed transaction. `balance` is the account balance
+/// before the transaction
+pub fn make_fee_breakdown(
+    transaction: &ton_block::Transaction,
+    balance: u64,
+) -> Result<FeeBreakdown, JsValue> {
+    let descr = match transaction.read_description().handle_error()? {
+        ton_block::TransactionDescr::Ordinary(descr) => descr,
+        _ => return Err(ModelError::UnexpectedTransactionType).handle_error(),
+    };
+
+    let storage_fee = descr
+        .storage_ph
+        .as_ref()
+        .map(|phase| phase.storage_fees_collected.0)
+        .unwrap_or_default();
+    let credit = descr
+        .credit_ph
+        .as_ref()
+        .map(|phase| phase.credit.grams.0)
+        .unwrap_or_default();
+    let (gas_used, gas_fee, exit_code) = match &descr.compute_ph {
+        ton_block::TrComputePhase::Vm(phase) => {
+            (phase.gas_used.0, phase.gas_fees.0, Some(phase.exit_code))
+        }
+        ton_block::TrComputePhase::Skipped(_) => (0, 0, None),
+    };
+    let (action_fee, fwd_fee) = match &descr.action {
+        Some(phase) => (
+            phase
+                .total_action_fees
+                .as_ref()
+                .map(|fees| fees.0)
+                .unwrap_or_default(),
+            phase
+                .total_fwd_fees
+                .as_ref()
+                .map(|fees| fees.0)
+                .unwrap_or_default(),
+        ),
+        None => (0, 0),
+    };
+
+    let out_messages = js_sys::Array::new();
+    let mut sent = 0u128;
+    transaction
+        .out_msgs
+        .iterate(|ton_block::InRefValue(message)| {
+            if let ton_block::CommonMsgInfo::IntMsgInfo(header) = message.header() {
+                sent += header.value.grams.0 + header.fwd_fee.0 + header.ihr_fee.0;
+                out_messages.push(
+                    &ObjectBuilder::new()
+                        .set("destination", header.dst.to_string())
+                        .set("value", header.value.grams.0.to_string())
+                        .set("fwdFee", header.fwd_fee.0.to_string())
+                        .set("bounce", header.bounce)
+                        .set(
+                            "stateInitHash",
+                            message
+                                .state_init()
+                                .and_then(|state_init| {
+                                    ton_block::Serializable::serialize(state_init).ok()
+                                })
+                                .map(|cell| cell.repr_hash().to_hex_string()),
+                        )
+                        .set("knownPayload", message.body().and_then(parse_known_payload))
+                        .build(),
+                );
+            }
+            Ok(true)
+        })
+        .handle_error()?;
+
+    // Forward fees which are left in the outgoing messages are already counted in `sent`
+    let balance_after = (balance as u128 + credit)
+        .saturating_sub(transaction.total_fees.grams.0)
+        .saturating_sub(sent);
+
+    Ok(ObjectBuilder::new()
+        .set("storageFee", storage_fee.to_string())
+        .set("gasUsed", gas_used.to_string())
+        .set("gasFee", gas_fee.to_string())
+        .set("actionFee", action_fee.to_string())
+        .set("fwdFee", fwd_fee.to_string())
+        .set(
+            "total",
+            nt_utils::compute_total_transaction_fees(transaction, &descr).to_string(),
+        )
+        .set("exitCode", exit_code)
+        .set("aborted", descr.aborted)
+        .set("balanceAfter", balance_after.to_string())
+        .set("outMessages", out_messages)
+        .build()
+        .unchecked_into())
+}
+
 #[wasm_bindgen(typescript_custom_section)]
 const TRANSACTION_ID: &str = r#"
 export type TransactionId = {
@@ -943,4 +1163,6 @@ enum ModelError {
     InvalidTransactionId,
     #[error("Invalid transaction execution options")]
     InvalidTransactionExecutionOptions,
+    #[error("Unexpected transaction type")]
+    UnexpectedTransactionType,
 }
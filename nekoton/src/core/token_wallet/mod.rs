@@ -2,6 +2,7 @@ use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
 use num_bigint::BigUint;
+use serde::Deserialize;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::*;
@@ -28,13 +29,18 @@ pub struct TokenWallet {
 }
 
 impl TokenWallet {
-    pub fn new(transport: TransportHandle, wallet: token_wallet::TokenWallet) -> Self {
+    pub fn new(
+        clock: Arc<nt_utils::ClockWithOffset>,
+        transport: TransportHandle,
+        wallet: token_wallet::TokenWallet,
+    ) -> Self {
         Self {
             version: wallet.version().to_string(),
             symbol: wallet.symbol().clone(),
             owner: wallet.owner().to_string(),
             address: wallet.address().to_string(),
             inner: Arc::new(TokenWalletImpl {
+                clock,
                 transport,
                 wallet: Mutex::new(wallet),
             }),
@@ -89,16 +95,25 @@ impl TokenWallet {
         let inner = self.inner.clone();
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
-            let wallet = inner.wallet.lock().trust_me();
+            let (root_token_contract, version) = {
+                let wallet = inner.wallet.lock().trust_me();
+                (
+                    wallet.symbol().root_token_contract.clone(),
+                    wallet.version(),
+                )
+            };
+            let recipient = resolve_transfer_recipient(
+                inner.clock.as_ref(),
+                inner.transport.as_ref(),
+                &root_token_contract,
+                version,
+                dest,
+            )
+            .await?;
 
-            // TODO: resolve token wallet by owner and send directly
+            let wallet = inner.wallet.lock().trust_me();
             let message = wallet
-                .prepare_transfer(
-                    core_models::TransferRecipient::OwnerWallet(dest),
-                    tokens,
-                    notify_receiver,
-                    payload,
-                )
+                .prepare_transfer(recipient, tokens, notify_receiver, payload)
                 .handle_error()?;
 
             crate::core::make_internal_message(message).map(JsValue::from)
@@ -147,6 +162,7 @@ impl TokenWallet {
 }
 
 pub struct TokenWalletImpl {
+    clock: Arc<nt_utils::ClockWithOffset>,
     transport: TransportHandle,
     wallet: Mutex<token_wallet::TokenWallet>,
 }
@@ -368,6 +384,45 @@ pub async fn get_token_root_details_with_user_token_wallet(
     )
 }
 
+/// Tokens are sent directly to the token wallet of the owner if it is deployed,
+/// otherwise to the owner, so that the wallet is deployed by the root contract
+pub async fn resolve_transfer_recipient(
+    clock: &dyn nt_utils::Clock,
+    transport: &dyn nt::transport::Transport,
+    root_token_contract: &ton_block::MsgAddressInt,
+    version: core_models::TokenWalletVersion,
+    owner: ton_block::MsgAddressInt,
+) -> Result<core_models::TransferRecipient, JsValue> {
+    let root_contract_state = match transport
+        .get_contract_state(root_token_contract)
+        .await
+        .handle_error()?
+    {
+        nt::transport::models::RawContractState::Exists(state) => state,
+        nt::transport::models::RawContractState::NotExists => {
+            return Err("Invalid root token contract").handle_error()
+        }
+    };
+    let token_wallet = token_wallet::RootTokenContractState(&root_contract_state)
+        .get_wallet_address(clock, version, &owner)
+        .handle_error()?;
+
+    Ok(
+        match transport
+            .get_contract_state(&token_wallet)
+            .await
+            .handle_error()?
+        {
+            nt::transport::models::RawContractState::Exists(_) => {
+                core_models::TransferRecipient::TokenWallet(token_wallet)
+            }
+            nt::transport::models::RawContractState::NotExists => {
+                core_models::TransferRecipient::OwnerWallet(owner)
+            }
+        },
+    )
+}
+
 pub async fn get_token_wallet_balance(
     clock: &dyn nt_utils::Clock,
     transport: &dyn nt::transport::Transport,
@@ -390,8 +445,129 @@ pub async fn get_token_wallet_balance(
     Ok(balance.to_string())
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedTokenWalletAsset {
+    pub root_token_contract: String,
+}
+
+/// Prepares transfers of all tokens from the wallets of the old owner to the new owner.
+/// Empty and not deployed token wallets, as well as missing root contracts, are skipped.
+///
+/// NOTE: attached amounts are paid by the old owner, so these messages must be sent
+/// before its balance is swept
+pub async fn prepare_token_migration(
+    clock: Arc<nt_utils::ClockWithOffset>,
+    transport: TransportHandle,
+    owner: ton_block::MsgAddressInt,
+    new_owner: ton_block::MsgAddressInt,
+    root_token_contracts: Vec<ton_block::MsgAddressInt>,
+) -> Result<TokenMigrationMessageList, JsValue> {
+    let result = js_sys::Array::new();
+
+    for root_token_contract in root_token_contracts {
+        let root_contract_state = match transport
+            .as_ref()
+            .get_contract_state(&root_token_contract)
+            .await
+            .handle_error()?
+        {
+            nt::transport::models::RawContractState::Exists(state) => state,
+            nt::transport::models::RawContractState::NotExists => continue,
+        };
+        let root_contract_state = token_wallet::RootTokenContractState(&root_contract_state);
+        let details = root_contract_state
+            .guess_details(clock.as_ref())
+            .handle_error()?;
+        let token_wallet_address = root_contract_state
+            .get_wallet_address(clock.as_ref(), details.version, &owner)
+            .handle_error()?;
+
+        let token_wallet_state = match transport
+            .as_ref()
+            .get_contract_state(&token_wallet_address)
+            .await
+            .handle_error()?
+        {
+            nt::transport::models::RawContractState::Exists(state) => state,
+            nt::transport::models::RawContractState::NotExists => continue,
+        };
+        let balance = token_wallet::TokenWalletContractState(&token_wallet_state)
+            .get_balance(clock.as_ref(), details.version)
+            .handle_error()?;
+        if balance == BigUint::default() {
+            continue;
+        }
+
+        let wallet = token_wallet::TokenWallet::subscribe(
+            clock.clone(),
+            transport.clone().into(),
+            owner.clone(),
+            root_token_contract.clone(),
+            Arc::new(TokenMigrationHandler),
+        )
+        .await
+        .handle_error()?;
+
+        let recipient = resolve_transfer_recipient(
+            clock.as_ref(),
+            transport.as_ref(),
+            &root_token_contract,
+            details.version,
+            new_owner.clone(),
+        )
+        .await?;
+        let message = wallet
+            .prepare_transfer(recipient, balance.clone(), false, Default::default())
+            .handle_error()?;
+
+        result.push(
+            &ObjectBuilder::new()
+                .set("rootTokenContract", root_token_contract.to_string())
+                .set("tokenWallet", token_wallet_address.to_string())
+                .set("tokens", balance.to_string())
+                .set("message", crate::core::make_internal_message(message)?)
+                .build(),
+        );
+    }
+
+    Ok(result.unchecked_into())
+}
+
+struct TokenMigrationHandler;
+
+impl token_wallet::TokenWalletSubscriptionHandler for TokenMigrationHandler {
+    fn on_balance_changed(&self, _: BigUint) {}
+
+    fn on_transactions_found(
+        &self,
+        _: Vec<core_models::TransactionWithData<core_models::TokenWalletTransaction>>,
+        _: core_models::TransactionsBatchInfo,
+    ) {
+    }
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const TOKEN_MIGRATION_MESSAGE: &str = r#"
+export type TokenMigrationMessage = {
+    rootTokenContract: string,
+    tokenWallet: string,
+    tokens: string,
+    message: InternalMessage,
+};
+"#;
+
 #[wasm_bindgen]
 extern "C" {
+    #[wasm_bindgen(typescript_type = "Array<TokenWalletAsset>")]
+    pub type TokenWalletAssetList;
+
+    #[wasm_bindgen(typescript_type = "Promise<Array<TokenMigrationMessage>>")]
+    pub type PromiseTokenMigrationMessageList;
+
+    #[wasm_bindgen(typescript_type = "Array<TokenMigrationMessage>")]
+    pub type TokenMigrationMessageList;
+
     #[wasm_bindgen(typescript_type = "Promise<InternalMessage>")]
     pub type PromiseInternalMessage;
 
@@ -2,6 +2,7 @@ use std::convert::TryFrom;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
+use gloo_utils::format::JsValueSerdeExt;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::*;
@@ -14,6 +15,12 @@ use crate::core::models::make_multisig_pending_transaction;
 use crate::transport::TransportHandle;
 use crate::utils::*;
 
+use self::multisig::*;
+use self::transfer::*;
+
+pub mod multisig;
+pub mod transfer;
+
 #[wasm_bindgen]
 pub struct TonWallet {
     #[wasm_bindgen(skip)]
@@ -27,17 +34,69 @@ pub struct TonWallet {
 }
 
 impl TonWallet {
-    pub fn new(transport: TransportHandle, wallet: ton_wallet::TonWallet) -> Self {
+    pub fn new(
+        clock: Arc<nt_utils::ClockWithOffset>,
+        transport: TransportHandle,
+        wallet: ton_wallet::TonWallet,
+        handler: Arc<TonWalletSubscriptionHandler>,
+    ) -> Self {
         Self {
             address: wallet.address().to_string(),
             public_key: hex::encode(wallet.public_key().as_bytes()),
             contract_type: wallet.wallet_type(),
             inner: Arc::new(TonWalletImpl {
+                clock,
                 transport,
                 wallet: Mutex::new(wallet),
+                handler,
+                pending_updates: Default::default(),
+                account_stuff: Default::default(),
             }),
         }
     }
+
+    fn prepare_update_message(
+        &self,
+        raw_current_state: &RawContractState,
+        public_key: &str,
+        method: &str,
+        make_input: impl FnOnce(&ton_abi::Function) -> anyhow::Result<Vec<ton_abi::Token>>,
+        timeout: u32,
+    ) -> Result<crate::crypto::UnsignedMessage, JsValue> {
+        let public_key = parse_public_key(public_key)?;
+        let function = update_function(self.contract_type, method).handle_error()?;
+        let input = make_input(&function).handle_error()?;
+
+        if !matches!(
+            raw_current_state.inner.storage.state,
+            ton_block::AccountState::AccountActive { .. }
+        ) {
+            return Err(TonWalletError::WalletNotDeployed).handle_error();
+        }
+
+        let message =
+            ton_block::Message::with_ext_in_header(ton_block::ExternalInboundMessageHeader {
+                dst: raw_current_state.inner.addr.clone(),
+                ..Default::default()
+            });
+
+        let inner = nt::core::utils::make_labs_unsigned_message(
+            self.inner.clock.as_ref(),
+            message,
+            core_models::Expiration::Timeout(timeout),
+            &public_key,
+            std::borrow::Cow::Owned(function),
+            input,
+        )
+        .handle_error()?;
+
+        Ok(crate::crypto::UnsignedMessage {
+            inner,
+            kind: crate::crypto::UnsignedMessageKind::Call {
+                method: method.to_owned(),
+            },
+        })
+    }
 }
 
 #[wasm_bindgen]
@@ -70,7 +129,10 @@ impl TonWallet {
         let inner = wallet
             .prepare_deploy(core_models::Expiration::Timeout(timeout))
             .handle_error()?;
-        Ok(crate::crypto::UnsignedMessage { inner })
+        Ok(crate::crypto::UnsignedMessage {
+            inner,
+            kind: crate::crypto::UnsignedMessageKind::Deploy,
+        })
     }
 
     #[wasm_bindgen(js_name = "prepareDeployWithMultipleOwners")]
@@ -93,7 +155,10 @@ impl TonWallet {
                 expiration_time,
             )
             .handle_error()?;
-        Ok(crate::crypto::UnsignedMessage { inner })
+        Ok(crate::crypto::UnsignedMessage {
+            inner,
+            kind: crate::crypto::UnsignedMessageKind::Deploy,
+        })
     }
 
     #[wasm_bindgen(js_name = "prepareConfirm")]
@@ -117,7 +182,101 @@ impl TonWallet {
             )
             .handle_error()?;
 
-        Ok(crate::crypto::UnsignedMessage { inner: message })
+        Ok(crate::crypto::UnsignedMessage {
+            inner: message,
+            kind: crate::crypto::UnsignedMessageKind::MultisigConfirm { transaction_id },
+        })
+    }
+
+    #[wasm_bindgen(js_name = "prepareSubmitUpdate")]
+    pub fn prepare_submit_update(
+        &self,
+        raw_current_state: &RawContractState,
+        public_key: &str,
+        params: MultisigUpdateParams,
+        timeout: u32,
+    ) -> Result<crate::crypto::UnsignedMessage, JsValue> {
+        let params = JsValue::into_serde::<ParsedMultisigUpdateParams>(&params).handle_error()?;
+
+        let custodians = params
+            .custodians
+            .map(|custodians| {
+                custodians
+                    .iter()
+                    .map(|public_key| parse_public_key(public_key))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+
+        let code = match params.code {
+            Some(code) => Some(parse_cell(&code)?),
+            None if requires_code(self.contract_type) => {
+                Some(get_account_code(&raw_current_state.inner).handle_error()?)
+            }
+            None => None,
+        };
+
+        let params = UpdateParams {
+            custodians,
+            req_confirms: params.req_confirms,
+            lifetime: params.lifetime,
+            code_hash: code.map(|code| code.repr_hash()),
+        };
+
+        self.prepare_update_message(
+            raw_current_state,
+            public_key,
+            "submitUpdate",
+            |function| make_submit_update_input(function, params),
+            timeout,
+        )
+    }
+
+    #[wasm_bindgen(js_name = "prepareConfirmUpdate")]
+    pub fn prepare_confirm_update(
+        &self,
+        raw_current_state: &RawContractState,
+        public_key: &str,
+        update_id: &str,
+        timeout: u32,
+    ) -> Result<crate::crypto::UnsignedMessage, JsValue> {
+        let update_id = u64::from_str_radix(update_id, 16).handle_error()?;
+
+        self.prepare_update_message(
+            raw_current_state,
+            public_key,
+            "confirmUpdate",
+            |function| make_update_id_input(function, update_id),
+            timeout,
+        )
+    }
+
+    #[wasm_bindgen(js_name = "prepareExecuteUpdate")]
+    pub fn prepare_execute_update(
+        &self,
+        raw_current_state: &RawContractState,
+        public_key: &str,
+        update_id: &str,
+        code: Option<String>,
+        timeout: u32,
+    ) -> Result<crate::crypto::UnsignedMessage, JsValue> {
+        let update_id = u64::from_str_radix(update_id, 16).handle_error()?;
+
+        let code = match code {
+            Some(code) => Some(parse_cell(&code)?),
+            None if requires_code(self.contract_type) => {
+                Some(get_account_code(&raw_current_state.inner).handle_error()?)
+            }
+            None => None,
+        };
+
+        self.prepare_update_message(
+            raw_current_state,
+            public_key,
+            "executeUpdate",
+            |function| make_execute_update_input(function, update_id, code),
+            timeout,
+        )
     }
 
     #[wasm_bindgen(js_name = "prepareTransfer")]
@@ -130,6 +289,8 @@ impl TonWallet {
         bounce: bool,
         body: &str,
         timeout: u32,
+        flags: Option<u8>,
+        state_init: Option<String>,
     ) -> Result<Option<crate::crypto::UnsignedMessage>, JsValue> {
         let public_key = parse_public_key(public_key)?;
         let destination = parse_address(dest)?;
@@ -142,29 +303,186 @@ impl TonWallet {
 
         let mut wallet = self.inner.wallet.lock().unwrap();
 
+        let state_init = state_init.as_deref().map(parse_state_init).transpose()?;
+
+        let gift = ton_wallet::Gift {
+            flags: flags.unwrap_or_else(|| core_models::MessageFlags::Normal.into()),
+            bounce,
+            destination,
+            amount,
+            body,
+            state_init,
+        };
+        validate_gift(&wallet, &gift).handle_error()?;
+        let kind = transfer_message_kind(&wallet, std::slice::from_ref(&gift));
+
         match wallet
             .prepare_transfer(
                 &raw_current_state.inner,
                 &public_key,
-                ton_wallet::Gift {
-                    flags: core_models::MessageFlags::Normal.into(),
-                    bounce,
-                    destination,
-                    amount,
-                    body,
-                    state_init: None,
-                },
+                gift,
                 core_models::Expiration::Timeout(timeout),
             )
             .handle_error()?
         {
             ton_wallet::TransferAction::Sign(inner) => {
-                Ok(Some(crate::crypto::UnsignedMessage { inner }))
+                Ok(Some(crate::crypto::UnsignedMessage { inner, kind }))
             }
             ton_wallet::TransferAction::DeployFirst => Ok(None),
         }
     }
 
+    /// Splits gifts into as few messages as the wallet allows.
+    /// Resulting messages must be sent in the same order
+    #[wasm_bindgen(js_name = "prepareTransferBatch")]
+    pub fn prepare_transfer_batch(
+        &self,
+        raw_current_state: &RawContractState,
+        public_key: &str,
+        gifts: TransferGiftList,
+        timeout: u32,
+    ) -> Result<Option<UnsignedMessageList>, JsValue> {
+        let public_key = parse_public_key(public_key)?;
+        let gifts = JsValue::into_serde::<Vec<ParsedGift>>(&gifts)
+            .handle_error()?
+            .into_iter()
+            .map(ParsedGift::try_into_gift)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if gifts.is_empty() {
+            return Err(TransferError::EmptyBatch).handle_error();
+        }
+
+        let max_messages = max_messages_per_transfer(self.contract_type);
+        if gifts.len() > max_messages && !can_split_transfer(self.contract_type) {
+            return Err(TransferError::TooManyMessages).handle_error();
+        }
+
+        let mut wallet = self.inner.wallet.lock().trust_me();
+        for gift in &gifts {
+            validate_gift(&wallet, gift).handle_error()?;
+        }
+
+        let mut messages = Vec::new();
+        for chunk in gifts.chunks(max_messages) {
+            let kind = transfer_message_kind(&wallet, chunk);
+            match prepare_transfer_chunk(
+                self.inner.clock.as_ref(),
+                &mut wallet,
+                &raw_current_state.inner,
+                &public_key,
+                chunk.to_vec(),
+                core_models::Expiration::Timeout(timeout),
+            )
+            .handle_error()?
+            {
+                ton_wallet::TransferAction::Sign(inner) => {
+                    messages.push(crate::crypto::UnsignedMessage { inner, kind })
+                }
+                ton_wallet::TransferAction::DeployFirst => return Ok(None),
+            }
+        }
+
+        Ok(Some(
+            messages
+                .into_iter()
+                .map(JsValue::from)
+                .collect::<js_sys::Array>()
+                .unchecked_into(),
+        ))
+    }
+
+    /// Prepares a transfer of the whole balance. Uses the `ALL_BALANCE` flag when
+    /// the wallet supports it, otherwise sends the balance minus the locally estimated fees,
+    /// so that nothing is left on the balance after the transaction.
+    ///
+    /// NOTE: token transfers from `prepareTokenMigration` are paid from this balance,
+    /// so they must be sent before the sweep
+    #[wasm_bindgen(js_name = "prepareSweep")]
+    pub fn prepare_sweep(
+        &self,
+        raw_current_state: &RawContractState,
+        public_key: &str,
+        dest: &str,
+        bounce: bool,
+        timeout: u32,
+    ) -> Result<PromiseOptionUnsignedMessage, JsValue> {
+        let public_key = parse_public_key(public_key)?;
+        let destination = parse_address(dest)?;
+        let current_state = raw_current_state.inner.clone();
+        let balance = u64::try_from(current_state.storage.balance.grams.0).handle_error()?;
+
+        let inner = self.inner.clone();
+
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let make_gift = |amount: u64, flags: u8| ton_wallet::Gift {
+                flags,
+                bounce,
+                destination: destination.clone(),
+                amount,
+                body: None,
+                state_init: None,
+            };
+            let expiration = core_models::Expiration::Timeout(timeout);
+
+            let mut wallet = inner.wallet.lock().trust_me();
+
+            let gift = if supports_custom_flags(&wallet) {
+                make_gift(balance, FLAG_ALL_BALANCE)
+            } else {
+                let flags: u8 = core_models::MessageFlags::Normal.into();
+                let message = match wallet
+                    .prepare_transfer(
+                        &current_state,
+                        &public_key,
+                        make_gift(balance, flags),
+                        expiration,
+                    )
+                    .handle_error()?
+                {
+                    ton_wallet::TransferAction::Sign(message) => {
+                        message.sign(&[0; 64]).handle_error()?.message
+                    }
+                    ton_wallet::TransferAction::DeployFirst => return Ok(JsValue::undefined()),
+                };
+
+                let transaction = wallet
+                    .contract_subscription()
+                    .execute_transaction_locally(
+                        &message,
+                        nt::core::TransactionExecutionOptions {
+                            disable_signature_check: true,
+                            override_balance: Some(balance),
+                        },
+                    )
+                    .await
+                    .handle_error()?;
+
+                let fees = compute_sweep_fees(&transaction).handle_error()?;
+                let amount = balance
+                    .checked_sub(fees)
+                    .filter(|amount| *amount > 0)
+                    .ok_or(TransferError::InsufficientBalance)
+                    .handle_error()?;
+                make_gift(amount, flags)
+            };
+
+            let kind = transfer_message_kind(&wallet, std::slice::from_ref(&gift));
+            match wallet
+                .prepare_transfer(&current_state, &public_key, gift, expiration)
+                .handle_error()?
+            {
+                ton_wallet::TransferAction::Sign(inner) => {
+                    Ok(JsValue::from(crate::crypto::UnsignedMessage {
+                        inner,
+                        kind,
+                    }))
+                }
+                ton_wallet::TransferAction::DeployFirst => Ok(JsValue::undefined()),
+            }
+        })))
+    }
+
     #[wasm_bindgen(js_name = "getCustodians")]
     pub fn get_custodians(&self) -> Option<CustodiansList> {
         let inner = self.inner.clone();
@@ -194,25 +512,25 @@ impl TonWallet {
             .unchecked_into()
     }
 
+    #[wasm_bindgen(js_name = "getMultisigPendingUpdates")]
+    pub fn get_pending_updates(&self) -> MultisigPendingUpdateList {
+        let pending_updates = self.inner.pending_updates.lock().trust_me();
+        pending_updates
+            .updates
+            .iter()
+            .map(make_multisig_pending_update)
+            .collect::<js_sys::Array>()
+            .unchecked_into()
+    }
+
     #[wasm_bindgen(js_name = "getContractState")]
     pub fn get_contract_state(&self) -> PromiseOptionRawContractState {
-        use nt::transport::models;
-
-        let address = self.inner.wallet.lock().trust_me().address().clone();
-        let transport = self.inner.transport.clone();
+        let inner = self.inner.clone();
 
         JsCast::unchecked_into(future_to_promise(async move {
-            let contract_state = transport
-                .as_ref()
-                .get_contract_state(&address)
-                .await
-                .handle_error()?;
-
-            Ok(match contract_state {
-                models::RawContractState::Exists(state) => JsValue::from(RawContractState {
-                    inner: state.account,
-                }),
-                models::RawContractState::NotExists => JsValue::undefined(),
+            Ok(match inner.get_account_stuff().await? {
+                Some(account) => JsValue::from(RawContractState { inner: account }),
+                None => JsValue::undefined(),
             })
         }))
     }
@@ -222,7 +540,7 @@ impl TonWallet {
         &self,
         signed_message: crate::crypto::JsSignedMessage,
         execution_options: crate::core::models::TransactionExecutionOptions,
-    ) -> Result<PromiseString, JsValue> {
+    ) -> Result<crate::core::models::PromiseFeeBreakdown, JsValue> {
         let inner = self.inner.clone();
         let message = crate::crypto::parse_signed_message(signed_message)?;
         let execution_options =
@@ -231,20 +549,17 @@ impl TonWallet {
         Ok(JsCast::unchecked_into(future_to_promise(async move {
             let wallet = inner.wallet.lock().trust_me();
 
+            let balance = execution_options
+                .override_balance
+                .unwrap_or(wallet.contract_state().balance);
+
             let transaction = wallet
                 .contract_subscription()
                 .execute_transaction_locally(&message.boc, execution_options)
                 .await
                 .handle_error()?;
 
-            let descr = transaction.read_description().handle_error()?;
-            let fees = if let ton_block::TransactionDescr::Ordinary(descr) = descr {
-                nt_utils::compute_total_transaction_fees(&transaction, &descr)
-            } else {
-                transaction.total_fees.grams.0
-            };
-
-            Ok(JsValue::from(fees.to_string()))
+            crate::core::models::make_fee_breakdown(&transaction, balance).map(JsValue::from)
         })))
     }
 
@@ -275,9 +590,12 @@ impl TonWallet {
         let inner = self.inner.clone();
 
         JsCast::unchecked_into(future_to_promise(async move {
-            let mut wallet = inner.wallet.lock().trust_me();
+            {
+                let mut wallet = inner.wallet.lock().trust_me();
+                wallet.refresh().await.handle_error()?;
+            }
 
-            wallet.refresh().await.handle_error()?;
+            inner.refresh_pending_updates().await?;
             Ok(JsValue::undefined())
         }))
     }
@@ -289,9 +607,12 @@ impl TonWallet {
         JsCast::unchecked_into(future_to_promise(async move {
             let block = inner.transport.get_block(&block_id).await?;
 
-            let mut wallet = inner.wallet.lock().trust_me();
-            wallet.handle_block(&block).await.handle_error()?;
+            {
+                let mut wallet = inner.wallet.lock().trust_me();
+                wallet.handle_block(&block).await.handle_error()?;
+            }
 
+            inner.refresh_pending_updates().await?;
             Ok(JsValue::undefined())
         }))
     }
@@ -318,9 +639,153 @@ impl TonWallet {
     }
 }
 
+fn transfer_message_kind(
+    wallet: &ton_wallet::TonWallet,
+    gifts: &[ton_wallet::Gift],
+) -> crate::crypto::UnsignedMessageKind {
+    match (wallet.wallet_type(), gifts) {
+        (ton_wallet::WalletType::Multisig(_), [gift]) => {
+            crate::crypto::UnsignedMessageKind::MultisigTransfer {
+                destination: gift.destination.to_string(),
+                amount: gift.amount,
+                bounce: gift.bounce,
+                body: gift.body.as_ref().map(|body| body.clone().into_cell()),
+                flags: gift.flags,
+                state_init_hash: gift_state_init_hash(gift),
+                submit: !supports_custom_flags(wallet),
+            }
+        }
+        _ => crate::crypto::UnsignedMessageKind::Transfers {
+            transfers: gifts
+                .iter()
+                .map(|gift| crate::crypto::TransferDescription {
+                    destination: gift.destination.to_string(),
+                    value: gift.amount as u128,
+                    bounce: gift.bounce,
+                    flags: Some(gift.flags),
+                    state_init_hash: gift_state_init_hash(gift),
+                    payload: gift.body.as_ref().map(|body| body.clone().into_cell()),
+                })
+                .collect(),
+        },
+    }
+}
+
+fn gift_state_init_hash(gift: &ton_wallet::Gift) -> Option<ton_types::UInt256> {
+    gift.state_init
+        .as_ref()
+        .and_then(|state_init| ton_block::Serializable::serialize(state_init).ok())
+        .map(|cell| cell.repr_hash())
+}
+
+fn last_transaction_lt(wallet: &ton_wallet::TonWallet) -> u64 {
+    match wallet.contract_state().last_transaction_id {
+        Some(nt_abi::LastTransactionId::Exact(id)) => id.lt,
+        Some(nt_abi::LastTransactionId::Inexact { latest_lt }) => latest_lt,
+        None => 0,
+    }
+}
+
 pub struct TonWalletImpl {
+    clock: Arc<nt_utils::ClockWithOffset>,
     transport: TransportHandle,
     wallet: Mutex<ton_wallet::TonWallet>,
+    handler: Arc<TonWalletSubscriptionHandler>,
+    pending_updates: Mutex<PendingUpdatesState>,
+    account_stuff: Mutex<Option<CachedAccountStuff>>,
+}
+
+/// Full state of the wallet, fetched for the latest known transaction
+struct CachedAccountStuff {
+    last_lt: u64,
+    account: Option<ton_block::AccountStuff>,
+}
+
+#[derive(Default)]
+struct PendingUpdatesState {
+    last_lt: Option<u64>,
+    updates: Vec<PendingUpdate>,
+}
+
+impl TonWalletImpl {
+    /// Returns the full wallet state. It is reused until the subscription
+    /// receives a new transaction
+    async fn get_account_stuff(&self) -> Result<Option<ton_block::AccountStuff>, JsValue> {
+        use nt::transport::models;
+
+        let (address, last_lt) = {
+            let wallet = self.wallet.lock().trust_me();
+            (wallet.address().clone(), last_transaction_lt(&wallet))
+        };
+
+        if let Some(cached) = &*self.account_stuff.lock().trust_me() {
+            if cached.last_lt == last_lt {
+                return Ok(cached.account.clone());
+            }
+        }
+
+        let account = match self
+            .transport
+            .as_ref()
+            .get_contract_state(&address)
+            .await
+            .handle_error()?
+        {
+            models::RawContractState::Exists(state) => Some(state.account),
+            models::RawContractState::NotExists => None,
+        };
+
+        *self.account_stuff.lock().trust_me() = Some(CachedAccountStuff {
+            last_lt,
+            account: account.clone(),
+        });
+        Ok(account)
+    }
+
+    /// Reloads pending custodian updates when the wallet state changes
+    async fn refresh_pending_updates(&self) -> Result<(), JsValue> {
+        let (wallet_type, last_lt, is_deployed) = {
+            let wallet = self.wallet.lock().trust_me();
+            let wallet_type = wallet.wallet_type();
+            if !supports_updates(wallet_type) {
+                return Ok(());
+            }
+            (
+                wallet_type,
+                last_transaction_lt(&wallet),
+                wallet.contract_state().is_deployed,
+            )
+        };
+
+        if self.pending_updates.lock().trust_me().last_lt == Some(last_lt) {
+            return Ok(());
+        }
+
+        // Wallets which are not deployed yet have no pending updates
+        let updates = match self.get_account_stuff().await? {
+            Some(account) if is_deployed => {
+                get_pending_updates(self.clock.as_ref(), wallet_type, account).handle_error()?
+            }
+            _ => Vec::new(),
+        };
+
+        let changed = {
+            let mut pending_updates = self.pending_updates.lock().trust_me();
+            pending_updates.last_lt = Some(last_lt);
+            if pending_updates.updates != updates {
+                pending_updates.updates = updates.clone();
+                true
+            } else {
+                false
+            }
+        };
+
+        if changed {
+            self.handler.on_unconfirmed_updates_changed(&updates);
+        }
+
+        Ok(())
+    }
 }
 
 #[wasm_bindgen]
@@ -368,6 +833,12 @@ extern "C" {
         this: &TonWalletSubscriptionHandlerImpl,
         transactions: MultisigPendingTransactionList,
     );
+
+    #[wasm_bindgen(method, js_name = "onUnconfirmedUpdatesChanged")]
+    pub fn on_unconfirmed_updates_changed(
+        this: &TonWalletSubscriptionHandlerImpl,
+        updates: MultisigPendingUpdateList,
+    );
 }
 
 unsafe impl Send for TonWalletSubscriptionHandlerImpl {}
@@ -384,6 +855,18 @@ impl From<TonWalletSubscriptionHandlerImpl> for TonWalletSubscriptionHandler {
     }
 }
 
+impl TonWalletSubscriptionHandler {
+    fn on_unconfirmed_updates_changed(&self, updates: &[PendingUpdate]) {
+        self.inner.on_unconfirmed_updates_changed(
+            updates
+                .iter()
+                .map(make_multisig_pending_update)
+                .collect::<js_sys::Array>()
+                .unchecked_into(),
+        );
+    }
+}
+
 impl ton_wallet::TonWalletSubscriptionHandler for TonWalletSubscriptionHandler {
     fn on_message_sent(
         &self,
@@ -489,6 +972,91 @@ extern "C" {
     pub type CustodiansList;
 }
 
+#[wasm_bindgen(typescript_custom_section)]
+const TRANSFER_GIFT: &str = r#"
+export type TransferGift = {
+    destination: string,
+    amount: string,
+    bounce: boolean,
+    body?: string,
+    flags?: number,
+    stateInit?: string,
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Array<TransferGift>")]
+    pub type TransferGiftList;
+
+    #[wasm_bindgen(typescript_type = "Array<UnsignedMessage>")]
+    pub type UnsignedMessageList;
+
+    #[wasm_bindgen(typescript_type = "Promise<UnsignedMessage | undefined>")]
+    pub type PromiseOptionUnsignedMessage;
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const MULTISIG_UPDATE: &str = r#"
+export type MultisigUpdateParams = {
+    custodians?: string[],
+    reqConfirms?: number,
+    lifetime?: number,
+    code?: string,
+};
+
+export type MultisigPendingUpdate = {
+    id: string,
+    index: number,
+    creator: string,
+    signsReceived: number,
+    confirmations: string[],
+    codeHash?: string,
+    custodians?: string[],
+    reqConfirms?: number,
+    lifetime?: number,
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "MultisigUpdateParams")]
+    pub type MultisigUpdateParams;
+
+    #[wasm_bindgen(typescript_type = "MultisigPendingUpdate")]
+    pub type MultisigPendingUpdate;
+
+    #[wasm_bindgen(typescript_type = "Array<MultisigPendingUpdate>")]
+    pub type MultisigPendingUpdateList;
+}
+
+fn make_multisig_pending_update(data: &PendingUpdate) -> MultisigPendingUpdate {
+    let make_list = |items: &[ton_types::UInt256]| {
+        items
+            .iter()
+            .map(|item| JsValue::from(item.to_hex_string()))
+            .collect::<js_sys::Array>()
+    };
+
+    ObjectBuilder::new()
+        .set("id", format!("{:x}", data.id))
+        .set("index", data.index)
+        .set("creator", data.creator.to_hex_string())
+        .set("signsReceived", data.signs)
+        .set("confirmations", make_list(&data.confirmations))
+        .set(
+            "codeHash",
+            data.code_hash
+                .as_ref()
+                .map(ton_types::UInt256::to_hex_string),
+        )
+        .set("custodians", data.custodians.as_deref().map(make_list))
+        .set("reqConfirms", data.req_confirms)
+        .set("lifetime", data.lifetime)
+        .build()
+        .unchecked_into()
+}
+
 fn parse_custodians_list(
     custodians: CustodiansList,
 ) -> Result<Vec<ed25519_dalek::PublicKey>, JsValue> {
@@ -534,6 +1102,23 @@ export type ContractType =
     | 'HighloadWalletV2';
 "#;
 
+pub fn all_wallet_types() -> Vec<ton_wallet::WalletType> {
+    [
+        "SafeMultisigWallet",
+        "SafeMultisigWallet24h",
+        "SetcodeMultisigWallet",
+        "BridgeMultisigWallet",
+        "SurfWallet",
+        "WalletV3",
+        "EverWallet",
+        "Multisig2",
+        "HighloadWalletV2",
+    ]
+    .iter()
+    .map(|name| ton_wallet::WalletType::from_str(name).trust_me())
+    .collect()
+}
+
 impl TryFrom<ContractType> for nt::core::ton_wallet::WalletType {
     type Error = JsValue;
 
@@ -576,16 +1161,11 @@ extern "C" {
 fn make_ton_wallet_transaction(
     data: core_models::TransactionWithData<core_models::TransactionAdditionalInfo>,
 ) -> TonWalletTransaction {
+    let info =
+        crate::core::models::make_transaction_additional_info_ext(&data.transaction, data.data);
     let transaction = crate::core::models::make_transaction(data.transaction);
-    if let Some(data) = data.data {
-        js_sys::Reflect::set(
-            &transaction,
-            &JsValue::from_str("info"),
-            &crate::core::models::make_transaction_additional_info(data)
-                .map(|item| item.unchecked_into())
-                .unwrap_or_else(JsValue::undefined),
-        )
-        .trust_me();
+    if let Some(info) = info {
+        js_sys::Reflect::set(&transaction, &JsValue::from_str("info"), &info).trust_me();
     }
     transaction.unchecked_into()
 }
@@ -611,4 +1191,6 @@ enum TonWalletError {
     ExpectedArray,
     #[error("Expected public key string")]
     ExpectedPublicKeyString,
+    #[error("Wallet not deployed")]
+    WalletNotDeployed,
 }
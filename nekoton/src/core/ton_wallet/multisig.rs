@@ -0,0 +1,733 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use anyhow::Result;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use serde::Deserialize;
+use ton_abi::{ParamType, Token, TokenValue};
+use ton_types::{Cell, UInt256};
+
+use nt::core::ton_wallet::{MultisigType, WalletType};
+use nt_abi::FunctionExt;
+
+const MULTISIG2_UPDATE_ABI: &str = r#"{
+    "ABI version": 2,
+    "version": "2.3",
+    "header": ["pubkey", "time", "expire"],
+    "functions": [
+        {
+            "name": "submitUpdate",
+            "inputs": [
+                {"name": "codeHash", "type": "optional(uint256)"},
+                {"name": "owners", "type": "optional(uint256[])"},
+                {"name": "reqConfirms", "type": "optional(uint8)"},
+                {"name": "lifetime", "type": "optional(uint32)"}
+            ],
+            "outputs": [{"name": "updateId", "type": "uint64"}]
+        },
+        {
+            "name": "confirmUpdate",
+            "inputs": [{"name": "updateId", "type": "uint64"}],
+            "outputs": []
+        },
+        {
+            "name": "executeUpdate",
+            "inputs": [
+                {"name": "updateId", "type": "uint64"},
+                {"name": "code", "type": "optional(cell)"}
+            ],
+            "outputs": []
+        },
+        {
+            "name": "getUpdateRequests",
+            "inputs": [],
+            "outputs": [
+                {
+                    "name": "updates",
+                    "type": "tuple[]",
+                    "components": [
+                        {"name": "id", "type": "uint64"},
+                        {"name": "index", "type": "uint8"},
+                        {"name": "signs", "type": "uint8"},
+                        {"name": "confirmationsMask", "type": "uint32"},
+                        {"name": "creator", "type": "uint256"},
+                        {"name": "codeHash", "type": "optional(uint256)"},
+                        {"name": "custodians", "type": "optional(uint256[])"},
+                        {"name": "reqConfirms", "type": "optional(uint8)"},
+                        {"name": "lifetime", "type": "optional(uint32)"}
+                    ]
+                }
+            ]
+        },
+        {
+            "name": "getCustodians",
+            "inputs": [],
+            "outputs": [
+                {
+                    "name": "custodians",
+                    "type": "tuple[]",
+                    "components": [
+                        {"name": "index", "type": "uint8"},
+                        {"name": "pubkey", "type": "uint256"}
+                    ]
+                }
+            ]
+        }
+    ],
+    "data": [],
+    "events": []
+}"#;
+
+const SETCODE_MULTISIG_UPDATE_ABI: &str = r#"{
+    "ABI version": 2,
+    "header": ["pubkey", "time", "expire"],
+    "functions": [
+        {
+            "name": "submitUpdate",
+            "inputs": [
+                {"name": "codeHash", "type": "uint256"},
+                {"name": "owners", "type": "uint256[]"},
+                {"name": "reqConfirms", "type": "uint8"}
+            ],
+            "outputs": [{"name": "updateId", "type": "uint64"}]
+        },
+        {
+            "name": "confirmUpdate",
+            "inputs": [{"name": "updateId", "type": "uint64"}],
+            "outputs": []
+        },
+        {
+            "name": "executeUpdate",
+            "inputs": [
+                {"name": "updateId", "type": "uint64"},
+                {"name": "code", "type": "cell"}
+            ],
+            "outputs": []
+        },
+        {
+            "name": "getUpdateRequests",
+            "inputs": [],
+            "outputs": [
+                {
+                    "name": "updates",
+                    "type": "tuple[]",
+                    "components": [
+                        {"name": "id", "type": "uint64"},
+                        {"name": "index", "type": "uint8"},
+                        {"name": "signs", "type": "uint8"},
+                        {"name": "confirmationsMask", "type": "uint32"},
+                        {"name": "creator", "type": "uint256"},
+                        {"name": "codeHash", "type": "uint256"},
+                        {"name": "custodians", "type": "uint256[]"},
+                        {"name": "reqConfirms", "type": "uint8"}
+                    ]
+                }
+            ]
+        },
+        {
+            "name": "getCustodians",
+            "inputs": [],
+            "outputs": [
+                {
+                    "name": "custodians",
+                    "type": "tuple[]",
+                    "components": [
+                        {"name": "index", "type": "uint8"},
+                        {"name": "pubkey", "type": "uint256"}
+                    ]
+                }
+            ]
+        }
+    ],
+    "data": [],
+    "events": []
+}"#;
+
+pub fn supports_updates(wallet_type: WalletType) -> bool {
+    update_abi(wallet_type).is_some()
+}
+
+fn update_abi(wallet_type: WalletType) -> Option<&'static str> {
+    match wallet_type {
+        WalletType::Multisig(MultisigType::Multisig2) => Some(MULTISIG2_UPDATE_ABI),
+        WalletType::Multisig(MultisigType::SetcodeMultisigWallet) => {
+            Some(SETCODE_MULTISIG_UPDATE_ABI)
+        }
+        _ => None,
+    }
+}
+
+/// Old multisig requires code hash in every update, so the current code is used by default
+pub fn requires_code(wallet_type: WalletType) -> bool {
+    matches!(
+        wallet_type,
+        WalletType::Multisig(MultisigType::SetcodeMultisigWallet)
+    )
+}
+
+pub fn get_account_code(account: &ton_block::AccountStuff) -> Result<Cell> {
+    match &account.storage.state {
+        ton_block::AccountState::AccountActive {
+            state_init: ton_block::StateInit {
+                code: Some(code), ..
+            },
+        } => Ok(code.clone()),
+        _ => Err(MultisigUpdateError::CodeNotFound.into()),
+    }
+}
+
+const MULTISIG2_TRANSFER_ABI: &str = r#"{
+    "ABI version": 2,
+    "version": "2.3",
+    "header": ["pubkey", "time", "expire"],
+    "functions": [
+        {
+            "name": "sendTransaction",
+            "inputs": [
+                {"name": "dest", "type": "address"},
+                {"name": "value", "type": "uint128"},
+                {"name": "bounce", "type": "bool"},
+                {"name": "flags", "type": "uint8"},
+                {"name": "payload", "type": "cell"}
+            ],
+            "outputs": []
+        },
+        {
+            "name": "submitTransaction",
+            "inputs": [
+                {"name": "dest", "type": "address"},
+                {"name": "value", "type": "uint128"},
+                {"name": "bounce", "type": "bool"},
+                {"name": "allBalance", "type": "bool"},
+                {"name": "payload", "type": "cell"},
+                {"name": "stateInit", "type": "optional(cell)"}
+            ],
+            "outputs": [{"name": "transId", "type": "uint64"}]
+        },
+        {
+            "name": "confirmTransaction",
+            "inputs": [{"name": "transactionId", "type": "uint64"}],
+            "outputs": []
+        }
+    ],
+    "data": [],
+    "events": []
+}"#;
+
+const SETCODE_MULTISIG_TRANSFER_ABI: &str = r#"{
+    "ABI version": 2,
+    "header": ["pubkey", "time", "expire"],
+    "functions": [
+        {
+            "name": "sendTransaction",
+            "inputs": [
+                {"name": "dest", "type": "address"},
+                {"name": "value", "type": "uint128"},
+                {"name": "bounce", "type": "bool"},
+                {"name": "flags", "type": "uint8"},
+                {"name": "payload", "type": "cell"}
+            ],
+            "outputs": []
+        },
+        {
+            "name": "submitTransaction",
+            "inputs": [
+                {"name": "dest", "type": "address"},
+                {"name": "value", "type": "uint128"},
+                {"name": "bounce", "type": "bool"},
+                {"name": "allBalance", "type": "bool"},
+                {"name": "payload", "type": "cell"}
+            ],
+            "outputs": [{"name": "transId", "type": "uint64"}]
+        },
+        {
+            "name": "confirmTransaction",
+            "inputs": [{"name": "transactionId", "type": "uint64"}],
+            "outputs": []
+        }
+    ],
+    "data": [],
+    "events": []
+}"#;
+
+/// Transfer methods of all multisig wallets, so that their messages
+/// can be described without the contract ABI
+pub fn transfer_abis() -> Vec<ton_abi::Contract> {
+    [MULTISIG2_TRANSFER_ABI, SETCODE_MULTISIG_TRANSFER_ABI]
+        .iter()
+        .filter_map(|abi| ton_abi::Contract::load(*abi).ok())
+        .collect()
+}
+
+pub fn update_function(wallet_type: WalletType, name: &str) -> Result<ton_abi::Function> {
+    let abi = update_abi(wallet_type).ok_or(MultisigUpdateError::UpdatesNotSupported)?;
+    let contract = ton_abi::Contract::load(abi)?;
+    Ok(contract.function(name)?.clone())
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedMultisigUpdateParams {
+    #[serde(default)]
+    pub custodians: Option<Vec<String>>,
+    #[serde(default)]
+    pub req_confirms: Option<u8>,
+    #[serde(default)]
+    pub lifetime: Option<u32>,
+    /// Base64 encoded new code
+    #[serde(default)]
+    pub code: Option<String>,
+}
+
+pub struct UpdateParams {
+    pub custodians: Option<Vec<ed25519_dalek::PublicKey>>,
+    pub req_confirms: Option<u8>,
+    pub lifetime: Option<u32>,
+    pub code_hash: Option<UInt256>,
+}
+
+pub fn make_submit_update_input(
+    function: &ton_abi::Function,
+    params: UpdateParams,
+) -> Result<Vec<Token>> {
+    let mut values = HashMap::new();
+    values.insert(
+        "codeHash",
+        params.code_hash.map(|hash| make_uint256(hash.as_slice())),
+    );
+    values.insert(
+        "owners",
+        params.custodians.map(|custodians| {
+            TokenValue::Array(
+                ParamType::Uint(256),
+                custodians
+                    .iter()
+                    .map(|public_key| make_uint256(public_key.as_bytes()))
+                    .collect(),
+            )
+        }),
+    );
+    values.insert("reqConfirms", params.req_confirms.map(|n| make_uint(n, 8)));
+    values.insert("lifetime", params.lifetime.map(|n| make_uint(n, 32)));
+    make_input(function, values)
+}
+
+pub fn make_update_id_input(function: &ton_abi::Function, update_id: u64) -> Result<Vec<Token>> {
+    let mut values = HashMap::new();
+    values.insert("updateId", Some(make_uint(update_id, 64)));
+    make_input(function, values)
+}
+
+pub fn make_execute_update_input(
+    function: &ton_abi::Function,
+    update_id: u64,
+    code: Option<Cell>,
+) -> Result<Vec<Token>> {
+    let mut values = HashMap::new();
+    values.insert("updateId", Some(make_uint(update_id, 64)));
+    values.insert("code", code.map(TokenValue::Cell));
+    make_input(function, values)
+}
+
+fn make_input(
+    function: &ton_abi::Function,
+    values: HashMap<&str, Option<TokenValue>>,
+) -> Result<Vec<Token>> {
+    make_tokens(&function.inputs, values)
+}
+
+/// Fills params by name. Missing values are allowed only for optional params
+fn make_tokens(
+    params: &[ton_abi::Param],
+    mut values: HashMap<&str, Option<TokenValue>>,
+) -> Result<Vec<Token>> {
+    let mut input = Vec::with_capacity(params.len());
+    for param in params {
+        let value = values.remove(param.name.as_str()).flatten();
+        let value = match (&param.kind, value) {
+            (ParamType::Optional(kind), value) => {
+                TokenValue::Optional(kind.as_ref().clone(), value.map(Box::new))
+            }
+            (_, Some(value)) => value,
+            (_, None) => return Err(MultisigUpdateError::MissingParam(param.name.clone()).into()),
+        };
+        input.push(Token::new(&param.name, value));
+    }
+
+    if let Some((name, _)) = values.into_iter().find(|(_, value)| value.is_some()) {
+        return Err(MultisigUpdateError::UnsupportedParam(name.to_owned()).into());
+    }
+
+    Ok(input)
+}
+
+fn make_uint<T: Into<BigUint>>(number: T, size: usize) -> TokenValue {
+    TokenValue::Uint(ton_abi::Uint {
+        number: number.into(),
+        size,
+    })
+}
+
+fn make_uint256(bytes: &[u8]) -> TokenValue {
+    make_uint(BigUint::from_bytes_be(bytes), 256)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingUpdate {
+    pub id: u64,
+    pub index: u8,
+    pub signs: u8,
+    pub confirmations: Vec<UInt256>,
+    pub creator: UInt256,
+    pub code_hash: Option<UInt256>,
+    pub custodians: Option<Vec<UInt256>>,
+    pub req_confirms: Option<u8>,
+    pub lifetime: Option<u32>,
+}
+
+pub fn get_pending_updates(
+    clock: &dyn nt_utils::Clock,
+    wallet_type: WalletType,
+    account_stuff: ton_block::AccountStuff,
+) -> Result<Vec<PendingUpdate>> {
+    let custodians = get_custodian_indices(clock, wallet_type, account_stuff.clone())?;
+
+    let function = update_function(wallet_type, "getUpdateRequests")?;
+    let output = function.run_local(clock, account_stuff, &[])?;
+
+    Ok(parse_pending_updates(output.tokens, &custodians)?)
+}
+
+fn parse_pending_updates(
+    tokens: Option<Vec<Token>>,
+    custodians: &[(u8, UInt256)],
+) -> Result<Vec<PendingUpdate>, MultisigUpdateError> {
+    let updates = tokens
+        .and_then(|tokens| tokens.into_iter().next())
+        .ok_or(MultisigUpdateError::InvalidGetterOutput)?;
+
+    let updates = match updates.value {
+        TokenValue::Array(_, updates) => updates,
+        _ => return Err(MultisigUpdateError::InvalidGetterOutput),
+    };
+
+    updates
+        .into_iter()
+        .map(|update| parse_pending_update(update, custodians))
+        .collect()
+}
+
+/// Confirmation masks use custodian indices from the contract, which don't match
+/// the order of the public keys
+fn get_custodian_indices(
+    clock: &dyn nt_utils::Clock,
+    wallet_type: WalletType,
+    account_stuff: ton_block::AccountStuff,
+) -> Result<Vec<(u8, UInt256)>> {
+    let function = update_function(wallet_type, "getCustodians")?;
+    let output = function.run_local(clock, account_stuff, &[])?;
+
+    let custodians = match output.tokens.and_then(|tokens| tokens.into_iter().next()) {
+        Some(Token {
+            value: TokenValue::Array(_, custodians),
+            ..
+        }) => custodians,
+        _ => return Err(MultisigUpdateError::InvalidGetterOutput.into()),
+    };
+
+    custodians
+        .into_iter()
+        .map(|custodian| {
+            let fields = match custodian {
+                TokenValue::Tuple(fields) => fields,
+                _ => return None,
+            };
+            let mut index = None;
+            let mut pubkey = None;
+            for field in fields {
+                match field.name.as_str() {
+                    "index" => index = parse_uint(&field.value)?.to_u8(),
+                    "pubkey" => pubkey = parse_uint256(&field.value),
+                    _ => {}
+                }
+            }
+            Some((index?, pubkey?))
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| MultisigUpdateError::InvalidGetterOutput.into())
+}
+
+fn parse_pending_update(
+    update: TokenValue,
+    custodians: &[(u8, UInt256)],
+) -> Result<PendingUpdate, MultisigUpdateError> {
+    let fields = match update {
+        TokenValue::Tuple(fields) => UpdateFields(
+            fields
+                .into_iter()
+                .map(|token| (token.name, unwrap_optional(token.value)))
+                .collect(),
+        ),
+        _ => return Err(MultisigUpdateError::InvalidGetterOutput),
+    };
+
+    let confirmations_mask = fields.required_uint::<u32>("confirmationsMask")?;
+    let confirmations = custodians
+        .iter()
+        .filter(|(index, _)| *index < 32 && confirmations_mask & (1 << *index) != 0)
+        .map(|(_, custodian)| *custodian)
+        .collect();
+
+    let update_custodians = match fields.get("custodians") {
+        Some(TokenValue::Array(_, items)) => Some(
+            items
+                .iter()
+                .map(parse_uint256)
+                .collect::<Option<Vec<_>>>()
+                .ok_or(MultisigUpdateError::InvalidUpdateField("custodians"))?,
+        ),
+        Some(_) => return Err(MultisigUpdateError::InvalidUpdateField("custodians")),
+        None => None,
+    };
+
+    Ok(PendingUpdate {
+        id: fields.required_uint("id")?,
+        index: fields.required_uint("index")?,
+        signs: fields.required_uint("signs")?,
+        confirmations,
+        creator: fields
+            .uint256("creator")?
+            .ok_or(MultisigUpdateError::InvalidUpdateField("creator"))?,
+        code_hash: fields.uint256("codeHash")?,
+        custodians: update_custodians,
+        req_confirms: fields.uint("reqConfirms")?,
+        lifetime: fields.uint("lifetime")?,
+    })
+}
+
+/// Tuple fields by name. Empty optionals are stored as `None`
+struct UpdateFields(HashMap<String, Option<TokenValue>>);
+
+impl UpdateFields {
+    fn get(&self, name: &str) -> Option<&TokenValue> {
+        self.0.get(name)?.as_ref()
+    }
+
+    /// Fails if the value doesn't fit into `T`, instead of truncating it
+    fn uint<T: TryFrom<u64>>(&self, name: &'static str) -> Result<Option<T>, MultisigUpdateError> {
+        self.get(name)
+            .map(|value| {
+                parse_uint(value)
+                    .and_then(ToPrimitive::to_u64)
+                    .and_then(|value| T::try_from(value).ok())
+                    .ok_or(MultisigUpdateError::InvalidUpdateField(name))
+            })
+            .transpose()
+    }
+
+    fn required_uint<T: TryFrom<u64>>(&self, name: &'static str) -> Result<T, MultisigUpdateError> {
+        self.uint(name)?
+            .ok_or(MultisigUpdateError::InvalidUpdateField(name))
+    }
+
+    fn uint256(&self, name: &'static str) -> Result<Option<UInt256>, MultisigUpdateError> {
+        self.get(name)
+            .map(|value| parse_uint256(value).ok_or(MultisigUpdateError::InvalidUpdateField(name)))
+            .transpose()
+    }
+}
+
+fn unwrap_optional(value: TokenValue) -> Option<TokenValue> {
+    match value {
+        TokenValue::Optional(_, value) => value.map(|value| *value),
+        value => Some(value),
+    }
+}
+
+fn parse_uint(value: &TokenValue) -> Option<&BigUint> {
+    match value {
+        TokenValue::Uint(value) => Some(&value.number),
+        _ => None,
+    }
+}
+
+fn parse_uint256(value: &TokenValue) -> Option<UInt256> {
+    let bytes = parse_uint(value)?.to_bytes_be();
+    if bytes.len() > 32 {
+        return None;
+    }
+    let mut result = [0u8; 32];
+    result[32 - bytes.len()..].copy_from_slice(&bytes);
+    Some(UInt256::from(result))
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum MultisigUpdateError {
+    #[error("Custodians update is not supported by this wallet")]
+    UpdatesNotSupported,
+    #[error("Missing update param: {0}")]
+    MissingParam(String),
+    #[error("Update param is not supported by this wallet: {0}")]
+    UnsupportedParam(String),
+    #[error("Wallet code not found")]
+    CodeNotFound,
+    #[error("Invalid getter output")]
+    InvalidGetterOutput,
+    #[error("Invalid pending update field: {0}")]
+    InvalidUpdateField(&'static str),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUSTODIANS: [(u8, [u8; 32]); 3] = [(0, [1; 32]), (2, [2; 32]), (1, [3; 32])];
+
+    fn custodians() -> Vec<(u8, UInt256)> {
+        CUSTODIANS
+            .iter()
+            .map(|(index, pubkey)| (*index, UInt256::from(*pubkey)))
+            .collect()
+    }
+
+    /// Encodes the getter output the same way as the contract does
+    fn encode_updates(
+        function: &ton_abi::Function,
+        updates: Vec<HashMap<&str, Option<TokenValue>>>,
+    ) -> ton_types::SliceData {
+        let kind = match &function.outputs[0].kind {
+            ParamType::Array(kind) => kind.as_ref().clone(),
+            _ => panic!("updates must be an array"),
+        };
+        let components = match &kind {
+            ParamType::Tuple(components) => components.clone(),
+            _ => panic!("update must be a tuple"),
+        };
+
+        let updates = updates
+            .into_iter()
+            .map(|values| TokenValue::Tuple(make_tokens(&components, values).unwrap()))
+            .collect();
+        let output = [Token::new("updates", TokenValue::Array(kind, updates))];
+
+        function
+            .encode_internal_output(function.output_id, &output)
+            .unwrap()
+            .into_cell()
+            .unwrap()
+            .into()
+    }
+
+    fn decode_updates(
+        wallet_type: WalletType,
+        updates: Vec<HashMap<&str, Option<TokenValue>>>,
+    ) -> Result<Vec<PendingUpdate>, MultisigUpdateError> {
+        let function = update_function(wallet_type, "getUpdateRequests").unwrap();
+        let output = function
+            .decode_output(encode_updates(&function, updates), true)
+            .unwrap();
+        parse_pending_updates(Some(output), &custodians())
+    }
+
+    fn make_update(
+        creator: u8,
+        confirmations_mask: u32,
+    ) -> HashMap<&'static str, Option<TokenValue>> {
+        let mut values = HashMap::new();
+        values.insert("id", Some(make_uint(0x6000_0000_0000_0001u64, 64)));
+        values.insert("index", Some(make_uint(creator, 8)));
+        values.insert("signs", Some(make_uint(2u8, 8)));
+        values.insert("confirmationsMask", Some(make_uint(confirmations_mask, 32)));
+        values.insert("creator", Some(make_uint256(&[creator + 1; 32])));
+        values
+    }
+
+    #[test]
+    fn decodes_setcode_multisig_updates() {
+        let mut update = make_update(0, 0b101);
+        update.insert("codeHash", Some(make_uint256(&[0xaa; 32])));
+        update.insert(
+            "custodians",
+            Some(TokenValue::Array(
+                ParamType::Uint(256),
+                vec![make_uint256(&[4; 32]), make_uint256(&[5; 32])],
+            )),
+        );
+        update.insert("reqConfirms", Some(make_uint(2u8, 8)));
+
+        let wallet_type = WalletType::Multisig(MultisigType::SetcodeMultisigWallet);
+        let updates = decode_updates(wallet_type, vec![update]).unwrap();
+        assert_eq!(
+            updates,
+            vec![PendingUpdate {
+                id: 0x6000_0000_0000_0001,
+                index: 0,
+                signs: 2,
+                confirmations: vec![UInt256::from([1; 32]), UInt256::from([2; 32])],
+                creator: UInt256::from([1; 32]),
+                code_hash: Some(UInt256::from([0xaa; 32])),
+                custodians: Some(vec![UInt256::from([4; 32]), UInt256::from([5; 32])]),
+                req_confirms: Some(2),
+                lifetime: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn decodes_multisig2_updates() {
+        let mut update = make_update(1, 0b10);
+        update.insert("reqConfirms", Some(make_uint(3u8, 8)));
+        update.insert("lifetime", Some(make_uint(3600u32, 32)));
+
+        let wallet_type = WalletType::Multisig(MultisigType::Multisig2);
+        let updates = decode_updates(wallet_type, vec![make_update(2, 0), update]).unwrap();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0].code_hash, None);
+        assert_eq!(updates[0].custodians, None);
+        assert_eq!(updates[0].req_confirms, None);
+        assert!(updates[0].confirmations.is_empty());
+        assert_eq!(
+            updates[1],
+            PendingUpdate {
+                id: 0x6000_0000_0000_0001,
+                index: 1,
+                signs: 2,
+                confirmations: vec![UInt256::from([3; 32])],
+                creator: UInt256::from([2; 32]),
+                code_hash: None,
+                custodians: None,
+                req_confirms: Some(3),
+                lifetime: Some(3600),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_range_fields() {
+        let mut fields = make_update(0, 0)
+            .into_iter()
+            .map(|(name, value)| Token::new(name, value.unwrap()))
+            .collect::<Vec<_>>();
+        for field in &mut fields {
+            if field.name == "index" {
+                field.value = make_uint(256u32, 32);
+            }
+        }
+
+        assert!(matches!(
+            parse_pending_update(TokenValue::Tuple(fields), &custodians()),
+            Err(MultisigUpdateError::InvalidUpdateField("index"))
+        ));
+    }
+
+    #[test]
+    fn loads_transfer_abis() {
+        let abis = transfer_abis();
+        assert_eq!(abis.len(), 2);
+
+        for name in ["sendTransaction", "submitTransaction", "confirmTransaction"] {
+            assert!(abis.iter().all(|abi| abi.function(name).is_ok()));
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use anyhow::Result;
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+use nt::core::models as core_models;
+use nt::core::ton_wallet::{self, MultisigType, TransferAction, WalletType};
+
+use crate::utils::*;
+
+const EVER_WALLET_MAX_MESSAGES: usize = 4;
+const HIGHLOAD_WALLET_V2_MAX_MESSAGES: usize = 254;
+
+pub const FLAG_PAY_FEES_SEPARATELY: u8 = 1;
+pub const FLAG_IGNORE_ERRORS: u8 = 2;
+pub const FLAG_DESTROY_IF_ZERO: u8 = 32;
+pub const FLAG_ALL_BALANCE: u8 = 128;
+
+/// Send mode bits which can be used by wallets (messages are sent from external messages,
+/// so there is no inbound value to carry)
+const SUPPORTED_FLAGS: u8 =
+    FLAG_PAY_FEES_SEPARATELY | FLAG_IGNORE_ERRORS | FLAG_DESTROY_IF_ZERO | FLAG_ALL_BALANCE;
+
+/// Max number of outgoing messages in a single external message
+pub fn max_messages_per_transfer(wallet_type: WalletType) -> usize {
+    match wallet_type {
+        WalletType::EverWallet => EVER_WALLET_MAX_MESSAGES,
+        WalletType::HighloadWalletV2 => HIGHLOAD_WALLET_V2_MAX_MESSAGES,
+        _ => 1,
+    }
+}
+
+/// Whether a large batch can be sent as several independent external messages.
+///
+/// Only `HighloadWalletV2` is allowed, since it protects from replays by the message hash.
+/// Seqno based wallets can't have more than one pending message, while `EverWallet` and
+/// multisig wallets reject messages which are not newer than the last accepted one,
+/// so chunks signed at the same time would replay-protect each other
+pub fn can_split_transfer(wallet_type: WalletType) -> bool {
+    matches!(wallet_type, WalletType::HighloadWalletV2)
+}
+
+/// Whether the outgoing message is sent with the flags specified in the transfer.
+/// Multisig wallets with several custodians only submit transactions, which are
+/// later sent by the contract with its own flags, so multisig wallets with
+/// unknown custodians are treated the same way
+pub fn supports_custom_flags(wallet: &ton_wallet::TonWallet) -> bool {
+    match wallet.wallet_type() {
+        WalletType::Multisig(_) => {
+            matches!(wallet.get_custodians(), Some(custodians) if custodians.len() == 1)
+        }
+        _ => true,
+    }
+}
+
+/// Checks whether the wallet can send the message with the specified flags and state init
+pub fn validate_gift(
+    wallet: &ton_wallet::TonWallet,
+    gift: &ton_wallet::Gift,
+) -> Result<(), TransferError> {
+    let flags = gift.flags;
+    if flags & !SUPPORTED_FLAGS != 0 {
+        return Err(TransferError::UnsupportedFlags(flags));
+    }
+
+    if let WalletType::Multisig(multisig_type) = wallet.wallet_type() {
+        if gift.state_init.is_some() && !matches!(multisig_type, MultisigType::Multisig2) {
+            return Err(TransferError::StateInitNotSupported);
+        }
+    }
+
+    let normal: u8 = core_models::MessageFlags::Normal.into();
+    if !supports_custom_flags(wallet) && flags != normal {
+        return Err(TransferError::UnsupportedFlags(flags));
+    }
+
+    Ok(())
+}
+
+pub fn prepare_transfer_chunk(
+    clock: &dyn nt_utils::Clock,
+    wallet: &mut ton_wallet::TonWallet,
+    current_state: &ton_block::AccountStuff,
+    public_key: &ed25519_dalek::PublicKey,
+    mut gifts: Vec<ton_wallet::Gift>,
+    expiration: core_models::Expiration,
+) -> Result<TransferAction> {
+    match wallet.wallet_type() {
+        WalletType::EverWallet => ton_wallet::ever_wallet::prepare_transfer(
+            clock,
+            public_key,
+            current_state,
+            wallet.address().clone(),
+            gifts,
+            expiration,
+        ),
+        WalletType::HighloadWalletV2 => ton_wallet::highload_wallet_v2::prepare_transfer(
+            clock,
+            public_key,
+            current_state,
+            gifts,
+            expiration,
+        ),
+        _ => match (gifts.pop(), gifts.is_empty()) {
+            (Some(gift), true) => {
+                wallet.prepare_transfer(current_state, public_key, gift, expiration)
+            }
+            _ => Err(TransferError::TooManyMessages.into()),
+        },
+    }
+}
+
+/// Fees which are paid from the wallet balance when the whole balance is sent
+/// without the `ALL_BALANCE` flag. Forward fees are paid from the message value
+pub fn compute_sweep_fees(transaction: &ton_block::Transaction) -> Result<u64> {
+    let descr = match transaction.read_description()? {
+        ton_block::TransactionDescr::Ordinary(descr) => descr,
+        _ => return Err(TransferError::UnexpectedTransaction.into()),
+    };
+
+    if !matches!(&descr.compute_ph, ton_block::TrComputePhase::Vm(phase) if phase.success) {
+        return Err(TransferError::ComputePhaseFailed.into());
+    }
+
+    let action_fees = descr
+        .action
+        .as_ref()
+        .and_then(|action| action.total_action_fees.as_ref())
+        .map(|fees| fees.0)
+        .unwrap_or_default();
+
+    let fees = transaction.total_fees.grams.0.saturating_sub(action_fees);
+    Ok(u64::try_from(fees)?)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedGift {
+    pub destination: String,
+    pub amount: String,
+    pub bounce: bool,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub flags: Option<u8>,
+    #[serde(default)]
+    pub state_init: Option<String>,
+}
+
+impl ParsedGift {
+    pub fn try_into_gift(self) -> Result<ton_wallet::Gift, JsValue> {
+        Ok(ton_wallet::Gift {
+            flags: self
+                .flags
+                .unwrap_or_else(|| core_models::MessageFlags::Normal.into()),
+            bounce: self.bounce,
+            destination: parse_address(&self.destination)?,
+            amount: u64::from_str(&self.amount).handle_error()?,
+            body: match self.body {
+                Some(body) if !body.is_empty() => Some(parse_slice(&body)?),
+                _ => None,
+            },
+            state_init: self
+                .state_init
+                .as_deref()
+                .map(parse_state_init)
+                .transpose()?,
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum TransferError {
+    #[error("Empty transfer batch")]
+    EmptyBatch,
+    #[error("Too many messages for this wallet type")]
+    TooManyMessages,
+    #[error("Message flags are not supported by this wallet type: {0}")]
+    UnsupportedFlags(u8),
+    #[error("State init is not supported by this wallet type")]
+    StateInitNotSupported,
+    #[error("Unexpected transaction type")]
+    UnexpectedTransaction,
+    #[error("Compute phase failed")]
+    ComputePhaseFailed,
+    #[error("Insufficient balance")]
+    InsufficientBalance,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_hash_protected_wallets_split_transfers() {
+        let cases = [
+            ("SafeMultisigWallet", false),
+            ("SafeMultisigWallet24h", false),
+            ("SetcodeMultisigWallet", false),
+            ("BridgeMultisigWallet", false),
+            ("SurfWallet", false),
+            ("WalletV3", false),
+            ("EverWallet", false),
+            ("Multisig2", false),
+            ("HighloadWalletV2", true),
+        ];
+        assert_eq!(
+            cases.len(),
+            crate::core::ton_wallet::all_wallet_types().len()
+        );
+
+        for (name, expected) in cases.iter() {
+            let wallet_type = WalletType::from_str(name).unwrap();
+            assert_eq!(can_split_transfer(wallet_type), *expected, "{}", name);
+        }
+    }
+}
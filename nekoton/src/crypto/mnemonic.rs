@@ -0,0 +1,347 @@
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use unicode_normalization::UnicodeNormalization;
+
+const DEFAULT_LABS_WORD_COUNT: u8 = 12;
+const LEGACY_WORD_COUNT: u8 = 24;
+const ENTROPY_MIXING_PREFIX: &[u8] = b"nekoton-extra-entropy-v1";
+const ENTROPY_FINGERPRINT_PREFIX: &[u8] = b"nekoton-entropy-fingerprint-v1";
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bip39Language {
+    #[default]
+    English,
+    Japanese,
+    Korean,
+}
+
+impl Bip39Language {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::English => "english",
+            Self::Japanese => "japanese",
+            Self::Korean => "korean",
+        }
+    }
+
+    fn wordlist(&self) -> &'static [&'static str] {
+        bip39::Language::from(*self)
+            .wordlist()
+            .get_words_by_prefix("")
+    }
+}
+
+impl From<Bip39Language> for bip39::Language {
+    fn from(language: Bip39Language) -> Self {
+        match language {
+            Bip39Language::English => Self::English,
+            Bip39Language::Japanese => Self::Japanese,
+            Bip39Language::Korean => Self::Korean,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateMnemonicOptions {
+    /// User provided entropy (e.g. dice rolls or coin flips)
+    #[serde(default)]
+    pub extra_entropy: Option<String>,
+    #[serde(default)]
+    pub word_count: Option<u8>,
+}
+
+pub struct GeneratedPhrase {
+    /// Phrase in the requested language
+    pub phrase: String,
+    /// Fingerprint of the entropy mixed with the user provided one
+    pub entropy_fingerprint: Option<[u8; 4]>,
+}
+
+pub fn generate_phrase(
+    mnemonic_type: nt::crypto::MnemonicType,
+    language: Bip39Language,
+    options: &GenerateMnemonicOptions,
+) -> Result<GeneratedPhrase, MnemonicError> {
+    let extra_entropy = options
+        .extra_entropy
+        .as_deref()
+        .map(|entropy| {
+            normalize_phrase(entropy)
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>()
+        })
+        .filter(|entropy| !entropy.is_empty());
+
+    let generated = match mnemonic_type {
+        nt::crypto::MnemonicType::Legacy => {
+            if language != Bip39Language::English {
+                return Err(MnemonicError::UnsupportedLanguage);
+            }
+            if extra_entropy.is_some()
+                || matches!(options.word_count, Some(count) if count != LEGACY_WORD_COUNT)
+            {
+                return Err(MnemonicError::UnsupportedOptions);
+            }
+
+            GeneratedPhrase {
+                phrase: nt::crypto::generate_key(mnemonic_type).words.join(" "),
+                entropy_fingerprint: None,
+            }
+        }
+        nt::crypto::MnemonicType::Labs(_) => {
+            let word_count = options.word_count.unwrap_or(DEFAULT_LABS_WORD_COUNT);
+            if !matches!(word_count, 12 | 15 | 18 | 21 | 24) {
+                return Err(MnemonicError::InvalidWordCount);
+            }
+
+            let mut entropy = vec![0u8; word_count as usize * 4 / 3];
+            rand::thread_rng().fill_bytes(&mut entropy);
+
+            // OS entropy is always used, so weak user input can't make the phrase worse
+            let entropy_fingerprint = extra_entropy.map(|extra_entropy| {
+                let extra_entropy = sha2::Sha256::digest(extra_entropy.as_bytes());
+
+                let mixed = sha2::Sha256::new()
+                    .chain(ENTROPY_MIXING_PREFIX)
+                    .chain(&entropy)
+                    .chain(extra_entropy)
+                    .finalize();
+                let len = entropy.len();
+                entropy.copy_from_slice(&mixed[..len]);
+
+                // Computed from the final entropy, so it changes with any other input
+                let hash = sha2::Sha256::new()
+                    .chain(ENTROPY_FINGERPRINT_PREFIX)
+                    .chain(&entropy)
+                    .finalize();
+                let mut fingerprint = [0u8; 4];
+                fingerprint.copy_from_slice(&hash[..4]);
+                fingerprint
+            });
+
+            let mnemonic = bip39::Mnemonic::from_entropy(&entropy, language.into())
+                .map_err(|_| MnemonicError::InvalidWordCount)?;
+
+            GeneratedPhrase {
+                phrase: mnemonic.into_phrase(),
+                entropy_fingerprint,
+            }
+        }
+    };
+
+    check_round_trip(&generated.phrase, mnemonic_type, language)?;
+    Ok(generated)
+}
+
+/// Applies NFKD normalization, as BIP39 requires before the seed derivation
+pub fn normalize_phrase(phrase: &str) -> String {
+    phrase.nfkd().collect()
+}
+
+/// Phrase prepared for the import into the keystore
+pub enum ImportPhrase {
+    /// English phrase, which can be passed to the keystore signers as is
+    English(String),
+    /// Phrase from the wordlist of another language
+    Native {
+        phrase: String,
+        language: Bip39Language,
+    },
+}
+
+/// Normalizes the phrase from the wordlist of the specified language.
+///
+/// NOTE: Signers derive keys from english phrases only. Phrases in other
+/// languages have a different BIP39 seed than the english phrase with the
+/// same entropy, so they must not be passed to the signers as is.
+pub fn prepare_import_phrase(
+    phrase: &str,
+    language: Bip39Language,
+) -> Result<ImportPhrase, MnemonicError> {
+    let phrase = normalize_phrase(phrase);
+    match language {
+        Bip39Language::English => Ok(ImportPhrase::English(phrase)),
+        _ => {
+            phrase_to_indices(&phrase, language)?;
+            Ok(ImportPhrase::Native { phrase, language })
+        }
+    }
+}
+
+pub fn derive_from_phrase(
+    phrase: &str,
+    mnemonic_type: nt::crypto::MnemonicType,
+    language: Bip39Language,
+) -> Result<ed25519_dalek::Keypair, MnemonicError> {
+    let phrase = normalize_phrase(phrase);
+    match (language, mnemonic_type) {
+        (Bip39Language::English, _) => nt::crypto::derive_from_phrase(&phrase, mnemonic_type)
+            .map_err(MnemonicError::Derivation),
+        (_, nt::crypto::MnemonicType::Legacy) => Err(MnemonicError::UnsupportedLanguage),
+        (_, nt::crypto::MnemonicType::Labs(account_id)) => {
+            let mnemonic = bip39::Mnemonic::from_phrase(&phrase, language.into())
+                .map_err(|_| MnemonicError::InvalidPhrase)?;
+            let seed = bip39::Seed::new(&mnemonic, "");
+
+            let path = format!("m/44'/396'/0'/0/{}", account_id);
+            let derived =
+                tiny_hderive::bip32::ExtendedPrivKey::derive(seed.as_bytes(), path.as_str())
+                    .map_err(|_| MnemonicError::InvalidPhrase)?;
+
+            let secret = ed25519_dalek::SecretKey::from_bytes(&derived.secret())
+                .map_err(|_| MnemonicError::InvalidPhrase)?;
+            let public = ed25519_dalek::PublicKey::from(&secret);
+            Ok(ed25519_dalek::Keypair { secret, public })
+        }
+    }
+}
+
+/// Checks that the generated phrase can be imported back with the same keys
+fn check_round_trip(
+    phrase: &str,
+    mnemonic_type: nt::crypto::MnemonicType,
+    language: Bip39Language,
+) -> Result<(), MnemonicError> {
+    let expected = derive_from_phrase(phrase, mnemonic_type, language)?;
+
+    let actual = match prepare_import_phrase(phrase, language)? {
+        ImportPhrase::English(phrase) => nt::crypto::derive_from_phrase(&phrase, mnemonic_type)
+            .map_err(MnemonicError::Derivation)?,
+        ImportPhrase::Native { phrase, language } => {
+            derive_from_phrase(&phrase, mnemonic_type, language)?
+        }
+    };
+
+    if expected.public != actual.public {
+        return Err(MnemonicError::RoundTripMismatch);
+    }
+    Ok(())
+}
+
+pub fn get_hints(word: &str, language: Bip39Language) -> Vec<&'static str> {
+    let prefix = word.nfkd().collect::<String>();
+    match language {
+        Bip39Language::English => nt::crypto::dict::get_hints(&prefix),
+        _ => language
+            .wordlist()
+            .iter()
+            .filter(|word| word.nfkd().collect::<String>().starts_with(&prefix))
+            .copied()
+            .collect(),
+    }
+}
+
+pub fn validate_phrase(
+    phrase: &str,
+    mnemonic_type: nt::crypto::MnemonicType,
+    language: Bip39Language,
+) -> Result<(), MnemonicError> {
+    derive_from_phrase(phrase, mnemonic_type, language).map(|_| ())
+}
+
+fn phrase_to_indices(phrase: &str, language: Bip39Language) -> Result<Vec<usize>, MnemonicError> {
+    let wordlist = language
+        .wordlist()
+        .iter()
+        .map(|word| word.nfkd().collect::<String>())
+        .collect::<Vec<_>>();
+
+    phrase
+        .split_whitespace()
+        .map(|word| {
+            wordlist
+                .iter()
+                .position(|item| item == word)
+                .ok_or_else(|| MnemonicError::UnknownWord(word.to_owned()))
+        })
+        .collect()
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum MnemonicError {
+    #[error("Invalid phrase")]
+    InvalidPhrase,
+    #[error("Unknown word: {0}")]
+    UnknownWord(String),
+    #[error("Language is not supported for this mnemonic type")]
+    UnsupportedLanguage,
+    #[error("Generated phrase doesn't derive the same keys after import")]
+    RoundTripMismatch,
+    #[error("Invalid word count")]
+    InvalidWordCount,
+    #[error("Options are not supported for this mnemonic type")]
+    UnsupportedOptions,
+    #[error("{0}")]
+    Derivation(anyhow::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_phrase_for_each_language() {
+        let options = GenerateMnemonicOptions::default();
+        let cases = [
+            (nt::crypto::MnemonicType::Legacy, Bip39Language::English),
+            (nt::crypto::MnemonicType::Labs(0), Bip39Language::English),
+            (nt::crypto::MnemonicType::Labs(0), Bip39Language::Japanese),
+            (nt::crypto::MnemonicType::Labs(1), Bip39Language::Korean),
+        ];
+
+        for (mnemonic_type, language) in cases.iter().copied() {
+            let generated = generate_phrase(mnemonic_type, language, &options).unwrap();
+            validate_phrase(&generated.phrase, mnemonic_type, language).unwrap();
+
+            match (prepare_import_phrase(&generated.phrase, language), language) {
+                (Ok(ImportPhrase::English(_)), Bip39Language::English) => {}
+                (Ok(ImportPhrase::Native { .. }), Bip39Language::Japanese)
+                | (Ok(ImportPhrase::Native { .. }), Bip39Language::Korean) => {}
+                _ => panic!("{} phrase is not imported", language.as_str()),
+            }
+        }
+    }
+
+    #[test]
+    fn native_phrases_are_not_derived_as_english() {
+        let options = GenerateMnemonicOptions::default();
+        let mnemonic_type = nt::crypto::MnemonicType::Labs(0);
+
+        let generated = generate_phrase(mnemonic_type, Bip39Language::Japanese, &options).unwrap();
+        assert!(validate_phrase(&generated.phrase, mnemonic_type, Bip39Language::English).is_err());
+        assert!(matches!(
+            prepare_import_phrase(&generated.phrase, Bip39Language::Korean),
+            Err(MnemonicError::UnknownWord(_))
+        ));
+        assert!(matches!(
+            generate_phrase(
+                nt::crypto::MnemonicType::Legacy,
+                Bip39Language::Korean,
+                &options
+            ),
+            Err(MnemonicError::UnsupportedLanguage)
+        ));
+    }
+
+    #[test]
+    fn entropy_fingerprint_is_computed_from_final_entropy() {
+        let mnemonic_type = nt::crypto::MnemonicType::Labs(0);
+        let options = GenerateMnemonicOptions {
+            extra_entropy: Some("1 6 3 4 2 5 6 1".to_owned()),
+            word_count: Some(24),
+        };
+
+        let first = generate_phrase(mnemonic_type, Bip39Language::English, &options).unwrap();
+        let second = generate_phrase(mnemonic_type, Bip39Language::English, &options).unwrap();
+        assert!(first.entropy_fingerprint.is_some());
+        assert_ne!(first.entropy_fingerprint, second.entropy_fingerprint);
+
+        let default = GenerateMnemonicOptions::default();
+        let generated = generate_phrase(mnemonic_type, Bip39Language::English, &default).unwrap();
+        assert!(generated.entropy_fingerprint.is_none());
+    }
+}
@@ -1,4 +1,5 @@
 use ed25519_dalek::Verifier;
+use std::borrow::Cow;
 use std::convert::TryFrom;
 
 use gloo_utils::format::JsValueSerdeExt;
@@ -11,11 +12,29 @@ use nt::crypto;
 
 use crate::utils::*;
 
+use self::mnemonic::*;
+
+pub mod mnemonic;
+
+/// Prepends network signature id to the data if it is required
+pub fn extend_with_signature_id(data: &[u8], signature_id: Option<i32>) -> Cow<'_, [u8]> {
+    match signature_id {
+        Some(signature_id) => {
+            let mut result = Vec::with_capacity(4 + data.len());
+            result.extend_from_slice(&signature_id.to_be_bytes());
+            result.extend_from_slice(data);
+            Cow::Owned(result)
+        }
+        None => Cow::Borrowed(data),
+    }
+}
+
 #[wasm_bindgen(js_name = "verifySignature")]
 pub fn verify_signature(
     public_key: &str,
     data_hash: &str,
     signature: &str,
+    signature_id: Option<i32>,
 ) -> Result<bool, JsValue> {
     let public_key = parse_public_key(public_key)?;
 
@@ -30,6 +49,17 @@ pub fn verify_signature(
         return Err("Invalid data hash. Expected 32 bytes").handle_error();
     }
 
+    let signature = parse_signature(signature)?;
+    let signature = match ed25519_dalek::Signature::try_from(signature.as_ref()) {
+        Ok(signature) => signature,
+        Err(_) => return Err("Invalid signature. Expected 64 bytes").handle_error(),
+    };
+
+    let data = extend_with_signature_id(&data_hash, signature_id);
+    Ok(public_key.verify(&data, &signature).is_ok())
+}
+
+pub fn parse_signature(signature: &str) -> Result<[u8; ed25519_dalek::SIGNATURE_LENGTH], JsValue> {
     let signature = match base64::decode(signature) {
         Ok(signature) => signature,
         Err(e) => match hex::decode(signature) {
@@ -37,18 +67,134 @@ pub fn verify_signature(
             Err(_) => return Err(e).handle_error(),
         },
     };
-    let signature = match ed25519_dalek::Signature::try_from(signature.as_slice()) {
-        Ok(signature) => signature,
-        Err(_) => return Err("Invalid signature. Expected 64 bytes").handle_error(),
-    };
-
-    Ok(public_key.verify(&data_hash, &signature).is_ok())
+    match <[u8; ed25519_dalek::SIGNATURE_LENGTH]>::try_from(signature.as_slice()) {
+        Ok(signature) => Ok(signature),
+        Err(_) => Err("Invalid signature. Expected 64 bytes").handle_error(),
+    }
 }
 
 #[wasm_bindgen]
 pub struct UnsignedMessage {
     #[wasm_bindgen(skip)]
     pub inner: Box<dyn crypto::UnsignedMessage>,
+    #[wasm_bindgen(skip)]
+    pub kind: UnsignedMessageKind,
+}
+
+/// What the message does, if it can't be fully decoded from its body
+#[derive(Clone)]
+pub enum UnsignedMessageKind {
+    Generic,
+    Deploy,
+    MultisigTransfer {
+        destination: String,
+        amount: u64,
+        bounce: bool,
+        body: Option<ton_types::Cell>,
+        flags: u8,
+        state_init_hash: Option<ton_types::UInt256>,
+        submit: bool,
+    },
+    MultisigConfirm {
+        transaction_id: u64,
+    },
+    /// Internal messages sent by the wallet itself, in the same order
+    Transfers {
+        transfers: Vec<TransferDescription>,
+    },
+    Call {
+        method: String,
+    },
+}
+
+impl UnsignedMessage {
+    pub fn ledger_signing_context(&self) -> Option<crate::external::ParsedLedgerSigningContext> {
+        use crate::external::ParsedLedgerSigningContext as Context;
+
+        let make_transfer =
+            |destination: &str, amount: String, bounce: bool, body: Option<ton_types::Cell>| {
+                match body.and_then(parse_token_transfer) {
+                    Some((recipient, tokens)) => Context::TokenTransfer {
+                        token_wallet: destination.to_owned(),
+                        recipient,
+                        tokens,
+                        attached_amount: amount,
+                    },
+                    None => Context::Transfer {
+                        address: destination.to_owned(),
+                        amount,
+                        bounce,
+                    },
+                }
+            };
+
+        let description = describe_unsigned_message(self.inner.as_ref(), None).ok()?;
+        let address = description.address.to_string();
+
+        Some(match &self.kind {
+            UnsignedMessageKind::Deploy => Context::Deploy { address },
+            UnsignedMessageKind::Call { method } => Context::Call {
+                address,
+                method: method.clone(),
+            },
+            UnsignedMessageKind::MultisigConfirm { transaction_id } => Context::MultisigConfirm {
+                wallet: address,
+                transaction_id: *transaction_id,
+            },
+            UnsignedMessageKind::MultisigTransfer {
+                destination,
+                amount,
+                bounce,
+                body,
+                submit,
+                ..
+            } => match make_transfer(destination, amount.to_string(), *bounce, body.clone()) {
+                Context::Transfer {
+                    address: destination,
+                    amount,
+                    bounce,
+                } if *submit => Context::MultisigSubmit {
+                    wallet: address,
+                    address: destination,
+                    amount,
+                    bounce,
+                },
+                context => context,
+            },
+            UnsignedMessageKind::Transfers { transfers } => match transfers.as_slice() {
+                [transfer] => make_transfer(
+                    &transfer.destination,
+                    transfer.value.to_string(),
+                    transfer.bounce,
+                    transfer.payload.clone(),
+                ),
+                _ => return None,
+            },
+            UnsignedMessageKind::Generic => match description.transfers.as_slice() {
+                [transfer] => make_transfer(
+                    &transfer.destination,
+                    transfer.value.to_string(),
+                    transfer.bounce,
+                    transfer.payload.clone(),
+                ),
+                [] if description.state_init_hash.is_some() => Context::Deploy { address },
+                _ => return None,
+            },
+        })
+    }
+}
+
+fn parse_token_transfer(payload: ton_types::Cell) -> Option<(String, String)> {
+    match nt::core::parsing::parse_payload(payload.into())? {
+        nt::core::models::KnownPayload::TokenOutgoingTransfer(transfer) => {
+            let recipient = match transfer.to {
+                nt::core::models::TransferRecipient::OwnerWallet(address)
+                | nt::core::models::TransferRecipient::TokenWallet(address) => address,
+            };
+            Some((recipient.to_string(), transfer.tokens.to_string()))
+        }
+        _ => None,
+    }
 }
 
 #[wasm_bindgen]
@@ -75,6 +221,699 @@ impl UnsignedMessage {
             .handle_error()
             .and_then(make_signed_message)
     }
+
+    #[wasm_bindgen(js_name = "signWithSignature")]
+    pub fn sign_with_signature(&self, signature: &str) -> Result<JsSignedMessage, JsValue> {
+        let signature = parse_signature(signature)?;
+        self.inner
+            .sign(&signature)
+            .handle_error()
+            .and_then(make_signed_message)
+    }
+
+    #[wasm_bindgen]
+    pub fn serialize(&self) -> Result<SerializedUnsignedMessage, JsValue> {
+        let signature_offset = find_signature_offset(self.inner.as_ref()).handle_error()?;
+        let message = self.inner.sign(&[0; 64]).handle_error()?.message;
+        if !verify_portable_hash(&message, signature_offset, self.inner.hash()).handle_error()? {
+            return Err(CryptoError::UnsupportedMessageLayout).handle_error();
+        }
+
+        let summary = make_unsigned_message_summary(self.inner.as_ref())?;
+        let boc = message
+            .serialize()
+            .and_then(|cell| ton_types::serialize_toc(&cell))
+            .map(base64::encode)
+            .handle_error()?;
+
+        Ok(ObjectBuilder::new()
+            .set("version", SERIALIZED_UNSIGNED_MESSAGE_VERSION)
+            .set("boc", boc)
+            .set("hash", self.hash())
+            .set("expireAt", self.inner.expire_at())
+            .set("signatureOffset", signature_offset as u32)
+            .set("summary", summary)
+            .build()
+            .unchecked_into())
+    }
+
+    #[wasm_bindgen]
+    pub fn deserialize(data: SerializedUnsignedMessage) -> Result<UnsignedMessage, JsValue> {
+        let data = JsValue::into_serde::<ParsedSerializedUnsignedMessage>(&data).handle_error()?;
+        if data.version != SERIALIZED_UNSIGNED_MESSAGE_VERSION {
+            return Err(CryptoError::UnsupportedSerializationVersion).handle_error();
+        }
+
+        let hash = parse_hash(&data.hash)?;
+        match data.boc.body() {
+            Some(body)
+                if body.remaining_bits()
+                    >= data.signature_offset + ed25519_dalek::SIGNATURE_LENGTH * 8 => {}
+            _ => return Err(CryptoError::InvalidMessageLayout).handle_error(),
+        }
+        if !verify_portable_hash(&data.boc, data.signature_offset, hash.as_slice())
+            .handle_error()?
+        {
+            return Err(CryptoError::MessageHashMismatch).handle_error();
+        }
+
+        let message = PortableUnsignedMessage {
+            message: data.boc,
+            signature_offset: data.signature_offset,
+            hash: hash.as_slice().to_vec(),
+            expire_at: data.expire_at,
+        };
+
+        // NOTE: summary is always rebuilt from the message itself
+        let summary = ParsedUnsignedMessageSummary::from_message(&message)?;
+        if summary != data.summary {
+            return Err(CryptoError::SummaryMismatch).handle_error();
+        }
+
+        Ok(UnsignedMessage {
+            inner: Box::new(message),
+            kind: UnsignedMessageKind::Generic,
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn summary(&self) -> Result<UnsignedMessageSummary, JsValue> {
+        make_unsigned_message_summary(self.inner.as_ref())
+    }
+
+    #[wasm_bindgen]
+    pub fn describe(
+        &self,
+        contract_abi: Option<String>,
+    ) -> Result<UnsignedMessageDescription, JsValue> {
+        let contract_abi = contract_abi
+            .as_deref()
+            .map(crate::helpers::abi::parse_contract_abi)
+            .transpose()?;
+
+        let mut description =
+            describe_unsigned_message(self.inner.as_ref(), contract_abi.as_ref())?;
+        // Fall back to what the message was built from when the body can't be decoded
+        if description.transfers.is_empty() {
+            match &self.kind {
+                UnsignedMessageKind::Transfers { transfers } => {
+                    description.transfers = transfers.clone();
+                }
+                UnsignedMessageKind::MultisigTransfer {
+                    destination,
+                    amount,
+                    bounce,
+                    body,
+                    flags,
+                    state_init_hash,
+                    ..
+                } => {
+                    description.transfers = vec![TransferDescription {
+                        destination: destination.clone(),
+                        value: *amount as u128,
+                        bounce: *bounce,
+                        flags: Some(*flags),
+                        state_init_hash: *state_init_hash,
+                        payload: body.clone(),
+                    }];
+                }
+                _ => {}
+            }
+        }
+        make_message_description(description)
+    }
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const UNSIGNED_MESSAGE_DESCRIPTION: &str = r#"
+export type UnsignedMessageDescription = {
+    address: string,
+    expireAt: number,
+    stateInitHash?: string,
+    method?: string,
+    params?: TokensObject,
+    transfers: TransferDescription[],
+};
+
+export type TransferDescription = {
+    destination: string,
+    value: string,
+    bounce: boolean,
+    flags?: number,
+    stateInitHash?: string,
+    warnings?: TransferWarning[],
+    knownPayload?: KnownPayload,
+};
+
+export type TransferWarning = 'all_balance' | 'destroy_if_zero' | 'ignore_errors' | 'deploy';
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "UnsignedMessageDescription")]
+    pub type UnsignedMessageDescription;
+}
+
+pub struct MessageDescription {
+    pub address: ton_block::MsgAddressInt,
+    pub expire_at: u32,
+    pub state_init_hash: Option<ton_types::UInt256>,
+    pub method: Option<String>,
+    pub params: Vec<ton_abi::Token>,
+    pub transfers: Vec<TransferDescription>,
+}
+
+impl MessageDescription {
+    fn set_call(&mut self, method: &ton_abi::Function, params: Vec<ton_abi::Token>) {
+        self.transfers = parse_transfer_params(&params).into_iter().collect();
+        self.method = Some(method.name.clone());
+        self.params = params;
+    }
+}
+
+#[derive(Clone)]
+pub struct TransferDescription {
+    pub destination: String,
+    pub value: u128,
+    pub bounce: bool,
+    pub flags: Option<u8>,
+    pub state_init_hash: Option<ton_types::UInt256>,
+    pub payload: Option<ton_types::Cell>,
+}
+
+impl TransferDescription {
+    /// Send modes and attachments which may lead to unexpected loss of funds
+    pub fn warnings(&self) -> Vec<&'static str> {
+        use crate::core::ton_wallet::transfer::{
+            FLAG_ALL_BALANCE, FLAG_DESTROY_IF_ZERO, FLAG_IGNORE_ERRORS,
+        };
+
+        let flags = self.flags.unwrap_or_default();
+        let mut warnings = Vec::new();
+        if flags & FLAG_ALL_BALANCE != 0 {
+            warnings.push("all_balance");
+        }
+        if flags & FLAG_DESTROY_IF_ZERO != 0 {
+            warnings.push("destroy_if_zero");
+        }
+        if flags & FLAG_IGNORE_ERRORS != 0 {
+            warnings.push("ignore_errors");
+        }
+        if self.state_init_hash.is_some() {
+            warnings.push("deploy");
+        }
+        warnings
+    }
+}
+
+/// Decodes the contents of the message which is going to be signed.
+///
+/// Method and transfers are decoded with the provided ABI. Without it only plain
+/// internal messages attached to the body (like in `WalletV3`) and multisig
+/// transfers are recognized.
+pub fn describe_unsigned_message(
+    message: &dyn crypto::UnsignedMessage,
+    contract_abi: Option<&ton_abi::Contract>,
+) -> Result<MessageDescription, JsValue> {
+    let expire_at = message.expire_at();
+    let message = message.sign(&[0; 64]).handle_error()?.message;
+
+    let address = match message.header() {
+        ton_block::CommonMsgInfo::ExtInMsgInfo(header) => header.dst.clone(),
+        _ => return Err(CryptoError::InvalidMessageLayout).handle_error(),
+    };
+    let state_init_hash = message
+        .state_init()
+        .map(|state_init| state_init.serialize().map(|cell| cell.repr_hash()))
+        .transpose()
+        .handle_error()?;
+
+    let mut description = MessageDescription {
+        address,
+        expire_at,
+        state_init_hash,
+        method: None,
+        params: Vec::new(),
+        transfers: Vec::new(),
+    };
+
+    let body = match message.body() {
+        Some(body) => body,
+        None => return Ok(description),
+    };
+
+    match contract_abi {
+        Some(contract_abi) => {
+            let method = JsValue::undefined().unchecked_into();
+            if let Some((method, params)) =
+                crate::helpers::abi::decode_input_tokens(contract_abi, body, method, false)?
+            {
+                description.set_call(method, params);
+            }
+        }
+        None => {
+            description.transfers = parse_attached_messages(body.clone());
+            if description.transfers.is_empty() {
+                // Both multisig ABIs share the function ids, so the layout is checked by decoding
+                for contract_abi in crate::core::ton_wallet::multisig::transfer_abis() {
+                    let method = JsValue::undefined().unchecked_into();
+                    if let Ok(Some((method, params))) = crate::helpers::abi::decode_input_tokens(
+                        &contract_abi,
+                        body.clone(),
+                        method,
+                        false,
+                    ) {
+                        description.set_call(method, params);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(description)
+}
+
+fn parse_transfer_params(params: &[ton_abi::Token]) -> Option<TransferDescription> {
+    use crate::core::ton_wallet::transfer::FLAG_ALL_BALANCE;
+    use num_traits::ToPrimitive;
+
+    let mut destination = None;
+    let mut value = None;
+    let mut bounce = false;
+    let mut flags = None;
+    let mut state_init_hash = None;
+    let mut payload = None;
+
+    for param in params {
+        match (param.name.as_str(), &param.value) {
+            ("dest", ton_abi::TokenValue::Address(address)) => {
+                destination = Some(address.to_string())
+            }
+            ("value", ton_abi::TokenValue::Uint(number)) => value = number.number.to_u128(),
+            ("value", ton_abi::TokenValue::Token(grams)) => value = Some(grams.0),
+            ("bounce", ton_abi::TokenValue::Bool(value)) => bounce = *value,
+            ("flags", ton_abi::TokenValue::Uint(number)) => flags = number.number.to_u8(),
+            // `submitTransaction` only has a switch instead of flags
+            ("allBalance", ton_abi::TokenValue::Bool(true)) => {
+                flags = Some(flags.unwrap_or_default() | FLAG_ALL_BALANCE)
+            }
+            ("payload", ton_abi::TokenValue::Cell(cell)) => payload = Some(cell.clone()),
+            ("stateInit", ton_abi::TokenValue::Optional(_, Some(value))) => {
+                if let ton_abi::TokenValue::Cell(cell) = value.as_ref() {
+                    state_init_hash = Some(cell.repr_hash());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Some(TransferDescription {
+        destination: destination?,
+        value: value?,
+        bounce,
+        flags,
+        state_init_hash,
+        payload,
+    })
+}
+
+fn parse_attached_messages(mut body: ton_types::SliceData) -> Vec<TransferDescription> {
+    use ton_block::Deserializable;
+
+    // Each attached message is prefixed with its send mode at the end of the body data
+    let count = body.remaining_references();
+    let flags = match body.remaining_bits().checked_sub(count * 8) {
+        Some(offset) if body.move_by(offset).is_ok() => (0..count)
+            .map(|_| body.get_next_byte().ok())
+            .collect::<Vec<_>>(),
+        _ => vec![None; count],
+    };
+
+    let mut result = Vec::with_capacity(count);
+    for (i, flags) in flags.into_iter().enumerate() {
+        let message = match body
+            .reference(i)
+            .and_then(ton_block::Message::construct_from_cell)
+        {
+            Ok(message) => message,
+            Err(_) => return Vec::new(),
+        };
+        let header = match message.int_header() {
+            Some(header) => header,
+            None => return Vec::new(),
+        };
+
+        result.push(TransferDescription {
+            destination: header.dst.to_string(),
+            value: header.value.grams.0,
+            bounce: header.bounce,
+            flags,
+            state_init_hash: message
+                .state_init()
+                .and_then(|state_init| state_init.serialize().ok())
+                .map(|cell| cell.repr_hash()),
+            payload: message.body().map(|body| body.into_cell()),
+        });
+    }
+    result
+}
+
+fn make_message_description(
+    data: MessageDescription,
+) -> Result<UnsignedMessageDescription, JsValue> {
+    let transfers = data
+        .transfers
+        .into_iter()
+        .map(|transfer| {
+            let warnings = transfer
+                .warnings()
+                .into_iter()
+                .map(JsValue::from)
+                .collect::<js_sys::Array>();
+
+            ObjectBuilder::new()
+                .set("destination", transfer.destination)
+                .set("value", transfer.value.to_string())
+                .set("bounce", transfer.bounce)
+                .set("flags", transfer.flags)
+                .set(
+                    "stateInitHash",
+                    transfer
+                        .state_init_hash
+                        .as_ref()
+                        .map(ton_types::UInt256::to_hex_string),
+                )
+                .set("warnings", warnings)
+                .set(
+                    "knownPayload",
+                    transfer.payload.and_then(|payload| {
+                        crate::core::models::parse_known_payload(payload.into())
+                    }),
+                )
+                .build()
+        })
+        .collect::<js_sys::Array>();
+
+    let params = match data.method {
+        Some(_) => Some(crate::helpers::abi::make_tokens_object(&data.params)?),
+        None => None,
+    };
+
+    Ok(ObjectBuilder::new()
+        .set("address", data.address.to_string())
+        .set("expireAt", data.expire_at)
+        .set(
+            "stateInitHash",
+            data.state_init_hash.map(|hash| hash.to_hex_string()),
+        )
+        .set("method", data.method)
+        .set("params", params)
+        .set("transfers", transfers)
+        .build()
+        .unchecked_into())
+}
+
+const SERIALIZED_UNSIGNED_MESSAGE_VERSION: u8 = 1;
+
+#[wasm_bindgen(typescript_custom_section)]
+const SERIALIZED_UNSIGNED_MESSAGE: &str = r#"
+export type SerializedUnsignedMessage = {
+    version: 1,
+    boc: string,
+    hash: string,
+    expireAt: number,
+    signatureOffset: number,
+    summary: UnsignedMessageSummary,
+};
+
+export type UnsignedMessageSummary = {
+    destination: string,
+    expireAt: number,
+    hasStateInit: boolean,
+    bodyHash: string,
+    method?: string,
+    amount: string,
+    transfers: TransferSummary[],
+};
+
+export type TransferSummary = {
+    destination: string,
+    value: string,
+    bounce: boolean,
+    flags?: number,
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SerializedUnsignedMessage")]
+    pub type SerializedUnsignedMessage;
+
+    #[wasm_bindgen(typescript_type = "UnsignedMessageSummary")]
+    pub type UnsignedMessageSummary;
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ParsedSerializedUnsignedMessage {
+    version: u8,
+    #[serde(deserialize_with = "deserialize_message")]
+    boc: ton_block::Message,
+    hash: String,
+    expire_at: u32,
+    signature_offset: usize,
+    summary: ParsedUnsignedMessageSummary,
+}
+
+#[derive(Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct ParsedUnsignedMessageSummary {
+    destination: String,
+    expire_at: u32,
+    has_state_init: bool,
+    body_hash: String,
+    #[serde(default)]
+    method: Option<String>,
+    amount: String,
+    transfers: Vec<ParsedTransferSummary>,
+}
+
+#[derive(Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct ParsedTransferSummary {
+    destination: String,
+    value: String,
+    bounce: bool,
+    #[serde(default)]
+    flags: Option<u8>,
+}
+
+impl ParsedUnsignedMessageSummary {
+    /// Transfers and method are decoded the same way as in `describe` without the ABI.
+    /// Values which are only known from the original inputs are not included,
+    /// since they can't be checked after deserialization
+    fn from_message(message: &dyn crypto::UnsignedMessage) -> Result<Self, JsValue> {
+        let description = describe_unsigned_message(message, None)?;
+        let message = message.sign(&[0; 64]).handle_error()?.message;
+        let body_hash = match message.body() {
+            Some(body) => body.into_cell().repr_hash().to_hex_string(),
+            None => return Err(CryptoError::InvalidMessageLayout).handle_error(),
+        };
+
+        let amount = description.transfers.iter().fold(0u128, |total, transfer| {
+            total.saturating_add(transfer.value)
+        });
+
+        Ok(Self {
+            destination: description.address.to_string(),
+            expire_at: description.expire_at,
+            has_state_init: description.state_init_hash.is_some(),
+            body_hash,
+            method: description.method,
+            amount: amount.to_string(),
+            transfers: description
+                .transfers
+                .into_iter()
+                .map(|transfer| ParsedTransferSummary {
+                    destination: transfer.destination,
+                    value: transfer.value.to_string(),
+                    bounce: transfer.bounce,
+                    flags: transfer.flags,
+                })
+                .collect(),
+        })
+    }
+}
+
+fn make_unsigned_message_summary(
+    message: &dyn crypto::UnsignedMessage,
+) -> Result<UnsignedMessageSummary, JsValue> {
+    let summary = ParsedUnsignedMessageSummary::from_message(message)?;
+
+    let transfers = summary
+        .transfers
+        .into_iter()
+        .map(|transfer| {
+            ObjectBuilder::new()
+                .set("destination", transfer.destination)
+                .set("value", transfer.value)
+                .set("bounce", transfer.bounce)
+                .set("flags", transfer.flags)
+                .build()
+        })
+        .collect::<js_sys::Array>();
+
+    Ok(ObjectBuilder::new()
+        .set("destination", summary.destination)
+        .set("expireAt", summary.expire_at)
+        .set("hasStateInit", summary.has_state_init)
+        .set("bodyHash", summary.body_hash)
+        .set("method", summary.method)
+        .set("amount", summary.amount)
+        .set("transfers", transfers)
+        .build()
+        .unchecked_into())
+}
+
+/// Unsigned message restored from its portable representation.
+///
+/// Stores the message with an empty signature and the bit offset of the signature
+/// in the body root cell, so it can be assembled without the original inputs.
+#[derive(Clone)]
+struct PortableUnsignedMessage {
+    message: ton_block::Message,
+    signature_offset: usize,
+    hash: Vec<u8>,
+    expire_at: u32,
+}
+
+impl crypto::UnsignedMessage for PortableUnsignedMessage {
+    fn refresh_timeout(&mut self, _: &dyn nt::utils::Clock) {
+        // Expiration time is a part of the signed hash and can't be changed here
+    }
+
+    fn expire_at(&self) -> u32 {
+        self.expire_at
+    }
+
+    fn hash(&self) -> &[u8] {
+        &self.hash
+    }
+
+    fn sign(
+        &self,
+        signature: &[u8; ed25519_dalek::SIGNATURE_LENGTH],
+    ) -> anyhow::Result<crypto::SignedMessage> {
+        let mut message = self.message.clone();
+        let body = message.body().ok_or(CryptoError::InvalidMessageLayout)?;
+        message.set_body(replace_signature(body, self.signature_offset, signature)?);
+
+        Ok(crypto::SignedMessage {
+            message,
+            expire_at: self.expire_at,
+        })
+    }
+}
+
+/// Finds the position of the signature in the body root cell by signing
+/// the message with two different fake signatures
+fn find_signature_offset(message: &dyn crypto::UnsignedMessage) -> anyhow::Result<usize> {
+    const SIGNATURE_BITS: usize = ed25519_dalek::SIGNATURE_LENGTH * 8;
+
+    let mut first = message
+        .sign(&[0x00; ed25519_dalek::SIGNATURE_LENGTH])?
+        .message
+        .body()
+        .ok_or(CryptoError::InvalidMessageLayout)?;
+    let mut second = message
+        .sign(&[0xff; ed25519_dalek::SIGNATURE_LENGTH])?
+        .message
+        .body()
+        .ok_or(CryptoError::InvalidMessageLayout)?;
+
+    if first.remaining_bits() != second.remaining_bits()
+        || first.remaining_references() != second.remaining_references()
+    {
+        return Err(CryptoError::InvalidMessageLayout.into());
+    }
+    for i in 0..first.remaining_references() {
+        if first.reference(i)?.repr_hash() != second.reference(i)?.repr_hash() {
+            return Err(CryptoError::InvalidMessageLayout.into());
+        }
+    }
+
+    let mut offset = None;
+    let mut position = 0;
+    while first.remaining_bits() > 0 {
+        let differs = first.get_next_bit()? != second.get_next_bit()?;
+        match offset {
+            None if differs => offset = Some(position),
+            Some(start) if differs != (position < start + SIGNATURE_BITS) => {
+                return Err(CryptoError::InvalidMessageLayout.into())
+            }
+            None | Some(_) => {}
+        }
+        position += 1;
+    }
+
+    match offset {
+        Some(offset) if offset + SIGNATURE_BITS <= position => Ok(offset),
+        _ => Err(CryptoError::InvalidMessageLayout.into()),
+    }
+}
+
+/// Checks that the hash matches the message body without the signature.
+///
+/// Supports the layouts of the builtin wallets (signature at the start of the body)
+/// and ABI 2.x external messages (optional signature bit, with the destination
+/// address prepended to the signed data since ABI 2.3)
+fn verify_portable_hash(
+    message: &ton_block::Message,
+    signature_offset: usize,
+    hash: &[u8],
+) -> anyhow::Result<bool> {
+    let destination = match message.header() {
+        ton_block::CommonMsgInfo::ExtInMsgInfo(header) => &header.dst,
+        _ => return Err(CryptoError::InvalidMessageLayout.into()),
+    };
+    let mut body = message.body().ok_or(CryptoError::InvalidMessageLayout)?;
+    body.move_by(signature_offset + ed25519_dalek::SIGNATURE_LENGTH * 8)?;
+
+    let mut unsigned = ton_types::BuilderData::new();
+    unsigned.append_bytestring(&body)?;
+    while body.remaining_references() > 0 {
+        unsigned.checked_append_reference(body.checked_drain_reference()?)?;
+    }
+
+    if unsigned.clone().into_cell()?.repr_hash().as_slice() == hash {
+        return Ok(true);
+    }
+
+    let mut with_address = destination.write_to_new_cell()?;
+    if with_address.append_builder(&unsigned).is_err() {
+        return Ok(false);
+    }
+    Ok(with_address.into_cell()?.repr_hash().as_slice() == hash)
+}
+
+fn replace_signature(
+    mut body: ton_types::SliceData,
+    offset: usize,
+    signature: &[u8; ed25519_dalek::SIGNATURE_LENGTH],
+) -> anyhow::Result<ton_types::SliceData> {
+    let prefix = body.get_next_slice(offset)?;
+    body.move_by(ed25519_dalek::SIGNATURE_LENGTH * 8)?;
+
+    let mut builder = ton_types::BuilderData::new();
+    builder.append_bytestring(&prefix)?;
+    builder.append_raw(signature, ed25519_dalek::SIGNATURE_LENGTH * 8)?;
+    builder.append_bytestring(&body)?;
+    while body.remaining_references() > 0 {
+        builder.checked_append_reference(body.checked_drain_reference()?)?;
+    }
+
+    Ok(builder.into_cell()?.into())
 }
 
 #[wasm_bindgen(typescript_custom_section)]
@@ -96,6 +935,56 @@ extern "C" {
     pub type JsSignedData;
 }
 
+#[wasm_bindgen(js_name = "verifyTypedData")]
+pub fn verify_typed_data(
+    public_key: &str,
+    params: crate::helpers::abi::ParamsList,
+    tokens: crate::helpers::abi::TokensObject,
+    abi_version: Option<String>,
+    signature: &str,
+    signature_id: Option<i32>,
+) -> Result<bool, JsValue> {
+    let public_key = parse_public_key(public_key)?;
+    let cell = crate::helpers::abi::pack_tokens_into_cell(params, tokens, abi_version)?;
+
+    let signature = parse_signature(signature)?;
+    let signature = match ed25519_dalek::Signature::try_from(signature.as_ref()) {
+        Ok(signature) => signature,
+        Err(_) => return Err("Invalid signature. Expected 64 bytes").handle_error(),
+    };
+
+    let data = extend_with_signature_id(cell.repr_hash().as_slice(), signature_id);
+    Ok(public_key.verify(&data, &signature).is_ok())
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const SIGNED_TYPED_DATA: &str = r#"
+export type SignedTypedData = SignedData & {
+    boc: string,
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SignedTypedData")]
+    pub type JsSignedTypedData;
+}
+
+pub fn make_signed_typed_data(
+    cell: &ton_types::Cell,
+    signature: [u8; 64],
+) -> Result<JsSignedTypedData, JsValue> {
+    let boc = ton_types::serialize_toc(cell).handle_error()?;
+
+    let signed_data = make_signed_data(cell.repr_hash().inner(), signature);
+    js_sys::Reflect::set(
+        &signed_data,
+        &JsValue::from_str("boc"),
+        &JsValue::from(base64::encode(boc)),
+    )?;
+    Ok(signed_data.unchecked_into())
+}
+
 pub fn make_signed_data(hash: [u8; 32], signature: [u8; 64]) -> JsSignedData {
     ObjectBuilder::new()
         .set("dataHash", hex::encode(hash))
@@ -189,12 +1078,30 @@ pub fn parse_signed_message(data: JsSignedMessage) -> Result<ParsedSignedMessage
     JsValue::into_serde::<ParsedSignedMessage>(&data).handle_error()
 }
 
+/// Generates a new phrase and checks that it can be imported back into the keystore,
+/// so languages which the keystore signers can't derive from are rejected
 #[wasm_bindgen(js_name = "generateMnemonic")]
-pub fn generate_mnemonic(mnemonic_type: JsMnemonicType) -> Result<GeneratedMnemonic, JsValue> {
+pub fn generate_mnemonic(
+    mnemonic_type: JsMnemonicType,
+    language: Option<JsBip39Language>,
+    options: Option<JsGenerateMnemonicOptions>,
+) -> Result<GeneratedMnemonic, JsValue> {
     let mnemonic_type = parse_mnemonic_type(mnemonic_type)?;
+    let language = parse_bip39_language(language)?;
+    let options = options
+        .map(|options| JsValue::into_serde::<GenerateMnemonicOptions>(&options))
+        .transpose()
+        .handle_error()?
+        .unwrap_or_default();
+
+    let generated = generate_phrase(mnemonic_type, language, &options).handle_error()?;
 
-    let key = crypto::generate_key(mnemonic_type);
-    Ok(make_generated_mnemonic(key.words.join(" "), mnemonic_type))
+    Ok(make_generated_mnemonic(
+        generated.phrase,
+        mnemonic_type,
+        language,
+        generated.entropy_fingerprint,
+    ))
 }
 
 #[wasm_bindgen(typescript_custom_section)]
@@ -202,6 +1109,15 @@ const GENERATED_MNEMONIC: &str = r#"
 export type GeneratedMnemonic = {
     phrase: string,
     mnemonicType: MnemonicType,
+    language: Bip39Language,
+    entropyFingerprint?: string,
+};
+
+export type Bip39Language = 'english' | 'japanese' | 'korean';
+
+export type GenerateMnemonicOptions = {
+    extraEntropy?: string,
+    wordCount?: 12 | 15 | 18 | 21 | 24,
 };
 "#;
 
@@ -209,19 +1125,36 @@ export type GeneratedMnemonic = {
 extern "C" {
     #[wasm_bindgen(typescript_type = "GeneratedMnemonic")]
     pub type GeneratedMnemonic;
+
+    #[wasm_bindgen(typescript_type = "Bip39Language")]
+    pub type JsBip39Language;
+
+    #[wasm_bindgen(typescript_type = "GenerateMnemonicOptions")]
+    pub type JsGenerateMnemonicOptions;
 }
 
 fn make_generated_mnemonic(
     phrase: String,
     mnemonic_type: nt::crypto::MnemonicType,
+    language: Bip39Language,
+    entropy_fingerprint: Option<[u8; 4]>,
 ) -> GeneratedMnemonic {
     ObjectBuilder::new()
         .set("phrase", phrase)
         .set("mnemonicType", make_mnemonic_type(mnemonic_type))
+        .set("language", language.as_str())
+        .set("entropyFingerprint", entropy_fingerprint.map(hex::encode))
         .build()
         .unchecked_into()
 }
 
+pub fn parse_bip39_language(data: Option<JsBip39Language>) -> Result<Bip39Language, JsValue> {
+    match data {
+        Some(data) => JsValue::into_serde::<Bip39Language>(&data).handle_error(),
+        None => Ok(Bip39Language::default()),
+    }
+}
+
 #[wasm_bindgen(js_name = "makeLabsMnemonic")]
 pub fn make_labs_mnemonic(id: u16) -> JsMnemonicType {
     make_mnemonic_type(crypto::MnemonicType::Labs(id))
@@ -291,3 +1224,190 @@ where
     let data = String::deserialize(deserializer)?;
     ton_block::Message::construct_from_base64(&data).map_err(D::Error::custom)
 }
+
+#[wasm_bindgen(typescript_custom_section)]
+const OWNERSHIP_PROOF: &str = r#"
+export type OwnershipProofParams = {
+    domain: string,
+    address: string,
+    timestamp: number,
+    nonce: string,
+    payload?: string,
+};
+
+export type OwnershipProof = OwnershipProofParams & {
+    publicKey: string,
+    signature: string,
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "OwnershipProofParams")]
+    pub type JsOwnershipProofParams;
+
+    #[wasm_bindgen(typescript_type = "OwnershipProof")]
+    pub type JsOwnershipProof;
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedOwnershipProofParams {
+    pub domain: String,
+    pub address: String,
+    pub timestamp: u64,
+    pub nonce: String,
+    #[serde(default)]
+    pub payload: Option<String>,
+}
+
+impl ParsedOwnershipProofParams {
+    /// Computes the hash to sign.
+    ///
+    /// The message starts with a fixed prefix, so it can't be a valid message cell hash.
+    /// Variable-length fields are length-prefixed and the payload has a presence flag,
+    /// so different params never produce the same message
+    pub fn hash(&self) -> Result<[u8; 32], JsValue> {
+        let address = parse_address(&self.address)?;
+        Ok(self.hash_with_address(&address))
+    }
+
+    fn hash_with_address(&self, address: &ton_block::MsgAddressInt) -> [u8; 32] {
+        use sha2::Digest;
+
+        fn update_var(hasher: &mut sha2::Sha256, data: &[u8]) {
+            hasher.update((data.len() as u32).to_be_bytes());
+            hasher.update(data);
+        }
+
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(OWNERSHIP_PROOF_PREFIX);
+        hasher.update(address.workchain_id().to_be_bytes());
+        update_var(&mut hasher, &address.address().get_bytestring(0));
+        update_var(&mut hasher, self.domain.as_bytes());
+        update_var(&mut hasher, self.nonce.as_bytes());
+        hasher.update(self.timestamp.to_be_bytes());
+        match &self.payload {
+            Some(payload) => {
+                hasher.update([1]);
+                update_var(&mut hasher, payload.as_bytes());
+            }
+            None => hasher.update([0]),
+        }
+        hasher.finalize().into()
+    }
+}
+
+pub fn parse_ownership_proof_params(
+    params: JsOwnershipProofParams,
+) -> Result<ParsedOwnershipProofParams, JsValue> {
+    JsValue::into_serde(&params).handle_error()
+}
+
+pub fn make_ownership_proof(
+    params: ParsedOwnershipProofParams,
+    public_key: &ed25519_dalek::PublicKey,
+    signature: [u8; 64],
+) -> JsOwnershipProof {
+    ObjectBuilder::new()
+        .set("domain", params.domain)
+        .set("address", params.address)
+        .set("timestamp", params.timestamp as f64)
+        .set("nonce", params.nonce)
+        .set("payload", params.payload)
+        .set("publicKey", hex::encode(public_key.as_bytes()))
+        .set("signature", base64::encode(signature))
+        .build()
+        .unchecked_into()
+}
+
+/// Checks the proof against the public key of the wallet from its account state
+#[wasm_bindgen(js_name = "verifyOwnershipProof")]
+pub fn verify_ownership_proof(
+    proof: JsOwnershipProof,
+    account_stuff_boc: &str,
+    signature_id: Option<i32>,
+) -> Result<bool, JsValue> {
+    #[derive(Deserialize)]
+    struct ParsedOwnershipProof {
+        #[serde(flatten)]
+        params: ParsedOwnershipProofParams,
+        signature: String,
+    }
+
+    let proof = JsValue::into_serde::<ParsedOwnershipProof>(&proof).handle_error()?;
+
+    let account = parse_account_stuff(account_stuff_boc)?;
+    if account.addr != parse_address(&proof.params.address)? {
+        return Ok(false);
+    }
+
+    let public_key = nt_abi::extract_public_key(&account).handle_error()?;
+    let public_key = ed25519_dalek::PublicKey::from_bytes(public_key.as_ref()).handle_error()?;
+
+    let signature = parse_signature(&proof.signature)?;
+    let signature = match ed25519_dalek::Signature::try_from(signature.as_ref()) {
+        Ok(signature) => signature,
+        Err(_) => return Err("Invalid signature. Expected 64 bytes").handle_error(),
+    };
+
+    let hash = proof.params.hash()?;
+    let data = extend_with_signature_id(&hash, signature_id);
+    Ok(public_key.verify(&data, &signature).is_ok())
+}
+
+const OWNERSHIP_PROOF_PREFIX: &[u8] = b"\xff\xffnekoton-ownership-proof-v1";
+
+#[derive(thiserror::Error, Debug)]
+enum CryptoError {
+    #[error("Unsupported serialization version")]
+    UnsupportedSerializationVersion,
+    #[error("Invalid message layout")]
+    InvalidMessageLayout,
+    #[error("Unsupported message layout")]
+    UnsupportedMessageLayout,
+    #[error("Message hash mismatch")]
+    MessageHashMismatch,
+    #[error("Message summary mismatch")]
+    SummaryMismatch,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn params(payload: Option<&str>) -> ParsedOwnershipProofParams {
+        ParsedOwnershipProofParams {
+            domain: "example.com".to_owned(),
+            address: String::new(),
+            timestamp: 1700000000,
+            nonce: "nonce".to_owned(),
+            payload: payload.map(str::to_owned),
+        }
+    }
+
+    fn address() -> ton_block::MsgAddressInt {
+        ton_block::MsgAddressInt::from_str(
+            "0:b5e9240fc2d2f1ff8cbb1d1dee7fb7cae155e5f6320e585fcc685698994a19a5",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn ownership_proof_payload_is_unambiguous() {
+        let address = address();
+
+        let none = params(None).hash_with_address(&address);
+        let empty = params(Some("")).hash_with_address(&address);
+        assert_ne!(none, empty);
+
+        let shifted = ParsedOwnershipProofParams {
+            domain: "example.co".to_owned(),
+            nonce: "mnonce".to_owned(),
+            ..params(None)
+        };
+        assert_ne!(none, shifted.hash_with_address(&address));
+    }
+}
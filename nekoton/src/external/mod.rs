@@ -1,9 +1,10 @@
 use std::convert::TryFrom;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use anyhow::{Error, Result};
 use async_trait::async_trait;
 use futures::channel::oneshot;
+use nt_utils::TrustMe;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
@@ -240,12 +241,30 @@ export type LedgerSignatureContext = {
     asset: string,
     address: string,
 }
+
+/**
+ * Versioned description of the message, sent next to the legacy `LedgerSignatureContext`
+ */
+export type LedgerSigningContext = { version: 1 } & (
+    | EnumItem<'transfer', { address: string, amount: string, bounce: boolean } & LedgerAssetInfo>
+    | EnumItem<'token_transfer', { tokenWallet: string, recipient: string, tokens: string, attachedAmount: string } & LedgerAssetInfo>
+    | EnumItem<'multisig_submit', { wallet: string, address: string, amount: string, bounce: boolean } & LedgerAssetInfo>
+    | EnumItem<'multisig_confirm', { wallet: string, transactionId: string }>
+    | EnumItem<'deploy', { address: string }>
+    | EnumItem<'call', { address: string, method: string }>
+);
+
+export type LedgerAssetInfo = {
+    decimals?: number,
+    asset?: string,
+};
 "#;
 
 #[wasm_bindgen]
 extern "C" {
     pub type LedgerConnector;
     pub type LedgerSignatureContext;
+    pub type LedgerSigningContext;
 
     #[wasm_bindgen(method, js_name = "getPublicKey")]
     pub fn get_public_key(this: &LedgerConnector, account: u16, handler: LedgerQueryResultHandler);
@@ -257,9 +276,150 @@ extern "C" {
         message: &[u8],
         context: Option<LedgerSignatureContext>,
         handler: LedgerQueryResultHandler,
+        signing_context: Option<LedgerSigningContext>,
     );
 }
 
+pub const LEDGER_SIGNING_CONTEXT_VERSION: u8 = 1;
+
+/// Detailed description of the message shown on the device
+#[derive(Clone, Debug)]
+pub enum ParsedLedgerSigningContext {
+    Transfer {
+        address: String,
+        amount: String,
+        bounce: bool,
+    },
+    TokenTransfer {
+        token_wallet: String,
+        recipient: String,
+        tokens: String,
+        attached_amount: String,
+    },
+    MultisigSubmit {
+        wallet: String,
+        address: String,
+        amount: String,
+        bounce: bool,
+    },
+    MultisigConfirm {
+        wallet: String,
+        transaction_id: u64,
+    },
+    Deploy {
+        address: String,
+    },
+    Call {
+        address: String,
+        method: String,
+    },
+}
+
+fn make_ledger_signature_context(
+    context: &nt::external::LedgerSignatureContext,
+) -> LedgerSignatureContext {
+    ObjectBuilder::new()
+        .set("amount", context.amount.to_string())
+        .set("decimals", context.decimals)
+        .set("asset", truncate_asset(&context.asset))
+        .set("address", context.address.to_string())
+        .build()
+        .unchecked_into()
+}
+
+/// Ledger app can't display asset names longer than 31 bytes
+fn truncate_asset(asset: &str) -> String {
+    let as_bytes = asset.as_bytes();
+    match as_bytes.len() {
+        len if len < 32 => asset.to_string(),
+        _ => String::from_utf8_lossy(&as_bytes[..31]).to_string(),
+    }
+}
+
+/// Asset info is provided by the caller, because it can't be extracted from the message
+fn make_ledger_signing_context(
+    context: ParsedLedgerSigningContext,
+    asset_info: Option<&nt::external::LedgerSignatureContext>,
+) -> LedgerSigningContext {
+    let with_asset_info = |builder: ObjectBuilder| match asset_info {
+        Some(info) => builder
+            .set("decimals", info.decimals)
+            .set("asset", truncate_asset(&info.asset)),
+        None => builder,
+    };
+
+    let (ty, data) = match context {
+        ParsedLedgerSigningContext::Transfer {
+            address,
+            amount,
+            bounce,
+        } => (
+            "transfer",
+            with_asset_info(
+                ObjectBuilder::new()
+                    .set("address", address)
+                    .set("amount", amount)
+                    .set("bounce", bounce),
+            ),
+        ),
+        ParsedLedgerSigningContext::TokenTransfer {
+            token_wallet,
+            recipient,
+            tokens,
+            attached_amount,
+        } => (
+            "token_transfer",
+            with_asset_info(
+                ObjectBuilder::new()
+                    .set("tokenWallet", token_wallet)
+                    .set("recipient", recipient)
+                    .set("tokens", tokens)
+                    .set("attachedAmount", attached_amount),
+            ),
+        ),
+        ParsedLedgerSigningContext::MultisigSubmit {
+            wallet,
+            address,
+            amount,
+            bounce,
+        } => (
+            "multisig_submit",
+            with_asset_info(
+                ObjectBuilder::new()
+                    .set("wallet", wallet)
+                    .set("address", address)
+                    .set("amount", amount)
+                    .set("bounce", bounce),
+            ),
+        ),
+        ParsedLedgerSigningContext::MultisigConfirm {
+            wallet,
+            transaction_id,
+        } => (
+            "multisig_confirm",
+            ObjectBuilder::new()
+                .set("wallet", wallet)
+                .set("transactionId", format!("{:x}", transaction_id)),
+        ),
+        ParsedLedgerSigningContext::Deploy { address } => {
+            ("deploy", ObjectBuilder::new().set("address", address))
+        }
+        ParsedLedgerSigningContext::Call { address, method } => (
+            "call",
+            ObjectBuilder::new()
+                .set("address", address)
+                .set("method", method),
+        ),
+    };
+
+    ObjectBuilder::new()
+        .set("version", LEDGER_SIGNING_CONTEXT_VERSION)
+        .set("type", ty)
+        .set("data", data.build())
+        .build()
+        .unchecked_into()
+}
+
 unsafe impl Send for LedgerConnector {}
 
 unsafe impl Sync for LedgerConnector {}
@@ -305,69 +465,159 @@ impl LedgerConnection {
 
 pub struct LedgerConnectionImpl {
     connector: Arc<LedgerConnector>,
+    /// Fingerprint of the connected device, reset when any query fails
+    device: Mutex<Option<String>>,
 }
 
 impl LedgerConnectionImpl {
     fn new(connector: LedgerConnector) -> Self {
         Self {
             connector: Arc::new(connector),
+            device: Default::default(),
         }
     }
-}
 
-#[async_trait]
-impl nt::external::LedgerConnection for LedgerConnectionImpl {
-    async fn get_public_key(&self, account: u16) -> Result<[u8; ed25519_dalek::PUBLIC_KEY_LENGTH]> {
+    /// Signs the data on the device with the specified fingerprint (if any).
+    ///
+    /// `message` is the 32-byte hash. The legacy `context` is sent as is, while `signing_context`
+    /// is only built from the message itself, so no fields are guessed. Both are forwarded
+    /// to the device app through the bridge.
+    pub async fn sign_with_context(
+        &self,
+        account: u16,
+        device: Option<String>,
+        message: &[u8],
+        context: Option<&nt::external::LedgerSignatureContext>,
+        signing_context: Option<ParsedLedgerSigningContext>,
+    ) -> Result<[u8; ed25519_dalek::SIGNATURE_LENGTH]> {
+        if let Some(expected) = &device {
+            self.check_device(expected).await?;
+        }
+
         let (tx, rx) = oneshot::channel();
-        self.connector.get_public_key(
+        self.connector.sign(
             account,
+            message,
+            context.map(make_ledger_signature_context),
             LedgerQueryResultHandler {
                 inner: QueryHandler::new(tx),
             },
+            signing_context
+                .map(|signing_context| make_ledger_signing_context(signing_context, context)),
         );
-        match rx.await.map_err(|_| LedgerConnectionError::QueryDropped)? {
-            Ok(vec) => Ok(<[u8; ed25519_dalek::PUBLIC_KEY_LENGTH]>::try_from(
+        let result = match rx.await.map_err(|_| LedgerConnectionError::QueryDropped)? {
+            Ok(vec) => Ok(<[u8; ed25519_dalek::SIGNATURE_LENGTH]>::try_from(
                 vec.as_slice(),
             )?),
             Err(err) => Err(err),
+        };
+        if result.is_err() {
+            self.reset_device();
         }
+        result
     }
 
-    async fn sign(
+    /// Returns fingerprint of the currently connected device
+    pub async fn get_device_fingerprint(&self) -> Result<String> {
+        let public_key = self.query_public_key(0).await?;
+        let device = compute_device_fingerprint(&public_key);
+        *self.device.lock().trust_me() = Some(device.clone());
+        Ok(device)
+    }
+
+    /// Fails if the device with the specified fingerprint is not connected.
+    /// Fingerprint is queried once per connection.
+    ///
+    /// NOTE: bridge doesn't select the device by its fingerprint,
+    /// so this check must precede every query for a known device
+    pub async fn check_device(&self, expected: &str) -> Result<()> {
+        let cached = self.device.lock().trust_me().clone();
+        let found = match cached {
+            Some(device) => device,
+            None => self.get_device_fingerprint().await?,
+        };
+        if expected != found {
+            // Device could have been replaced without failed queries
+            self.reset_device();
+            return Err(LedgerConnectionError::WrongDevice {
+                expected: expected.to_owned(),
+                found,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    fn reset_device(&self) {
+        self.device.lock().trust_me().take();
+    }
+
+    /// Returns public keys of the accounts in range `offset..offset + limit`
+    pub async fn get_public_keys(
+        &self,
+        offset: u16,
+        limit: u16,
+        device: Option<String>,
+    ) -> Result<Vec<ed25519_dalek::PublicKey>> {
+        if let Some(expected) = &device {
+            self.check_device(expected).await?;
+        }
+
+        let mut public_keys = Vec::with_capacity(limit as usize);
+        for account in offset..offset.saturating_add(limit) {
+            let public_key = self.query_public_key(account).await?;
+            public_keys.push(ed25519_dalek::PublicKey::from_bytes(&public_key)?);
+        }
+        Ok(public_keys)
+    }
+
+    async fn query_public_key(
         &self,
         account: u16,
-        message: &[u8],
-        context: &Option<nt::external::LedgerSignatureContext>,
-    ) -> Result<[u8; ed25519_dalek::SIGNATURE_LENGTH]> {
+    ) -> Result<[u8; ed25519_dalek::PUBLIC_KEY_LENGTH]> {
         let (tx, rx) = oneshot::channel();
-        self.connector.sign(
+        self.connector.get_public_key(
             account,
-            message,
-            context.as_ref().map(|ctx| {
-                ObjectBuilder::new()
-                    .set("amount", ctx.amount.to_string())
-                    .set("decimals", ctx.decimals)
-                    .set("asset", {
-                        let as_bytes = ctx.asset.as_bytes();
-                        match as_bytes.len() {
-                            len if len < 32 => ctx.asset.to_string(),
-                            _ => String::from_utf8_lossy(&as_bytes[..31]).to_string(),
-                        }
-                    })
-                    .set("address", ctx.address.to_string())
-                    .build()
-                    .unchecked_into()
-            }),
             LedgerQueryResultHandler {
                 inner: QueryHandler::new(tx),
             },
         );
-        match rx.await.map_err(|_| LedgerConnectionError::QueryDropped)? {
-            Ok(vec) => Ok(<[u8; ed25519_dalek::SIGNATURE_LENGTH]>::try_from(
+        let result = match rx.await.map_err(|_| LedgerConnectionError::QueryDropped)? {
+            Ok(vec) => Ok(<[u8; ed25519_dalek::PUBLIC_KEY_LENGTH]>::try_from(
                 vec.as_slice(),
             )?),
             Err(err) => Err(err),
+        };
+        if result.is_err() {
+            self.reset_device();
         }
+        result
+    }
+}
+
+const DEVICE_FINGERPRINT_LEN: usize = 16;
+
+/// Device fingerprint is derived from the public key of the account 0
+pub fn compute_device_fingerprint(public_key: &[u8; ed25519_dalek::PUBLIC_KEY_LENGTH]) -> String {
+    use sha2::Digest;
+
+    hex::encode(&sha2::Sha256::digest(public_key)[..DEVICE_FINGERPRINT_LEN])
+}
+
+#[async_trait]
+impl nt::external::LedgerConnection for LedgerConnectionImpl {
+    async fn get_public_key(&self, account: u16) -> Result<[u8; ed25519_dalek::PUBLIC_KEY_LENGTH]> {
+        self.query_public_key(account).await
+    }
+
+    async fn sign(
+        &self,
+        account: u16,
+        message: &[u8],
+        context: &Option<nt::external::LedgerSignatureContext>,
+    ) -> Result<[u8; ed25519_dalek::SIGNATURE_LENGTH]> {
+        self.sign_with_context(account, None, message, context.as_ref(), None)
+            .await
     }
 }
 
@@ -377,6 +627,8 @@ pub enum LedgerConnectionError {
     QueryDropped,
     #[error("Query failed")]
     QueryFailed,
+    #[error("Wrong Ledger device connected (expected {expected}, found {found})")]
+    WrongDevice { expected: String, found: String },
 }
 
 unsafe impl Send for JrpcSender {}
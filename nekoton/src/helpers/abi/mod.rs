@@ -233,13 +233,16 @@ pub fn create_external_message(
             input,
         )
         .handle_error()?,
+        kind: crate::crypto::UnsignedMessageKind::Call {
+            method: method.name.clone(),
+        },
     })
 }
 
 #[wasm_bindgen(js_name = "parseKnownPayload")]
 pub fn parse_known_payload(payload: &str) -> Option<crate::core::models::KnownPayload> {
     let payload = parse_slice(payload).ok()?;
-    crate::core::models::make_known_payload(nt::core::parsing::parse_payload(payload))
+    crate::core::models::parse_known_payload(payload)
 }
 
 #[wasm_bindgen(js_name = "decodeInput")]
@@ -251,12 +254,12 @@ pub fn decode_input(
 ) -> Result<Option<DecodedInput>, JsValue> {
     let message_body = parse_slice(message_body)?;
     let contract_abi = parse_contract_abi(contract_abi)?;
-    let method = match guess_method_by_input(&contract_abi, &message_body, method, internal)? {
-        Some(method) => method,
+    let (method, input) = match decode_input_tokens(&contract_abi, message_body, method, internal)?
+    {
+        Some(decoded) => decoded,
         None => return Ok(None),
     };
 
-    let input = method.decode_input(message_body, internal).handle_error()?;
     Ok(Some(
         ObjectBuilder::new()
             .set("method", &method.name)
@@ -266,6 +269,21 @@ pub fn decode_input(
     ))
 }
 
+pub fn decode_input_tokens<'a>(
+    contract_abi: &'a ton_abi::Contract,
+    message_body: ton_types::SliceData,
+    method: JsMethodName,
+    internal: bool,
+) -> Result<Option<(&'a ton_abi::Function, Vec<ton_abi::Token>)>, JsValue> {
+    let method = match guess_method_by_input(contract_abi, &message_body, method, internal)? {
+        Some(method) => method,
+        None => return Ok(None),
+    };
+
+    let input = method.decode_input(message_body, internal).handle_error()?;
+    Ok(Some((method, input)))
+}
+
 #[wasm_bindgen(js_name = "decodeEvent")]
 pub fn decode_event(
     message_body: &str,
@@ -1054,7 +1072,7 @@ fn parse_token(param: &ton_abi::Param, value: JsValue) -> Result<ton_abi::Token,
     })
 }
 
-fn make_tokens_object(tokens: &[ton_abi::Token]) -> Result<TokensObject, JsValue> {
+pub fn make_tokens_object(tokens: &[ton_abi::Token]) -> Result<TokensObject, JsValue> {
     let object = js_sys::Object::new();
     for token in tokens.iter() {
         js_sys::Reflect::set(
@@ -1347,13 +1365,21 @@ pub fn pack_into_cell(
     tokens: TokensObject,
     abi_version: Option<String>,
 ) -> Result<String, JsValue> {
+    let cell = pack_tokens_into_cell(params, tokens, abi_version)?;
+    let bytes = ton_types::serialize_toc(&cell).handle_error()?;
+    Ok(base64::encode(&bytes))
+}
+
+pub fn pack_tokens_into_cell(
+    params: ParamsList,
+    tokens: TokensObject,
+    abi_version: Option<String>,
+) -> Result<ton_types::Cell, JsValue> {
     let params = parse_params_list(params).handle_error()?;
     let tokens = parse_tokens_object(&params, tokens).handle_error()?;
 
     let abi_version = parse_optional_abi_version(abi_version)?;
-    let cell = nt_abi::pack_into_cell(&tokens, abi_version).handle_error()?;
-    let bytes = ton_types::serialize_toc(&cell).handle_error()?;
-    Ok(base64::encode(&bytes))
+    nt_abi::pack_into_cell(&tokens, abi_version).handle_error()
 }
 
 #[wasm_bindgen(js_name = "unpackFromCell")]
@@ -1421,7 +1447,7 @@ enum AbiError {
     UnsupportedHeader,
 }
 
-fn parse_contract_abi(contract_abi: &str) -> Result<ton_abi::Contract, JsValue> {
+pub fn parse_contract_abi(contract_abi: &str) -> Result<ton_abi::Contract, JsValue> {
     ton_abi::Contract::load(contract_abi).handle_error()
 }
 
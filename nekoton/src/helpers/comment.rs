@@ -0,0 +1,95 @@
+use anyhow::Result;
+use ton_types::{BuilderData, Cell, SliceData};
+
+/// Prefix of the encrypted comment payload (plain comments start with zero)
+pub const ENCRYPTED_COMMENT_TAG: u32 = 0x6e656331;
+
+const PUBLIC_KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const CELL_BYTES: usize = 127;
+const HEADER_BYTES: usize = 4 + PUBLIC_KEY_LEN * 2 + NONCE_LEN;
+
+/// Layout: `[tag u32][source 32B][recipient 32B][nonce 12B][data...]`,
+/// where data is continued in the chain of child cells
+pub fn build_encrypted_comment_payload(data: &nt::crypto::EncryptedData) -> Result<Cell> {
+    if data.nonce.len() != NONCE_LEN {
+        return Err(EncryptedCommentError::InvalidNonce.into());
+    }
+
+    let (head, tail) = data
+        .data
+        .split_at(std::cmp::min(data.data.len(), CELL_BYTES - HEADER_BYTES));
+
+    let mut child = None;
+    for chunk in tail.chunks(CELL_BYTES).rev() {
+        let mut builder = BuilderData::new();
+        builder.append_raw(chunk, chunk.len() * 8)?;
+        if let Some(child) = child.take() {
+            builder.checked_append_reference(child)?;
+        }
+        child = Some(builder.into_cell()?);
+    }
+
+    let mut builder = BuilderData::new();
+    builder.append_u32(ENCRYPTED_COMMENT_TAG)?;
+    builder.append_raw(data.source_public_key.as_bytes(), PUBLIC_KEY_LEN * 8)?;
+    builder.append_raw(data.recipient_public_key.as_bytes(), PUBLIC_KEY_LEN * 8)?;
+    builder.append_raw(&data.nonce, NONCE_LEN * 8)?;
+    builder.append_raw(head, head.len() * 8)?;
+    if let Some(child) = child {
+        builder.checked_append_reference(child)?;
+    }
+
+    builder.into_cell()
+}
+
+pub fn parse_encrypted_comment_payload(
+    mut payload: SliceData,
+) -> Option<nt::crypto::EncryptedData> {
+    if payload.get_next_u32().ok()? != ENCRYPTED_COMMENT_TAG {
+        return None;
+    }
+
+    let source_public_key = read_public_key(&mut payload)?;
+    let recipient_public_key = read_public_key(&mut payload)?;
+    let nonce = payload.get_next_bytes(NONCE_LEN).ok()?;
+
+    let mut data = Vec::new();
+    loop {
+        if payload.remaining_bits() % 8 != 0 {
+            return None;
+        }
+        data.extend(payload.get_next_bytes(payload.remaining_bits() / 8).ok()?);
+
+        match payload.remaining_references() {
+            0 => break,
+            1 => payload = payload.reference(0).ok()?.into(),
+            _ => return None,
+        }
+    }
+
+    Some(nt::crypto::EncryptedData {
+        algorithm: nt::crypto::EncryptionAlgorithm::ChaCha20Poly1305,
+        source_public_key,
+        recipient_public_key,
+        data,
+        nonce,
+    })
+}
+
+fn read_public_key(payload: &mut SliceData) -> Option<ed25519_dalek::PublicKey> {
+    let bytes = payload.get_next_bytes(PUBLIC_KEY_LEN).ok()?;
+    ed25519_dalek::PublicKey::from_bytes(&bytes).ok()
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum EncryptedCommentError {
+    #[error("Invalid nonce")]
+    InvalidNonce,
+    #[error("Comment is not encrypted for this key")]
+    UnknownKey,
+    #[error("Invalid comment")]
+    InvalidComment,
+    #[error("Recipient wallet is not deployed")]
+    RecipientNotDeployed,
+}
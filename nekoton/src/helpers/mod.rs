@@ -8,6 +8,7 @@ use wasm_bindgen::JsCast;
 use crate::utils::*;
 
 pub mod abi;
+pub mod comment;
 
 #[wasm_bindgen(js_name = "base64ToUtf8Lossy")]
 pub fn base64_to_utf8_lossy(data: &str) -> Result<String, JsValue> {
@@ -86,23 +87,27 @@ pub fn repack_address(address: &str) -> Result<String, JsValue> {
 }
 
 #[wasm_bindgen(js_name = "getBip39Hints")]
-pub fn get_hints(word: &str) -> StringArray {
-    nt::crypto::dict::get_hints(word)
+pub fn get_hints(
+    word: &str,
+    language: Option<crate::crypto::JsBip39Language>,
+) -> Result<StringArray, JsValue> {
+    let language = crate::crypto::parse_bip39_language(language)?;
+    Ok(crate::crypto::mnemonic::get_hints(word, language)
         .into_iter()
-        .map(|word| JsValue::from_str(word))
+        .map(JsValue::from_str)
         .collect::<js_sys::Array>()
-        .unchecked_into()
+        .unchecked_into())
 }
 
 #[wasm_bindgen(js_name = "validateMnemonic")]
 pub fn validate_mnemonic(
     phrase: &str,
     mnemonic_type: crate::crypto::JsMnemonicType,
+    language: Option<crate::crypto::JsBip39Language>,
 ) -> Result<(), JsValue> {
     let mnemonic_type = crate::crypto::parse_mnemonic_type(mnemonic_type)?;
-    nt::crypto::derive_from_phrase(phrase, mnemonic_type)
-        .handle_error()
-        .map(|_| ())
+    let language = crate::crypto::parse_bip39_language(language)?;
+    crate::crypto::mnemonic::validate_phrase(phrase, mnemonic_type, language).handle_error()
 }
 
 #[wasm_bindgen(js_name = "encodeComment")]
@@ -14,6 +14,9 @@ use wasm_bindgen::prelude::*;
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     fn log(s: &str);
+
+    #[wasm_bindgen(js_namespace = console)]
+    pub(crate) fn warn(s: &str);
 }
 
 #[wasm_bindgen(start)]
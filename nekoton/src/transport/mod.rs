@@ -1,12 +1,16 @@
 use std::convert::{TryFrom, TryInto};
-use std::sync::Arc;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
+use gloo_utils::format::JsValueSerdeExt;
+use serde::Deserialize;
 use ton_block::Serializable;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::*;
 
 use nt::transport;
+use nt_utils::TrustMe;
 
 use crate::core::token_wallet::RootTokenContractDetailsWithAddress;
 use crate::utils::*;
@@ -31,8 +35,36 @@ impl TransportHandle {
             _ => Err(TransportError::MethodNotSupported).handle_error(),
         }
     }
+
+    /// Returns global id of the network if it must be mixed into signatures,
+    /// and the seqno of the key block with the config
+    pub async fn get_signature_id(&self) -> Result<(Option<i32>, u32), JsValue> {
+        let key_block = self.as_ref().get_latest_key_block().await.handle_error()?;
+        let seqno = key_block.read_info().handle_error()?.seq_no();
+
+        let config = key_block
+            .read_extra()
+            .and_then(|extra| extra.read_custom())
+            .handle_error()?
+            .and_then(|custom| custom.config().cloned())
+            .ok_or(TransportError::BlockchainConfigNotFound)
+            .handle_error()?;
+
+        let signature_id = if config.capabilities() & CAP_SIGNATURE_WITH_ID != 0 {
+            Some(key_block.global_id())
+        } else {
+            None
+        };
+        Ok((signature_id, seqno))
+    }
 }
 
+const CAP_SIGNATURE_WITH_ID: u64 = 0x4000000;
+
+/// Network config can only change in a key block, so the cached signature id
+/// is revalidated against the latest key block after this interval
+const SIGNATURE_ID_CHECK_INTERVAL_MS: u64 = 60_000;
+
 impl<'a> AsRef<dyn transport::Transport + 'a> for TransportHandle {
     fn as_ref(&self) -> &(dyn transport::Transport + 'a) {
         match self {
@@ -57,6 +89,49 @@ pub struct Transport {
     pub handle: TransportHandle,
     #[wasm_bindgen(skip)]
     pub clock: Arc<nt_utils::ClockWithOffset>,
+    #[wasm_bindgen(skip)]
+    pub signature_id: Arc<Mutex<Option<CachedSignatureId>>>,
+}
+
+/// Signature id with the latest key block it was read from
+#[derive(Copy, Clone)]
+pub struct CachedSignatureId {
+    signature_id: Option<i32>,
+    key_block_seqno: u32,
+    checked_at: u64,
+}
+
+/// Returns the network signature id. The cached one is used
+/// until it is revalidated against the latest key block
+pub async fn resolve_signature_id(
+    handle: &TransportHandle,
+    cached: &Mutex<Option<CachedSignatureId>>,
+) -> Result<Option<i32>, JsValue> {
+    let now = nt_utils::now_ms_u64();
+
+    let cached_signature_id = *cached.lock().trust_me();
+    Ok(match cached_signature_id {
+        Some(cached) if now < cached.checked_at + SIGNATURE_ID_CHECK_INTERVAL_MS => {
+            cached.signature_id
+        }
+        _ => {
+            let (signature_id, key_block_seqno) = handle.get_signature_id().await?;
+
+            let mut cached = cached.lock().trust_me();
+            let is_newer = match &*cached {
+                Some(cached) => key_block_seqno >= cached.key_block_seqno,
+                None => true,
+            };
+            if is_newer {
+                *cached = Some(CachedSignatureId {
+                    signature_id,
+                    key_block_seqno,
+                    checked_at: now,
+                });
+            }
+            signature_id
+        }
+    })
 }
 
 #[wasm_bindgen]
@@ -67,6 +142,7 @@ impl Transport {
         Self {
             handle: TransportHandle::GraphQl(transport),
             clock: gql.clock.clone(),
+            signature_id: Default::default(),
         }
     }
 
@@ -76,6 +152,7 @@ impl Transport {
         Self {
             handle: TransportHandle::Jrpc(transport),
             clock: jrpc.clock.clone(),
+            signature_id: Default::default(),
         }
     }
 
@@ -127,17 +204,19 @@ impl Transport {
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
             let wallet = nt::core::ton_wallet::TonWallet::subscribe(
-                clock,
+                clock.clone(),
                 handle.clone().into(),
                 workchain,
                 public_key,
                 contract_type,
-                handler,
+                handler.clone(),
             )
             .await
             .handle_error()?;
 
-            Ok(JsValue::from(TonWallet::new(handle, wallet)))
+            Ok(JsValue::from(TonWallet::new(
+                clock, handle, wallet, handler,
+            )))
         })))
     }
 
@@ -157,15 +236,17 @@ impl Transport {
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
             let wallet = nt::core::ton_wallet::TonWallet::subscribe_by_address(
-                clock,
+                clock.clone(),
                 handle.clone().into(),
                 address,
-                handler,
+                handler.clone(),
             )
             .await
             .handle_error()?;
 
-            Ok(JsValue::from(TonWallet::new(handle, wallet)))
+            Ok(JsValue::from(TonWallet::new(
+                clock, handle, wallet, handler,
+            )))
         })))
     }
 
@@ -187,7 +268,7 @@ impl Transport {
 
         Ok(JsCast::unchecked_into(future_to_promise(async move {
             let wallet = nt::core::token_wallet::TokenWallet::subscribe(
-                clock,
+                clock.clone(),
                 handle.clone().into(),
                 owner,
                 root_token_contract,
@@ -196,7 +277,7 @@ impl Transport {
             .await
             .handle_error()?;
 
-            Ok(JsValue::from(TokenWallet::new(handle, wallet)))
+            Ok(JsValue::from(TokenWallet::new(clock, handle, wallet)))
         })))
     }
 
@@ -242,6 +323,116 @@ impl Transport {
         })))
     }
 
+    #[wasm_bindgen(js_name = "getSignatureId")]
+    pub fn get_signature_id(&self) -> PromiseOptionNumber {
+        let handle = self.handle.clone();
+        let cached = self.signature_id.clone();
+
+        JsCast::unchecked_into(future_to_promise(async move {
+            let signature_id = resolve_signature_id(&handle, &cached).await?;
+            Ok(signature_id.map(JsValue::from).unwrap_or_default())
+        }))
+    }
+
+    #[wasm_bindgen(js_name = "discoverAccounts")]
+    pub fn discover_accounts(
+        &self,
+        key_store: &crate::core::keystore::KeyStore,
+        master_key: &str,
+        password: Option<String>,
+        options: Option<DiscoverAccountsOptions>,
+    ) -> Result<PromiseDiscoveredAccountList, JsValue> {
+        let master_key = parse_public_key(master_key)?;
+        let options = options
+            .map(|options| JsValue::into_serde::<ParsedDiscoverAccountsOptions>(&options))
+            .transpose()
+            .handle_error()?
+            .unwrap_or_default();
+
+        let wallet_types = match options.contract_types {
+            Some(contract_types) => contract_types
+                .iter()
+                .map(|item| nt::core::ton_wallet::WalletType::from_str(item))
+                .collect::<Result<Vec<_>, _>>()
+                .handle_error()?,
+            None => crate::core::ton_wallet::all_wallet_types(),
+        };
+        let workchain_id = options.workchain.unwrap_or_default();
+        let gap_limit = options
+            .gap_limit
+            .unwrap_or(DEFAULT_DISCOVERY_GAP_LIMIT)
+            .max(1);
+
+        let handle = self.handle.clone();
+        let inner = key_store.inner.clone();
+        let state = key_store.state.clone();
+
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            let mut result = Vec::new();
+            let mut empty_keys = 0;
+            let mut offset = 0u16;
+
+            while empty_keys < gap_limit {
+                let public_keys = crate::core::keystore::get_derived_public_keys(
+                    &inner,
+                    &state,
+                    master_key,
+                    password.clone(),
+                    options.cache,
+                    offset,
+                    gap_limit,
+                )
+                .await?;
+                if public_keys.is_empty() {
+                    break;
+                }
+
+                for (i, public_key) in public_keys.iter().enumerate() {
+                    let account_id = offset + i as u16;
+
+                    let wallets = nt::core::ton_wallet::find_existing_wallets(
+                        handle.as_ref(),
+                        public_key,
+                        workchain_id,
+                        &wallet_types,
+                    )
+                    .await
+                    .handle_error()?
+                    .into_iter()
+                    .filter(|wallet| {
+                        wallet.contract_state.is_deployed || wallet.contract_state.balance > 0
+                    })
+                    .collect::<Vec<_>>();
+
+                    if wallets.is_empty() {
+                        empty_keys += 1;
+                        if empty_keys >= gap_limit {
+                            break;
+                        }
+                        continue;
+                    }
+                    empty_keys = 0;
+
+                    result.extend(
+                        wallets
+                            .into_iter()
+                            .map(|wallet| make_discovered_account(wallet, account_id)),
+                    );
+                }
+
+                offset = match offset.checked_add(public_keys.len() as u16) {
+                    Some(offset) => offset,
+                    None => break,
+                };
+            }
+
+            Ok(result
+                .into_iter()
+                .collect::<js_sys::Array>()
+                .unchecked_into())
+        })))
+    }
+
     #[wasm_bindgen(js_name = "getNativeWalletInitData")]
     pub fn get_native_wallet_init_data(
         &self,
@@ -324,6 +515,33 @@ impl Transport {
         })))
     }
 
+    #[wasm_bindgen(js_name = "prepareTokenMigration")]
+    pub fn prepare_token_migration(
+        &self,
+        owner: &str,
+        new_owner: &str,
+        assets: crate::core::token_wallet::TokenWalletAssetList,
+    ) -> Result<crate::core::token_wallet::PromiseTokenMigrationMessageList, JsValue> {
+        use crate::core::token_wallet::*;
+
+        let owner = parse_address(owner)?;
+        let new_owner = parse_address(new_owner)?;
+        let root_token_contracts = JsValue::into_serde::<Vec<ParsedTokenWalletAsset>>(&assets)
+            .handle_error()?
+            .into_iter()
+            .map(|asset| parse_address(&asset.root_token_contract))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let clock = self.clock.clone();
+        let handle = self.handle.clone();
+
+        Ok(JsCast::unchecked_into(future_to_promise(async move {
+            prepare_token_migration(clock, handle, owner, new_owner, root_token_contracts)
+                .await
+                .map(JsValue::from)
+        })))
+    }
+
     #[wasm_bindgen(js_name = "getTokenRootDetailsFromTokenWallet")]
     pub fn get_token_root_details_from_token_wallet(
         &self,
@@ -689,6 +907,73 @@ extern "C" {
     pub type PromiseExistingWalletInfoList;
 }
 
+#[wasm_bindgen(typescript_custom_section)]
+const DISCOVER_ACCOUNTS: &'static str = r#"
+export type DiscoverAccountsOptions = {
+    workchain?: number,
+    contractTypes?: ContractType[],
+    gapLimit?: number,
+    cache?: boolean,
+};
+
+export type DiscoveredAccount = ExistingWalletInfo & {
+    name: string,
+    workchain: number,
+    accountId: number,
+};
+"#;
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ParsedDiscoverAccountsOptions {
+    #[serde(default)]
+    workchain: Option<i8>,
+    #[serde(default)]
+    contract_types: Option<Vec<String>>,
+    #[serde(default)]
+    gap_limit: Option<u16>,
+    #[serde(default)]
+    cache: bool,
+}
+
+fn make_discovered_account(
+    data: nt::core::ton_wallet::ExistingWalletInfo,
+    account_id: u16,
+) -> JsValue {
+    let name = format!("Account {}", account_id as u32 + 1);
+    let workchain = data.address.workchain_id();
+
+    let account = make_existing_wallet_info(data);
+    js_sys::Reflect::set(&account, &JsValue::from_str("name"), &JsValue::from(name)).trust_me();
+    js_sys::Reflect::set(
+        &account,
+        &JsValue::from_str("workchain"),
+        &JsValue::from(workchain),
+    )
+    .trust_me();
+    js_sys::Reflect::set(
+        &account,
+        &JsValue::from_str("accountId"),
+        &JsValue::from(account_id),
+    )
+    .trust_me();
+    account
+}
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "DiscoverAccountsOptions")]
+    pub type DiscoverAccountsOptions;
+
+    #[wasm_bindgen(typescript_type = "Promise<Array<DiscoveredAccount>>")]
+    pub type PromiseDiscoveredAccountList;
+
+    #[wasm_bindgen(typescript_type = "Promise<number | undefined>")]
+    pub type PromiseOptionNumber;
+}
+
+const DEFAULT_DISCOVERY_GAP_LIMIT: u16 = 5;
+
 #[wasm_bindgen(typescript_custom_section)]
 const FULL_CONTRACT_STATE: &'static str = r#"
 export type FullContractState = {
@@ -752,6 +1037,8 @@ enum TransportError {
     MethodNotSupported,
     #[error("Wallet not deployed")]
     WalletNotDeployed,
+    #[error("Blockchain config not found")]
+    BlockchainConfigNotFound,
 }
 
 #[wasm_bindgen]
@@ -116,11 +116,24 @@ export class NekotonController extends EventEmitter {
         const ledgerBridge = new LedgerBridge()
         const ledgerConnection = new nt.LedgerConnection(new LedgerConnection(ledgerBridge))
 
-        const keyStore = await nt.KeyStore.load(storage, ledgerConnection)
+        let accountController: AccountController | undefined
+        const keyStore = await nt.KeyStore.load(storage, ledgerConnection, {
+            onKeyAdded: (entry: nt.KeyStoreEntry) => accountController?.handleKeyUpdated(entry),
+            onKeyRemoved: (entry: nt.KeyStoreEntry) => accountController?.handleKeyRemoved(entry),
+            onKeyRenamed: (entry: nt.KeyStoreEntry) => accountController?.handleKeyUpdated(entry),
+            onPasswordCacheChanged: (_masterKey: string, _cached: boolean) => {},
+        })
         setInterval(() => {
             keyStore.refreshPasswordCache()
         }, 10000)
 
+        // Keystore updates from other contexts are only visible through the storage
+        window.browser.storage.onChanged.addListener((changes, areaName) => {
+            if (areaName === 'local' && changes[nt.KeyStore.revisionStorageKey()] != null) {
+                keyStore.reload().catch(console.error)
+            }
+        })
+
         const clock = new nt.ClockWithOffset()
 
         const connectionController = new ConnectionController({
@@ -133,7 +146,7 @@ export class NekotonController extends EventEmitter {
 
         const localizationController = new LocalizationController({})
 
-        const accountController = new AccountController({
+        accountController = new AccountController({
             clock,
             storage,
             accountsStorage,
@@ -159,6 +172,7 @@ export class NekotonController extends EventEmitter {
         const subscriptionsController = new SubscriptionController({
             clock,
             connectionController,
+            keyStore,
         })
 
         await localizationController.initialSync()
@@ -342,6 +356,7 @@ export class NekotonController extends EventEmitter {
                 accountController,
                 'getMultisigPendingTransactions'
             ),
+            getMultisigPendingUpdates: nodeifyAsync(accountController, 'getMultisigPendingUpdates'),
             findExistingWallets: nodeifyAsync(accountController, 'findExistingWallets'),
             getTonWalletInitData: nodeifyAsync(accountController, 'getTonWalletInitData'),
             getTokenRootDetailsFromTokenWallet: nodeifyAsync(
@@ -352,6 +367,7 @@ export class NekotonController extends EventEmitter {
             updateTokenWallets: nodeifyAsync(accountController, 'updateTokenWallets'),
             logOut: nodeifyAsync(this, 'logOut'),
             estimateFees: nodeifyAsync(accountController, 'estimateFees'),
+            estimateFeeBreakdown: nodeifyAsync(accountController, 'estimateFeeBreakdown'),
             estimateConfirmationFees: nodeifyAsync(accountController, 'estimateConfirmationFees'),
             estimateDeploymentFees: nodeifyAsync(accountController, 'estimateDeploymentFees'),
             prepareTransferMessage: nodeifyAsync(accountController, 'prepareTransferMessage'),
@@ -448,8 +464,9 @@ export class NekotonController extends EventEmitter {
             return false
         }
 
+        // KDF settings of the key passwords are stored in the keystore entry, so they are restored with it
         const keystore = parsedStorage[KEYSTORE_STORAGE_KEY]
-        if (typeof keystore !== 'string' || !nt.KeyStore.verify(keystore)) {
+        if (typeof keystore !== 'string' || !(await nt.KeyStore.verify(keystore)).valid) {
             return false
         }
 
@@ -6,6 +6,8 @@ import { ContractSubscription, IContractHandler } from '../../utils/ContractSubs
 export interface ITonWalletHandler extends IContractHandler<nt.Transaction> {
     onUnconfirmedTransactionsChanged(unconfirmedTransactions: nt.MultisigPendingTransaction[]): void
 
+    onUnconfirmedUpdatesChanged(unconfirmedUpdates: nt.MultisigPendingUpdate[]): void
+
     onCustodiansChanged(custodians: string[]): void
 
     onDetailsChanged(details: nt.TonWalletDetails): void
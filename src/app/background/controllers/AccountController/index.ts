@@ -70,6 +70,9 @@ export interface AccountControllerState extends BaseState {
     accountUnconfirmedTransactions: {
         [address: string]: { [transactionId: string]: nt.MultisigPendingTransaction }
     }
+    accountUnconfirmedUpdates: {
+        [address: string]: { [updateId: string]: nt.MultisigPendingUpdate }
+    }
     accountTokenTransactions: {
         [address: string]: { [rootTokenContract: string]: nt.TokenWalletTransaction[] }
     }
@@ -98,6 +101,7 @@ const defaultState: AccountControllerState = {
     accountTransactions: {},
     accountMultisigTransactions: {},
     accountUnconfirmedTransactions: {},
+    accountUnconfirmedUpdates: {},
     accountTokenTransactions: {},
     accountPendingTransactions: {},
     accountFailedTransactions: {},
@@ -635,6 +639,24 @@ export class AccountController extends BaseController<
         }
     }
 
+    public handleKeyUpdated(entry: nt.KeyStoreEntry) {
+        this.update({
+            storedKeys: {
+                ...this.state.storedKeys,
+                [entry.publicKey]: entry,
+            },
+        })
+    }
+
+    public handleKeyRemoved(entry: nt.KeyStoreEntry) {
+        const storedKeys = { ...this.state.storedKeys }
+        delete storedKeys[entry.publicKey]
+
+        this.update({
+            storedKeys,
+        })
+    }
+
     public async removeKey({ publicKey }: KeyToRemove): Promise<nt.KeyStoreEntry | undefined> {
         const entry = await this._removeKey({ publicKey })
         const storedKeys = { ...this.state.storedKeys }
@@ -940,14 +962,23 @@ export class AccountController extends BaseController<
     }
 
     public async isPasswordCached(publicKey: string): Promise<boolean> {
-        return this.config.keyStore.isPasswordCached(publicKey)
+        return this.config.keyStore.isPasswordCached(publicKey) != null
     }
 
     public async estimateFees(
         address: string,
         params: TransferMessageToPrepare,
         executionOptions: nt.TransactionExecutionOptions
-    ) {
+    ): Promise<string> {
+        const fees = await this.estimateFeeBreakdown(address, params, executionOptions)
+        return fees.total
+    }
+
+    public async estimateFeeBreakdown(
+        address: string,
+        params: TransferMessageToPrepare,
+        executionOptions: nt.TransactionExecutionOptions
+    ): Promise<nt.FeeBreakdown> {
         const subscription = await this._tonWalletSubscriptions.get(address)
         requireTonWalletSubscription(address, subscription)
 
@@ -1009,7 +1040,8 @@ export class AccountController extends BaseController<
 
             try {
                 const signedMessage = unsignedMessage.signFake()
-                return await wallet.estimateFees(signedMessage, {})
+                const fees = await wallet.estimateFees(signedMessage, {})
+                return fees.total
             } catch (e: any) {
                 throw new NekotonRpcError(RpcErrorCode.INTERNAL, e.toString())
             } finally {
@@ -1034,9 +1066,10 @@ export class AccountController extends BaseController<
             const unsignedMessage = wallet.prepareDeploy(60)
             try {
                 const signedMessage = unsignedMessage.signFake()
-                return await wallet.estimateFees(signedMessage, {
+                const fees = await wallet.estimateFees(signedMessage, {
                     overrideBalance: '100000000000',
                 })
+                return fees.total
             } catch (e: any) {
                 console.error(e)
                 throw new NekotonRpcError(RpcErrorCode.INTERNAL, e.toString())
@@ -1059,6 +1092,19 @@ export class AccountController extends BaseController<
         })
     }
 
+    public async getMultisigPendingUpdates(address: string) {
+        const subscription = await this._tonWalletSubscriptions.get(address)
+        requireTonWalletSubscription(address, subscription)
+
+        return subscription.use(async (wallet) => {
+            try {
+                return wallet.getMultisigPendingUpdates()
+            } catch (e: any) {
+                throw new NekotonRpcError(RpcErrorCode.INTERNAL, e.toString())
+            }
+        })
+    }
+
     public async prepareTransferMessage(
         address: string,
         params: TransferMessageToPrepare,
@@ -1093,7 +1139,7 @@ export class AccountController extends BaseController<
             }
 
             try {
-                return await this.config.keyStore.sign(unsignedMessage, password)
+                return await this.signMessage(unsignedMessage, password)
             } catch (e: any) {
                 throw new NekotonRpcError(RpcErrorCode.INTERNAL, e.toString())
             } finally {
@@ -1128,7 +1174,7 @@ export class AccountController extends BaseController<
                     60
                 )
 
-                return await this.config.keyStore.sign(unsignedMessage, password)
+                return await this.signMessage(unsignedMessage, password)
             } catch (e: any) {
                 throw new NekotonRpcError(RpcErrorCode.INTERNAL, e.toString())
             } finally {
@@ -1166,7 +1212,7 @@ export class AccountController extends BaseController<
             }
 
             try {
-                return await this.config.keyStore.sign(unsignedMessage, password)
+                return await this.signMessage(unsignedMessage, password)
             } catch (e: any) {
                 throw new NekotonRpcError(RpcErrorCode.INTERNAL, e.toString())
             } finally {
@@ -1197,32 +1243,55 @@ export class AccountController extends BaseController<
         })
     }
 
-    public async signData(data: string, password: nt.KeyPassword) {
-        return this.config.keyStore.signData(data, password)
+    public async signData(data: string, password: nt.KeyPassword, origin?: string) {
+        return this.config.connectionController.use(async ({ data: { transport } }) =>
+            this.config.keyStore.signData(data, password, transport, origin)
+        )
     }
 
-    public async signDataRaw(data: string, password: nt.KeyPassword) {
-        return this.config.keyStore.signDataRaw(data, password)
+    public async signDataRaw(data: string, password: nt.KeyPassword, origin?: string) {
+        return this.config.connectionController.use(async ({ data: { transport } }) =>
+            this.config.keyStore.signDataRaw(data, password, transport, origin)
+        )
     }
 
     public async signPreparedMessage(
         unsignedMessage: nt.UnsignedMessage,
-        password: nt.KeyPassword
+        password: nt.KeyPassword,
+        origin?: string
     ) {
-        return this.config.keyStore.sign(unsignedMessage, password)
+        return this.signMessage(unsignedMessage, password, origin)
+    }
+
+    // Signature id is resolved by the keystore from the current transport
+    private async signMessage(
+        unsignedMessage: nt.UnsignedMessage,
+        password: nt.KeyPassword,
+        origin?: string
+    ) {
+        return this.config.connectionController.use(async ({ data: { transport } }) =>
+            this.config.keyStore.sign(unsignedMessage, password, transport, origin)
+        )
     }
 
     public async encryptData(
         data: string,
         recipientPublicKeys: string[],
         algorithm: nt.EncryptionAlgorithm,
-        password: nt.KeyPassword
+        password: nt.KeyPassword,
+        origin?: string
     ) {
-        return this.config.keyStore.encryptData(data, recipientPublicKeys, algorithm, password)
+        return this.config.keyStore.encryptData(
+            data,
+            recipientPublicKeys,
+            algorithm,
+            password,
+            origin
+        )
     }
 
-    public async decryptData(data: nt.EncryptedData, password: nt.KeyPassword) {
-        return this.config.keyStore.decryptData(data, password)
+    public async decryptData(data: nt.EncryptedData, password: nt.KeyPassword, origin?: string) {
+        return this.config.keyStore.decryptData(data, password, origin)
     }
 
     public async sendMessage(
@@ -1374,6 +1443,7 @@ export class AccountController extends BaseController<
                     pendingTransaction.messageHash,
                     false
                 )
+                this._controller._settleSessionKeyMessage(pendingTransaction.messageHash, false)
                 this._controller._resolveMessageRequest(
                     this._address,
                     pendingTransaction.messageHash,
@@ -1387,6 +1457,7 @@ export class AccountController extends BaseController<
                     pendingTransaction.messageHash,
                     true
                 )
+                this._controller._settleSessionKeyMessage(pendingTransaction.messageHash, true)
                 this._controller._resolveMessageRequest(
                     this._address,
                     pendingTransaction.messageHash,
@@ -1419,6 +1490,10 @@ export class AccountController extends BaseController<
                 )
             }
 
+            onUnconfirmedUpdatesChanged(unconfirmedUpdates: nt.MultisigPendingUpdate[]) {
+                this._controller._updateUnconfirmedUpdates(this._address, unconfirmedUpdates)
+            }
+
             onCustodiansChanged(custodians: string[]) {
                 this._controller._updateCustodians(this._address, custodians)
             }
@@ -1592,6 +1667,7 @@ export class AccountController extends BaseController<
             accountTokenTransactions: {},
             accountMultisigTransactions: {},
             accountUnconfirmedTransactions: {},
+            accountUnconfirmedUpdates: {},
             accountPendingTransactions: {},
             accountFailedTransactions: {},
         })
@@ -1642,6 +1718,10 @@ export class AccountController extends BaseController<
         }
     }
 
+    private _settleSessionKeyMessage(messageHash: string, delivered: boolean) {
+        this.config.keyStore.settleSessionKeyMessage(messageHash, delivered).catch(console.error)
+    }
+
     private _clearPendingTransaction(address: string, messageHash: string, sent: boolean) {
         const { accountPendingTransactions, accountFailedTransactions } = this.state
 
@@ -1885,6 +1965,28 @@ export class AccountController extends BaseController<
         })
     }
 
+    private _updateUnconfirmedUpdates(
+        address: string,
+        unconfirmedUpdates: nt.MultisigPendingUpdate[]
+    ) {
+        let { accountUnconfirmedUpdates } = this.state
+
+        const entries: { [updateId: string]: nt.MultisigPendingUpdate } = {}
+
+        unconfirmedUpdates.forEach((update) => {
+            entries[update.id] = update
+        })
+
+        accountUnconfirmedUpdates = {
+            ...accountUnconfirmedUpdates,
+            [address]: entries,
+        }
+
+        this.update({
+            accountUnconfirmedUpdates,
+        })
+    }
+
     private _updateCustodians(address: string, custodians: string[]) {
         let { accountCustodians } = this.state
         accountCustodians[address] = custodians
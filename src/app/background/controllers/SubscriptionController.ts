@@ -17,6 +17,7 @@ const DEFAULT_POLLING_INTERVAL = 10000 // 10s
 export interface SubscriptionControllerConfig extends BaseConfig {
     clock: nt.ClockWithOffset
     connectionController: ConnectionController
+    keyStore: nt.KeyStore
     notifyTab?: <T extends ProviderEvent>(
         tabId: number,
         payload: { method: T; params: RawProviderEventData<T> }
@@ -236,6 +237,7 @@ export class SubscriptionController extends BaseController<
             }
 
             onMessageExpired(pendingTransaction: nt.PendingTransaction) {
+                this._controller._settleSessionKeyMessage(pendingTransaction.messageHash, false)
                 this._enabled &&
                     this._controller
                         ._resolveMessageRequest(
@@ -247,6 +249,7 @@ export class SubscriptionController extends BaseController<
             }
 
             onMessageSent(pendingTransaction: nt.PendingTransaction, transaction: nt.Transaction) {
+                this._controller._settleSessionKeyMessage(pendingTransaction.messageHash, true)
                 this._enabled &&
                     this._controller
                         ._resolveMessageRequest(
@@ -317,6 +320,10 @@ export class SubscriptionController extends BaseController<
         await this._subscriptionsMutex.use(async () => this._tryUnsubscribe(address))
     }
 
+    private _settleSessionKeyMessage(messageHash: string, delivered: boolean) {
+        this.config.keyStore.settleSessionKeyMessage(messageHash, delivered).catch(console.error)
+    }
+
     private async _resolveMessageRequest(
         address: string,
         id: string,
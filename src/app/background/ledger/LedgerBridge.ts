@@ -16,8 +16,11 @@ type IBridgeApi = {
     'ledger-sign-message': {
         input: {
             account: number
+            // 32-byte hash of the data. Networks with a signature id are rejected before the bridge
             message: Uint8Array
             context?: nt.LedgerSignatureContext
+            // Detailed description of the message, shown by the app versions which support it
+            signingContext?: nt.LedgerSigningContext
         }
         output: {
             signature: Uint8Array
@@ -78,12 +81,14 @@ export default class LedgerBridge extends EventEmitter {
     public async signHash(
         account: number,
         message: Uint8Array,
-        context?: nt.LedgerSignatureContext
+        context?: nt.LedgerSignatureContext,
+        signingContext?: nt.LedgerSigningContext
     ) {
         const { success, payload, error } = await this._sendMessage('ledger-sign-message', {
             account,
             message,
             context,
+            signingContext,
         })
 
         if (success && payload) {
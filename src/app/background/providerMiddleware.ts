@@ -1022,7 +1022,7 @@ const signData: ProviderMethod<'signData'> = async (req, res, _next, end, ctx) =
     })
 
     try {
-        res.result = await accountController.signData(data, password)
+        res.result = await accountController.signData(data, password, origin)
         end()
     } catch (e: any) {
         throw invalidRequest(req, e.toString())
@@ -1057,7 +1057,7 @@ const signDataRaw: ProviderMethod<'signDataRaw'> = async (req, res, _next, end,
     })
 
     try {
-        res.result = await accountController.signDataRaw(data, password)
+        res.result = await accountController.signDataRaw(data, password, origin)
         end()
     } catch (e: any) {
         throw invalidRequest(req, e.toString())
@@ -1099,7 +1099,8 @@ const encryptData: ProviderMethod<'encryptData'> = async (req, res, _next, end,
                 data,
                 recipientPublicKeys,
                 algorithm,
-                password
+                password,
+                origin
             ),
         }
         end()
@@ -1147,7 +1148,7 @@ const decryptData: ProviderMethod<'decryptData'> = async (req, res, _next, end,
 
     try {
         res.result = {
-            data: await accountController.decryptData(encryptedData, password),
+            data: await accountController.decryptData(encryptedData, password, origin),
         }
         end()
     } catch (e: any) {
@@ -1218,7 +1219,9 @@ const estimateFees: ProviderMethod<'estimateFees'> = async (req, res, _next, end
 
         try {
             const signedMessage = unsignedMessage.signFake()
-            return await wallet.estimateFees(signedMessage, {})
+            // NOTE: provider API only has the total fees, breakdown is shown in the approval
+            const fees = await wallet.estimateFees(signedMessage, {})
+            return fees.total
         } catch (e: any) {
             throw invalidRequest(req, e.toString())
         } finally {
@@ -1324,7 +1327,7 @@ const sendMessageDelayed: ProviderMethod<'sendMessageDelayed'> = async (
         }
 
         try {
-            return await accountController.signPreparedMessage(unsignedMessage, password)
+            return await accountController.signPreparedMessage(unsignedMessage, password, origin)
         } catch (e: any) {
             throw invalidRequest(req, e.toString())
         } finally {
@@ -1445,7 +1448,7 @@ const sendMessage: ProviderMethod<'sendMessage'> = async (req, res, _next, end,
         }
 
         try {
-            return await accountController.signPreparedMessage(unsignedMessage, password)
+            return await accountController.signPreparedMessage(unsignedMessage, password, origin)
         } catch (e: any) {
             throw invalidRequest(req, e.toString())
         } finally {
@@ -1550,7 +1553,11 @@ const sendExternalMessage: ProviderMethod<'sendExternalMessage'> = async (
     let signedMessage: nt.SignedMessage
     try {
         unsignedMessage.refreshTimeout(clock)
-        signedMessage = await accountController.signPreparedMessage(unsignedMessage, password)
+        signedMessage = await accountController.signPreparedMessage(
+            unsignedMessage,
+            password,
+            origin
+        )
     } catch (e: any) {
         throw invalidRequest(req, e.toString())
     } finally {
@@ -1656,7 +1663,11 @@ const sendExternalMessageDelayed: ProviderMethod<'sendExternalMessageDelayed'> =
     let signedMessage: nt.SignedMessage
     try {
         unsignedMessage.refreshTimeout(clock)
-        signedMessage = await accountController.signPreparedMessage(unsignedMessage, password)
+        signedMessage = await accountController.signPreparedMessage(
+            unsignedMessage,
+            password,
+            origin
+        )
     } catch (e: any) {
         throw invalidRequest(req, e.toString())
     } finally {
@@ -18,11 +18,12 @@ export class LedgerConnection {
     async sign(
         account: number,
         message: Buffer,
-        context: nt.LedgerSignatureContext,
-        handler: nt.LedgerQueryResultHandler
+        context: nt.LedgerSignatureContext | undefined,
+        handler: nt.LedgerQueryResultHandler,
+        signingContext: nt.LedgerSigningContext | undefined
     ) {
         await this.bridge
-            .signHash(account, new Uint8Array(message), context)
+            .signHash(account, new Uint8Array(message), context, signingContext)
             .then((signature) => {
                 handler.onResult(signature)
             })
@@ -65,8 +65,11 @@ export function ExportSeed({ onBack }: Props): JSX.Element {
         try {
             await rpc
                 .exportMasterKey(prepareExportKey(accountability.currentMasterKey, password))
-                .then(({ phrase }) => {
-                    setSeedPhrase(phrase.split(' '))
+                .then((exported) => {
+                    if (!('phrase' in exported)) {
+                        throw new Error('Key has no seed phrase')
+                    }
+                    setSeedPhrase(exported.phrase.split(' '))
                     setStep(ExportSeedStep.COPY_SEED_PHRASE)
                 })
                 .catch((e: string) => {
@@ -62,7 +62,7 @@ export function ApproveSendMessage({
 
     const [localStep, setLocalStep] = React.useState(ApproveStep.MESSAGE_PREVIEW)
     const [error, setError] = React.useState<string>()
-    const [fees, setFees] = React.useState<string>()
+    const [fees, setFees] = React.useState<nt.FeeBreakdown>()
     const [selectedKey, setKey] = React.useState<nt.KeyStoreEntry | undefined>(keys[0])
     const [tokenTransaction, setTokenTransaction] = React.useState<{
         amount: string
@@ -108,7 +108,7 @@ export function ApproveSendMessage({
         }
 
         await rpc
-            .estimateFees(account.tonWallet.address, messageToPrepare, {})
+            .estimateFeeBreakdown(account.tonWallet.address, messageToPrepare, {})
             .then((fees) => setFees(fees))
             .catch(console.error)
     }
@@ -239,10 +239,18 @@ export function ApproveSendMessage({
                                 <span className="approval__spend-details-param-value approval--send-message__amount">
                                     <TonAssetIcon className="root-token-icon noselect" />
                                     {fees != null
-                                        ? `~${convertTons(fees)} ${NATIVE_CURRENCY}`
+                                        ? `~${convertTons(fees.total)} ${NATIVE_CURRENCY}`
                                         : intl.formatMessage({ id: 'CALCULATING_HINT' })}
                                 </span>
                             )}
+                            {isDeployed && fees?.aborted && (
+                                <div className="check-seed__content-error">
+                                    {intl.formatMessage(
+                                        { id: 'APPROVE_SEND_MESSAGE_TRANSACTION_ABORTED' },
+                                        { exitCode: fees.exitCode ?? '-' }
+                                    )}
+                                </div>
+                            )}
                             {!isDeployed && (
                                 <div className="check-seed__content-error">
                                     {intl.formatMessage({
@@ -315,7 +323,7 @@ export function ApproveSendMessage({
                               }
                     }
                     recipient={recipient}
-                    fees={fees}
+                    fees={fees?.total}
                     error={error}
                     disabled={inProcess}
                     showHeading={false}
@@ -85,6 +85,7 @@ export default {
     APPROVE_SEND_MESSAGE_TERM_BLOCKCHAIN_FEE: 'Blockchain fee',
     APPROVE_SEND_MESSAGE_INSUFFICIENT_FUNDS: 'Insufficient funds',
     APPROVE_SEND_MESSAGE_OPERATION_NOT_POSSIBLE: 'Operation is not possible. Wallet is not deployed.',
+    APPROVE_SEND_MESSAGE_TRANSACTION_ABORTED: 'Transaction is expected to fail (exit code: {exitCode})',
     APPROVE_SEND_MESSAGE_PASSWORD_FIELD_PLACEHOLDER: 'Password...',
     APPROVE_SEND_MESSAGE_PASSWORD_FIELD_HINT: 'Enter password for seed: {name}',
     APPROVE_SEND_MESSAGE_APPROVE_WITH_LEDGER_HINT: 'Please confirm the transaction with your Ledger',
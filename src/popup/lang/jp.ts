@@ -84,6 +84,7 @@ export default {
     APPROVE_SEND_MESSAGE_TERM_BLOCKCHAIN_FEE: 'ブロックチェーン手数料',
     APPROVE_SEND_MESSAGE_INSUFFICIENT_FUNDS: '資金不足',
     APPROVE_SEND_MESSAGE_OPERATION_NOT_POSSIBLE: '操作できません。ウォレットがデプロイされていません。',
+    APPROVE_SEND_MESSAGE_TRANSACTION_ABORTED: 'トランザクションは失敗する見込みです（終了コード: {exitCode}）',
     APPROVE_SEND_MESSAGE_PASSWORD_FIELD_PLACEHOLDER: 'パスワード...',
     APPROVE_SEND_MESSAGE_PASSWORD_FIELD_HINT: 'シード{name}のパスワードを入力してください',
     APPROVE_SEND_MESSAGE_APPROVE_WITH_LEDGER_HINT: 'Ledgerでトランザクションを確認してください',
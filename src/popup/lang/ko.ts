@@ -84,6 +84,7 @@ export default {
     APPROVE_SEND_MESSAGE_TERM_BLOCKCHAIN_FEE: '블록체인 수수료',
     APPROVE_SEND_MESSAGE_INSUFFICIENT_FUNDS: '잔고 부족',
     APPROVE_SEND_MESSAGE_OPERATION_NOT_POSSIBLE: '지갑이 활성화되어 있지 않아 운영이 불가능합니다',
+    APPROVE_SEND_MESSAGE_TRANSACTION_ABORTED: '트랜잭션이 실패할 것으로 예상됩니다 (종료 코드: {exitCode})',
     APPROVE_SEND_MESSAGE_PASSWORD_FIELD_PLACEHOLDER: '비밀번호',
     APPROVE_SEND_MESSAGE_PASSWORD_FIELD_HINT: '시드 비밀번호 입력: {name}',
     APPROVE_SEND_MESSAGE_APPROVE_WITH_LEDGER_HINT: '레저(Ledger)로 거래를 확인하십시오',